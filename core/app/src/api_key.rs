@@ -0,0 +1,144 @@
+use std::{fmt, str::FromStr};
+
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use axum::http::StatusCode;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use common::error::AppError;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    local_db::LocalDB,
+    roles::Role,
+    session::hash_token,
+    user::User,
+};
+
+/// Prefix of API keys, so they are easy to tell apart from other secrets, such as in logs.
+const API_KEY_PREFIX: &str = "bsk_";
+
+/// What requests authenticated with an API key may do.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ApiKeyScope {
+    /// Browse data, run graphs and export, whatever the user's role. Nothing is changed.
+    ReadOnly,
+
+    /// Anything the user's role allows.
+    #[default]
+    Full,
+}
+
+impl ApiKeyScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::ReadOnly => "read_only",
+            ApiKeyScope::Full => "full",
+        }
+    }
+
+    /// The highest role users may act with under this scope.
+    pub fn max_role(&self) -> Option<Role> {
+        match self {
+            ApiKeyScope::ReadOnly => Some(Role::Analyst),
+            ApiKeyScope::Full => None,
+        }
+    }
+}
+
+impl fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read_only" => Ok(ApiKeyScope::ReadOnly),
+            "full" => Ok(ApiKeyScope::Full),
+            _ => Err(format!("Unknown scope '{s}'")),
+        }
+    }
+}
+
+/// Generate a new API key, and return it with its hash. Only the hash should be kept.
+pub(crate) fn generate_api_key() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+
+    let key = format!("{API_KEY_PREFIX}{}", URL_SAFE_NO_PAD.encode(bytes));
+    let hash = hash_token(&key);
+
+    (key, hash)
+}
+
+/// The user `key` belongs to, acting within the scope of the key.
+pub(crate) fn authenticate(db: &LocalDB, key: &str) -> Result<User, AppError> {
+    let invalid = || {
+        AppError::HttpError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or expired API key".to_string(),
+        )
+    };
+
+    if !key.starts_with(API_KEY_PREFIX) {
+        return Err(invalid());
+    }
+
+    let key = db
+        .use_api_key(&hash_token(key), Utc::now().timestamp())?
+        .ok_or_else(invalid)?;
+
+    Ok(User {
+        id: key.user_id,
+        is_guest: false,
+        max_role: key.scope.max_role(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::{
+        local_db::{ApiKey, LocalDB},
+        roles::Role,
+    };
+
+    use super::{authenticate, generate_api_key, ApiKeyScope};
+
+    #[test]
+    fn test_authenticate() {
+        let db = LocalDB::memory().unwrap();
+        db.setup().unwrap();
+
+        let now = Utc::now().timestamp();
+        let create = |id: &str, scope, expires_at| {
+            let (key, hash) = generate_api_key();
+            let details = ApiKey {
+                id: id.to_string(),
+                user_id: "ada".to_string(),
+                name: id.to_string(),
+                scope,
+                expires_at,
+                created_at: now,
+                last_used_at: None,
+            };
+            db.create_api_key(&details, &hash).unwrap();
+            key
+        };
+
+        let read_only = create("ci", ApiKeyScope::ReadOnly, Some(now + 60));
+        let expired = create("old", ApiKeyScope::Full, Some(now - 60));
+
+        let user = authenticate(&db, &read_only).unwrap();
+        assert_eq!(user.id, "ada");
+        assert_eq!(user.effective_role(Some(Role::Admin)), Some(Role::Analyst));
+
+        assert!(authenticate(&db, &expired).is_err());
+        assert!(authenticate(&db, "bsk_unknown").is_err());
+    }
+}
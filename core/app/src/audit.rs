@@ -0,0 +1,230 @@
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use base::{SharedDB, SharedTable};
+use chrono::Utc;
+use common::{
+    data::{
+        row::ColumnValue,
+        table::{TableQueryOpts, UpdateTableData},
+    },
+    error::AppError,
+    query::filter::{Filter, FilterCombinator, FilterExpression},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::local_db::AuditEntry;
+
+/// Most rows kept in a [`Snapshot`]. Changes affecting more rows are only partly captured.
+const MAX_SNAPSHOT_ROWS: usize = 1000;
+
+/// Rows of a table, as they were before or after a change.
+pub(crate) type Snapshot = Vec<HashMap<String, ColumnValue>>;
+
+/// A data-modifying operation recorded in the audit log.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AuditOperation {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl AuditOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOperation::Insert => "insert",
+            AuditOperation::Update => "update",
+            AuditOperation::Delete => "delete",
+        }
+    }
+}
+
+impl fmt::Display for AuditOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditOperation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "insert" => Ok(AuditOperation::Insert),
+            "update" => Ok(AuditOperation::Update),
+            "delete" => Ok(AuditOperation::Delete),
+            _ => Err(format!("Unknown operation '{s}'")),
+        }
+    }
+}
+
+/// A change made to the data of a table, as the functions of this module make and describe it.
+///
+/// Affected rows are identified by the values of a key column: the table's configured primary
+/// key column if any, or else the column the operation matched rows by. Rows are only captured
+/// before and after the change when a primary key column is configured, since they can't be
+/// found again otherwise.
+pub(crate) struct Change {
+    pub operation: AuditOperation,
+    pub key_column: Option<String>,
+    pub keys: Vec<String>,
+    pub before: Option<Snapshot>,
+    pub after: Option<Snapshot>,
+}
+
+impl Change {
+    /// The audit log entry of the change, made now by the user.
+    pub fn into_entry(self, user_id: &str, conn_id: &Uuid, table: &str) -> AuditEntry {
+        AuditEntry {
+            id: 0,
+            user_id: user_id.to_string(),
+            conn_id: *conn_id,
+            table: table.to_string(),
+            operation: self.operation,
+            key_column: self.key_column,
+            keys: self.keys,
+            before: self.before,
+            after: self.after,
+            created_at: Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Insert `input` into the table with primary key column `pk`.
+pub(crate) fn insert(
+    table: &SharedTable,
+    pk: Option<String>,
+    input: HashMap<String, String>,
+) -> Result<Change, AppError> {
+    let keys = pk
+        .as_ref()
+        .and_then(|pk| input.get(pk))
+        .cloned()
+        .into_iter()
+        .collect();
+
+    // The inserted row may not have a key until it's written, so it's captured as given.
+    let after = pk.is_some().then(|| {
+        vec![input
+            .iter()
+            .map(|(col, value)| (col.clone(), ColumnValue::from(value.as_str())))
+            .collect()]
+    });
+
+    table.insert_data(input)?;
+
+    Ok(Change {
+        operation: AuditOperation::Insert,
+        key_column: pk,
+        keys,
+        before: None,
+        after,
+    })
+}
+
+/// Apply `input` to the table with primary key column `pk`.
+pub(crate) fn update(
+    table: &SharedTable,
+    db: &SharedDB,
+    pk: Option<String>,
+    input: UpdateTableData,
+) -> Result<Change, AppError> {
+    let Some(pk) = pk else {
+        let change = Change {
+            operation: AuditOperation::Update,
+            key_column: Some(input.unique_key.clone()),
+            keys: input.unique_values.clone(),
+            before: None,
+            after: None,
+        };
+
+        table.update_data(input)?;
+        return Ok(change);
+    };
+
+    let before = snapshot(table, db, &input.unique_key, input.unique_values.clone())?;
+    let keys = keys_of(&before, &pk);
+
+    table.update_data(input)?;
+
+    // Rows are found again by primary key, in case the update changed the unique key.
+    let after = snapshot(table, db, &pk, keys.clone())?;
+
+    Ok(Change {
+        operation: AuditOperation::Update,
+        key_column: Some(pk),
+        keys,
+        before: Some(before),
+        after: Some(after),
+    })
+}
+
+/// Delete rows whose `col` is `value` from the table with primary key column `pk`.
+pub(crate) fn delete(
+    table: &SharedTable,
+    db: &SharedDB,
+    pk: Option<String>,
+    col: String,
+    value: String,
+) -> Result<Change, AppError> {
+    let change = match pk {
+        Some(pk) => {
+            let before = snapshot(table, db, &col, vec![value.clone()])?;
+            Change {
+                operation: AuditOperation::Delete,
+                keys: keys_of(&before, &pk),
+                key_column: Some(pk),
+                before: Some(before),
+                after: None,
+            }
+        }
+        None => Change {
+            operation: AuditOperation::Delete,
+            key_column: Some(col.clone()),
+            keys: vec![value.clone()],
+            before: None,
+            after: None,
+        },
+    };
+
+    table.delete_data(col, value)?;
+    Ok(change)
+}
+
+/// Rows of the table whose `column` is one of `values`.
+fn snapshot(
+    table: &SharedTable,
+    db: &SharedDB,
+    column: &str,
+    values: Vec<String>,
+) -> Result<Snapshot, AppError> {
+    if values.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let opts = TableQueryOpts {
+        table: table.name().to_string(),
+        offset: 0,
+        row_count: MAX_SNAPSHOT_ROWS,
+        filters: Some(vec![Filter {
+            combinator: FilterCombinator::BASE,
+            column: column.to_string(),
+            expression: FilterExpression::Includes(values),
+            table: None,
+        }
+        .into()]),
+        columns: None,
+        order_by: None,
+        search_opts: None,
+    };
+
+    table.query_data(opts, db)
+}
+
+fn keys_of(rows: &Snapshot, column: &str) -> Vec<String> {
+    rows.iter()
+        .filter_map(|row| row.get(column))
+        .map(ToString::to_string)
+        .collect()
+}
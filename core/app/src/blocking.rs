@@ -0,0 +1,179 @@
+use std::{sync::Arc, time::Duration};
+
+use base::connector::RowSink;
+use common::error::AppError;
+use tokio::{
+    runtime::Handle,
+    sync::{mpsc, Semaphore},
+    time::timeout,
+};
+
+use crate::utils::get_env;
+
+/// Number of blocking tasks allowed to run at once, when `BASABLE_BLOCKING_TASKS` is not set.
+const DEFAULT_BLOCKING_TASKS: usize = 16;
+
+/// Number of items a task started with [`BlockingPool::stream`] may produce ahead of its receiver.
+const STREAM_BUFFER: usize = 256;
+
+/// How long a task started with [`BlockingPool::stream`] waits for its receiver to make room,
+/// before it's stopped.
+const STREAM_SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs blocking work, such as database queries, off the async runtime.
+///
+/// Backend connectors are synchronous, so calling them from a handler would block a runtime
+/// worker and stall every other request on it. Tasks started with [`BlockingPool::run`] are
+/// moved to Tokio's blocking threads instead. At most `size` of them run at once, others wait
+/// for their turn without holding a thread.
+#[derive(Clone)]
+pub(crate) struct BlockingPool {
+    permits: Arc<Semaphore>,
+}
+
+impl BlockingPool {
+    pub fn new(size: usize) -> Self {
+        BlockingPool {
+            permits: Arc::new(Semaphore::new(size)),
+        }
+    }
+
+    /// Create a [`BlockingPool`] sized by the `BASABLE_BLOCKING_TASKS` environment variable.
+    pub fn from_env() -> Result<Self, AppError> {
+        let size = match get_env("BASABLE_BLOCKING_TASKS") {
+            Ok(size) => size
+                .parse::<usize>()
+                .ok()
+                .filter(|size| *size > 0)
+                .ok_or_else(|| {
+                    AppError::InitError(format!("Invalid BASABLE_BLOCKING_TASKS '{size}'"))
+                })?,
+            Err(_) => DEFAULT_BLOCKING_TASKS,
+        };
+
+        Ok(BlockingPool::new(size))
+    }
+
+    /// Run `task` on a blocking thread once the pool has room for it, and wait for its result.
+    pub async fn run<F, R>(&self, task: F) -> Result<R, AppError>
+    where
+        F: FnOnce() -> Result<R, AppError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        // The permit moves with the task, so it's only released when the task is done, even if
+        // the request is dropped in the meantime.
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            task()
+        })
+        .await
+        .map_err(|err| AppError::ServerError(err.to_string()))?
+    }
+
+    /// Run `task` on a blocking thread once the pool has room for it, without waiting for it to
+    /// finish. Items `task` hands to its sink arrive through the returned channel, followed by
+    /// the task's error if it fails.
+    ///
+    /// The sink waits while the channel is full, and returns an error once the receiver is
+    /// dropped, so `task` can stop early. It also gives up after [`STREAM_SEND_TIMEOUT`], so a
+    /// receiver that stops reading, such as a slow client, doesn't hold the pool's room and
+    /// `task`'s resources for longer.
+    pub async fn stream<F, T>(&self, task: F) -> Result<mpsc::Receiver<Result<T, AppError>>, AppError>
+    where
+        F: FnOnce(&mut RowSink<'_, T>) -> Result<(), AppError> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.stream_with_timeout(task, STREAM_SEND_TIMEOUT).await
+    }
+
+    async fn stream_with_timeout<F, T>(
+        &self,
+        task: F,
+        send_timeout: Duration,
+    ) -> Result<mpsc::Receiver<Result<T, AppError>>, AppError>
+    where
+        F: FnOnce(&mut RowSink<'_, T>) -> Result<(), AppError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = self
+            .permits
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+        let runtime = Handle::current();
+
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+
+            let result = task(&mut |item| {
+                runtime
+                    .block_on(timeout(send_timeout, sender.send(Ok(item))))
+                    .map_err(|_| AppError::ServerError("Stream receiver stopped reading".to_string()))?
+                    .map_err(|_| AppError::ServerError("Stream receiver dropped".to_string()))
+            });
+
+            // The receiver may be gone or not reading anymore, so the error is only sent if
+            // there's room for it.
+            if let Err(err) = result {
+                let _ = sender.try_send(Err(err));
+            }
+        });
+
+        Ok(receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use super::{BlockingPool, STREAM_BUFFER};
+
+    #[tokio::test]
+    async fn test_stream_timeout() {
+        let pool = BlockingPool::new(1);
+        let sent = Arc::new(AtomicUsize::new(0));
+
+        let counter = sent.clone();
+        let receiver = pool
+            .stream_with_timeout(
+                move |sink| {
+                    for item in 0..STREAM_BUFFER * 2 {
+                        sink(item)?;
+                        counter.fetch_add(1, Ordering::SeqCst);
+                    }
+                    Ok(())
+                },
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap();
+
+        // Nothing is read, so the task gives up once the channel is full, and the pool has
+        // room again.
+        let run = pool.run(|| Ok(()));
+        tokio::time::timeout(Duration::from_secs(5), run)
+            .await
+            .expect("stalled stream kept its permit")
+            .unwrap();
+
+        assert_eq!(sent.load(Ordering::SeqCst), STREAM_BUFFER);
+        drop(receiver);
+    }
+}
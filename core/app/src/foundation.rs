@@ -1,17 +1,7 @@
-use std::str::FromStr;
-use std::sync::Arc;
-
-use axum::http::StatusCode;
-use base::config::{ConfigRaw, DatabaseType, SourceType};
-use base::connector::Connector;
-use base::db::DB;
-use base::mysql_plugin::connector::MysqlConnector;
-use base::mysql_plugin::db::MySqlDB;
 use base::SharedDB;
-use common::error::AppError;
 use uuid::Uuid;
 
-use crate::user::{create_jwt, JwtSession, User};
+use crate::user::User;
 
 #[derive(Default)]
 pub(crate) struct Basable {
@@ -19,40 +9,12 @@ pub(crate) struct Basable {
 }
 
 impl Basable {
-    /// Creates a new thread-safe instance of [`SharedDB`] as required by the [`Config`] parameter.
-    ///
-    /// The `auth_session` param should be set to `true` if current app [`User`] is logged.
-    pub(crate) fn create_connection(
-        config: &ConfigRaw,
-        user_id: String,
-    ) -> Result<SharedDB, AppError> {
-        let mut db = match config.get_source()? {
-            SourceType::Database(db) => match db {
-                DatabaseType::Mysql => {
-                    let conn = MysqlConnector::new(config.clone())?;
-                    let db = MySqlDB::new(Arc::new(conn), user_id);
-                    Ok(db)
-                }
-                _ => Err(AppError::not_implemented()),
-            },
-            _ => Err(AppError::not_implemented()),
-        }?;
-
-        let conn = db.connector().clone();
-        db.load_tables(conn)?;
-
-        Ok(Arc::new(db))
-    }
-
-    /// Creates a new guest user using the request `SocketAddr`
-    pub(crate) fn create_guest_user(req_ip: &str) -> Result<JwtSession, AppError> {
-        let user = User {
-            id: req_ip.to_owned(),
+    /// Creates a new guest user. Each guest gets its own id, so guests never share connections.
+    pub(crate) fn create_guest_user() -> User {
+        User {
+            id: Uuid::new_v4().to_string(),
             ..Default::default()
-        };
-
-        let session_id = create_jwt(user)?; // jwt encode the ip
-        Ok(session_id)
+        }
     }
 
     /// Add connection.
@@ -60,22 +22,20 @@ impl Basable {
         self.connections.push(db.clone());
     }
 
-    pub fn get_connection(&self, id: &str, user_id: &str) -> Result<SharedDB, AppError> {
+    /// Remove the open connection with the given id, if any. Queries already running on it are
+    /// allowed to finish.
+    pub(crate) fn remove_connection(&mut self, id: &Uuid) {
+        self.connections.retain(|c| c.id() != id);
+    }
 
-        match Uuid::from_str(id) {
-            Ok(id) => {
-                let conn = self
-                    .connections
-                    .iter()
-                    .find(|c| *c.id() == id && c.user_id() == user_id)
-                    .map(|c| c.clone());
+    /// Remove every open connection of the user.
+    pub(crate) fn remove_user_connections(&mut self, user_id: &str) {
+        self.connections.retain(|c| c.user_id() != user_id);
+    }
 
-                match conn {
-                    Some(conn) => Ok(conn),
-                    None => Err(AppError::HttpError(StatusCode::NOT_FOUND, "Connection instance not found".to_string()))
-                }
-            }
-            Err(err) => Err(AppError::ServerError(err.to_string()))
-        }
+    /// Get the open connection with the given id. Whether a user may use it is up to their
+    /// [`Role`](crate::roles::Role) on it.
+    pub fn get_connection(&self, id: &Uuid) -> Option<SharedDB> {
+        self.connections.iter().find(|c| c.id() == id).cloned()
     }
 }
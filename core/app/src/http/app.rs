@@ -56,6 +56,7 @@ pub fn app() -> Result<BasableHttpService, AppError> {
 
     let state = AppState::create()?;
     state.local_db.setup()?;
+    state.vault.seal_saved_passwords()?;
 
     let routes = core_routes();
     let static_files_service =
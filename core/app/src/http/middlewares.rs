@@ -1,39 +1,45 @@
+use std::{collections::HashMap, marker::PhantomData, str::FromStr};
+
 use axum::{
     async_trait,
-    extract::{rejection::PathRejection, FromRef, FromRequestParts, Path},
+    extract::{rejection::PathRejection, FromRef, FromRequestParts, Path, Query},
     http::{header::AUTHORIZATION, request::Parts, StatusCode},
     RequestPartsExt,
 };
 use base::{SharedDB, SharedTable};
+use uuid::Uuid;
+
+use crate::{
+    api_key::authenticate,
+    roles::RequiredRole,
+    state::AppState,
+    user::{decode_jwt, Claims, User},
+    AppError,
+};
 
-use crate::{state::AppState, user::{decode_jwt, User}, AppError};
-
-/// Extracts information about the current [`User`] by inspecting the Authorization
-/// header. If Authorization is not provided, it checks for `B-Session-Id`, which should
-/// be provided for guest users. If none of this is found, the `User` is `None`.
-pub(crate) struct AuthExtractor(pub User);
+/// Extracts the [`Claims`] of the access token the request is authenticated with, from the
+/// Authorization header. If Authorization is not provided, it checks for `Session-Id`, which
+/// should be provided for guest users. Tokens of ended sessions are rejected.
+pub(crate) struct ClaimsExtractor(pub Claims);
 
 #[async_trait]
-impl<S> FromRequestParts<S> for AuthExtractor
+impl<S> FromRequestParts<S> for ClaimsExtractor
 where
     AppState: FromRef<S>,
     S: Send + Sync,
 {
     type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let mut auth_header = parts.headers.get(AUTHORIZATION);
 
         // If Authorization header does not exist, use session-id to retrieve guest user.
-        if let None = auth_header {
+        if auth_header.is_none() {
             auth_header = parts.headers.get("session-id");
         }
 
-        match auth_header {
-            Some(hv) => match decode_jwt(hv) {
-                Ok(user) => Ok(AuthExtractor(user)),
-                Err(e) => Err(e),
-            },
+        let claims = match auth_header {
+            Some(hv) => decode_jwt(hv)?,
             None => {
                 let err = AppError::HttpError(
                     StatusCode::UNAUTHORIZED,
@@ -41,7 +47,57 @@ where
                 );
                 return Err(err);
             }
+        };
+
+        let state = extract_app_state(parts, state).await;
+        if state.sessions.is_revoked(&claims)? {
+            return Err(AppError::HttpError(
+                StatusCode::UNAUTHORIZED,
+                "Session has ended.".to_string(),
+            ));
         }
+
+        Ok(ClaimsExtractor(claims))
+    }
+}
+
+/// Extracts information about the current [`User`], from the API key in the `X-Api-Key` header
+/// if set, or else as [`ClaimsExtractor`] does. The user is kept in the request's extensions, so
+/// a request is only authenticated once, however many extractors need its user.
+pub(crate) struct AuthExtractor(pub User);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthExtractor
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(user) = parts.extensions.get::<User>() {
+            return Ok(AuthExtractor(user.clone()));
+        }
+
+        let user = match parts.headers.get("x-api-key") {
+            Some(key) => {
+                let key = key
+                    .to_str()
+                    .map_err(|err| AppError::HttpError(StatusCode::UNAUTHORIZED, err.to_string()))?
+                    .to_string();
+
+                let state = extract_app_state(parts, state).await;
+                authenticate(&state.local_db, &key)?
+            }
+            None => {
+                let ClaimsExtractor(claims) =
+                    ClaimsExtractor::from_request_parts(parts, state).await?;
+                claims.user()
+            }
+        };
+
+        parts.extensions.insert(user.clone());
+        Ok(AuthExtractor(user))
     }
 }
 
@@ -65,11 +121,8 @@ where
                 let conn_id = header.to_str().map_err(|err| {
                     AppError::HttpError(StatusCode::UNAUTHORIZED, err.to_string())
                 })?;
-                let bsbl = state.instance.lock().map_err(|err| {
-                    AppError::HttpError(StatusCode::UNAUTHORIZED, err.to_string())
-                })?;
-                let db = bsbl.get_connection(conn_id, &user.id)?;
-                std::mem::drop(bsbl); // release Mutex lock
+                let db = state.connection(conn_id, &user.id).await?;
+                state.presence.touch(db.id(), &user.id);
 
                 Ok(DbExtractor(db))
             }
@@ -116,6 +169,78 @@ where
     }
 }
 
+/// Rejects requests of users whose role on the connection is below `R`. The role on the table
+/// is used instead, when the route has one in its path, or in its `table` query parameter.
+/// Graphs joining the table in their `foreign_table` query parameter require the role on that
+/// table too. Holds the id of the connection.
+pub(crate) struct RequireRole<R: RequiredRole>(pub Uuid, pub PhantomData<R>);
+
+#[async_trait]
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+    R: RequiredRole,
+{
+    type Rejection = AppError;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let AuthExtractor(user) = AuthExtractor::from_request_parts(parts, state).await?;
+        let state = extract_app_state(parts, state).await;
+
+        let not_found = || {
+            AppError::HttpError(
+                StatusCode::NOT_FOUND,
+                "Connection instance not found".to_string(),
+            )
+        };
+
+        let conn_id = parts
+            .headers
+            .get("connection-id")
+            .ok_or_else(|| {
+                AppError::HttpError(
+                    StatusCode::PRECONDITION_REQUIRED,
+                    "Connection Id not provided".to_string(),
+                )
+            })?
+            .to_str()
+            .ok()
+            .and_then(|id| Uuid::from_str(id).ok())
+            .ok_or_else(not_found)?;
+
+        let mut params = Query::<HashMap<String, String>>::from_request_parts(parts, &state)
+            .await
+            .map(|Query(params)| params)
+            .unwrap_or_default();
+
+        let table = match Path::<String>::from_request_parts(parts, &state).await {
+            Ok(Path(table)) => Some(table),
+            Err(_) => params.remove("table"),
+        };
+        let foreign_table = params.remove("foreign_table");
+
+        let mut tables = vec![table.as_deref()];
+        tables.extend(foreign_table.as_deref().map(Some));
+
+        for table in tables {
+            let role = state.local_db.role(&conn_id, &user.id, table)?;
+            match user.effective_role(role) {
+                Some(role) if role >= R::ROLE => {}
+                Some(role) => {
+                    let scope = table.map_or(String::new(), |table| format!(" on '{table}'"));
+                    return Err(AppError::HttpError(
+                        StatusCode::FORBIDDEN,
+                        format!("This requires the {} role{scope}, but you are {role}", R::ROLE),
+                    ));
+                }
+                None => return Err(not_found()),
+            }
+        }
+
+        Ok(RequireRole(conn_id, PhantomData))
+    }
+}
+
 /// Extract app state and get basable instance
 /// https://docs.rs/axum/0.6.4/axum/extract/struct.State.html#for-library-authors
 async fn extract_app_state<S>(parts: &mut Parts, state: &S) -> AppState
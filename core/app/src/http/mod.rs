@@ -1,3 +1,6 @@
 pub(crate) mod middlewares;
 pub(crate) mod app;
 pub(super) mod routes;
+
+#[cfg(test)]
+pub(crate) mod test_app;
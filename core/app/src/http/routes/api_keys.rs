@@ -0,0 +1,169 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use axum_macros::debug_handler;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    api_key::{generate_api_key, ApiKeyScope},
+    http::middlewares::ClaimsExtractor,
+    local_db::ApiKey,
+    state::AppState,
+    user::User,
+    AppError,
+};
+
+#[derive(Deserialize)]
+pub(crate) struct CreateApiKey {
+    name: String,
+
+    #[serde(default)]
+    scope: ApiKeyScope,
+
+    /// Unix timestamp the key expires at. Keys don't expire if not set.
+    expires_at: Option<i64>,
+}
+
+/// A new API key. Only its hash is kept, so `key` can't be shown again.
+#[derive(Serialize)]
+pub(crate) struct CreatedApiKey {
+    key: String,
+
+    #[serde(flatten)]
+    details: ApiKey,
+}
+
+/// API keys are managed with session tokens only, so a key can't be used to make more keys.
+/// Guests don't get keys, as their sessions are not meant to last.
+fn registered_user(claims: &ClaimsExtractor) -> Result<User, AppError> {
+    let user = claims.0.user();
+
+    if user.is_guest {
+        return Err(AppError::HttpError(
+            StatusCode::FORBIDDEN,
+            "Only registered users can manage API keys".to_string(),
+        ));
+    }
+
+    Ok(user)
+}
+
+#[debug_handler]
+pub(crate) async fn create_api_key(
+    State(state): State<AppState>,
+    claims: ClaimsExtractor,
+    Json(opts): Json<CreateApiKey>,
+) -> Result<Json<CreatedApiKey>, AppError> {
+    let user = registered_user(&claims)?;
+    let now = Utc::now().timestamp();
+
+    if opts.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            "API keys must expire in the future".to_string(),
+        ));
+    }
+
+    let (key, key_hash) = generate_api_key();
+    let details = ApiKey {
+        id: Uuid::new_v4().to_string(),
+        user_id: user.id,
+        name: opts.name,
+        scope: opts.scope,
+        expires_at: opts.expires_at,
+        created_at: now,
+        last_used_at: None,
+    };
+    state.local_db.create_api_key(&details, &key_hash)?;
+
+    Ok(Json(CreatedApiKey { key, details }))
+}
+
+#[debug_handler]
+pub(crate) async fn list_api_keys(
+    State(state): State<AppState>,
+    claims: ClaimsExtractor,
+) -> Result<Json<Vec<ApiKey>>, AppError> {
+    let user = registered_user(&claims)?;
+
+    Ok(Json(state.local_db.list_api_keys(&user.id)?))
+}
+
+#[debug_handler]
+pub(crate) async fn revoke_api_key(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+    claims: ClaimsExtractor,
+) -> Result<String, AppError> {
+    let user = registered_user(&claims)?;
+
+    if !state.local_db.revoke_api_key(&id, &user.id)? {
+        return Err(AppError::HttpError(
+            StatusCode::NOT_FOUND,
+            "API key not found".to_string(),
+        ));
+    }
+
+    Ok("Operation successful".to_string())
+}
+
+/// Routes for managing the API keys of a user.
+pub(super) fn api_key_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_api_keys).post(create_api_key))
+        .route("/:id", delete(revoke_api_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::http::test_app::{bearer, TestApp};
+
+    #[tokio::test]
+    async fn test_api_keys() {
+        let app = TestApp::new();
+        let auth = bearer(&app.register("ada@example.com").await);
+        let conn_id = app.connect(&auth).await;
+
+        let opts = json!({ "name": "reports", "scope": "read_only" });
+        let resp = app
+            .request(Method::POST, "/api-keys", &[("authorization", &auth)], Some(opts))
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+        let created = resp.json();
+        let key = created["key"].as_str().unwrap();
+        let headers = [("x-api-key", key), ("connection-id", conn_id.as_str())];
+
+        let uri = "/graphs/category?table=orders&analysis=simple&target_column=customer";
+        let resp = app.request(Method::GET, uri, &headers, None).await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        // Read-only keys can't change data, whatever the user's role.
+        let row = json!({ "customer": "eve", "total": "5" });
+        let resp = app
+            .request(Method::POST, "/tables/data/orders", &headers, Some(row))
+            .await;
+        assert_eq!(resp.status, StatusCode::FORBIDDEN);
+
+        // Keys can't manage keys.
+        let resp = app.request(Method::GET, "/api-keys", &headers, None).await;
+        assert_eq!(resp.status, StatusCode::UNAUTHORIZED);
+
+        let uri = format!("/api-keys/{}", created["id"].as_str().unwrap());
+        let resp = app
+            .request(Method::DELETE, &uri, &[("authorization", &auth)], None)
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        let uri = "/graphs/category?table=orders&analysis=simple&target_column=customer";
+        let resp = app.request(Method::GET, uri, &headers, None).await;
+        assert_eq!(resp.status, StatusCode::UNAUTHORIZED, "{}", resp.body);
+    }
+}
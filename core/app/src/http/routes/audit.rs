@@ -0,0 +1,32 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use axum_macros::debug_handler;
+
+use crate::{
+    http::middlewares::{AuthExtractor, RequireRole},
+    local_db::{AuditEntry, AuditFilter},
+    roles::Admin,
+    state::AppState,
+    AppError,
+};
+
+/// List changes made to the data of the connection, most recent first.
+#[debug_handler]
+pub(crate) async fn list_audit_entries(
+    Query(filter): Query<AuditFilter>,
+    AuthExtractor(_): AuthExtractor,
+    RequireRole(conn_id, _): RequireRole<Admin>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<AuditEntry>>, AppError> {
+    let entries = state.local_db.list_audit_entries(&conn_id, &filter)?;
+
+    Ok(Json(entries))
+}
+
+/// Routes for the audit log of connections.
+pub(super) fn audit_routes() -> Router<AppState> {
+    Router::new().route("/", get(list_audit_entries))
+}
@@ -1,26 +1,336 @@
-use std::net::SocketAddr;
+use std::sync::OnceLock;
 
-use axum::{
-    extract::{ConnectInfo, State},
-    routing::post,
-    Json, Router,
-};
+use axum::{extract::State, http::StatusCode, routing::post, Json, Router};
 use axum_macros::debug_handler;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    foundation::Basable,
+    http::middlewares::{AuthExtractor, ClaimsExtractor},
+    local_db::Account,
+    state::AppState,
+    user::{hash_password, verify_password, JwtSession, User},
+    AppError,
+};
+
+/// Passwords shorter than this are rejected at registration.
+const MIN_PASSWORD_LEN: usize = 8;
+
+#[derive(Deserialize)]
+pub(crate) struct Credentials {
+    email: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RefreshOpts {
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct LogoutOpts {
+    refresh_token: Option<String>,
+}
+
+#[debug_handler]
+async fn create_guest_user(State(state): State<AppState>) -> Result<Json<JwtSession>, AppError> {
+    let user = Basable::create_guest_user();
+    let session = state.sessions.start(&user)?;
+
+    Ok(Json(session))
+}
+
+/// Register a new user, and start a session for them. Connections of the guest making the
+/// request, if any, are given to the new user.
+#[debug_handler]
+async fn register(
+    State(state): State<AppState>,
+    guest: Option<AuthExtractor>,
+    Json(creds): Json<Credentials>,
+) -> Result<Json<JwtSession>, AppError> {
+    let email = creds.email.trim().to_string();
+
+    let is_email = email
+        .split_once('@')
+        .is_some_and(|(name, domain)| !name.is_empty() && !domain.is_empty());
+    if !is_email {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            "Invalid email address".to_string(),
+        ));
+    }
+
+    if creds.password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            format!("Passwords must be at least {MIN_PASSWORD_LEN} characters long"),
+        ));
+    }
+
+    // Hashing is slow by design, so it's kept off the async runtime.
+    let password = creds.password;
+    let password_hash = state.blocking.run(move || hash_password(&password)).await?;
+
+    let account = Account {
+        id: Uuid::new_v4().to_string(),
+        email,
+        password_hash,
+    };
+    state.local_db.create_account(&account)?;
+
+    adopt_guest_connections(&state, guest, &account.id).await?;
+    start_session(&state, account)
+}
 
-use crate::{foundation::Basable, state::AppState, user::JwtSession, AppError};
+/// Start a session for a registered user. Connections of the guest making the request, if any,
+/// are given to the user.
+#[debug_handler]
+async fn login(
+    State(state): State<AppState>,
+    guest: Option<AuthExtractor>,
+    Json(creds): Json<Credentials>,
+) -> Result<Json<JwtSession>, AppError> {
+    let account = state.local_db.get_account_by_email(creds.email.trim())?;
+
+    // A password is verified even if there's no such account, so the response time doesn't
+    // reveal which emails are registered.
+    let hash = account
+        .as_ref()
+        .map(|account| account.password_hash.clone());
+    let password = creds.password;
+    let verified = state
+        .blocking
+        .run(move || {
+            let hash = hash.as_deref().unwrap_or_else(|| dummy_hash());
+            Ok(verify_password(&password, hash))
+        })
+        .await?;
+
+    match account {
+        Some(account) if verified => {
+            adopt_guest_connections(&state, guest, &account.id).await?;
+            start_session(&state, account)
+        }
+        _ => Err(AppError::HttpError(
+            StatusCode::UNAUTHORIZED,
+            "Invalid email or password".to_string(),
+        )),
+    }
+}
 
+/// Get new tokens for a session, in exchange for its refresh token.
 #[debug_handler]
-async fn create_guest_user(
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    State(_): State<AppState>,
+async fn refresh(
+    State(state): State<AppState>,
+    Json(opts): Json<RefreshOpts>,
 ) -> Result<Json<JwtSession>, AppError> {
-    let addr = addr.ip().to_string();
-    let session = Basable::create_guest_user(&addr)?;
+    let session = state.sessions.refresh(&opts.refresh_token)?;
 
     Ok(Json(session))
 }
 
+/// End the current session. Its access token is denied from then on, and the refresh token
+/// passed along, if any, is revoked.
+#[debug_handler]
+async fn logout(
+    State(state): State<AppState>,
+    ClaimsExtractor(claims): ClaimsExtractor,
+    Json(opts): Json<LogoutOpts>,
+) -> Result<String, AppError> {
+    state.sessions.end(&claims, opts.refresh_token.as_deref())?;
+
+    Ok("Operation successful".to_string())
+}
+
+fn start_session(state: &AppState, account: Account) -> Result<Json<JwtSession>, AppError> {
+    let user = User {
+        id: account.id,
+        is_guest: false,
+        max_role: None,
+    };
+
+    Ok(Json(state.sessions.start(&user)?))
+}
+
+/// Hash of a password no one has, checked when logging in with an unknown email.
+fn dummy_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash_password(&Uuid::new_v4().to_string()).unwrap_or_default())
+}
+
+/// Give the saved connections of `guest` to the registered user `user_id`.
+async fn adopt_guest_connections(
+    state: &AppState,
+    guest: Option<AuthExtractor>,
+    user_id: &str,
+) -> Result<(), AppError> {
+    let Some(AuthExtractor(guest)) = guest else {
+        return Ok(());
+    };
+
+    if guest.is_guest {
+        state.local_db.transfer_connections(&guest.id, user_id)?;
+
+        // Open connections still belong to the guest. They're closed, and restored for the
+        // user on first use.
+        state
+            .instance
+            .write()
+            .await
+            .remove_user_connections(&guest.id);
+    }
+
+    Ok(())
+}
+
 /// Routes for user session management and authentication
 pub(super) fn auth_routes() -> Router<AppState> {
-    Router::new().route("/guest", post(create_guest_user))
-}
\ No newline at end of file
+    Router::new()
+        .route("/guest", post(create_guest_user))
+        .route("/register", post(register))
+        .route("/login", post(login))
+        .route("/refresh", post(refresh))
+        .route("/logout", post(logout))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::http::test_app::{bearer, TestApp};
+
+    #[tokio::test]
+    async fn test_register_and_login() {
+        let app = TestApp::new();
+
+        // Connections of a guest go to the account they register.
+        let guest = app.guest().await;
+        app.connect(&guest).await;
+
+        let creds = json!({ "email": "ada@example.com", "password": "correct horse" });
+        let resp = app
+            .request(
+                Method::POST,
+                "/auth/register",
+                &[("authorization", &guest)],
+                Some(creds.clone()),
+            )
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+        let user = bearer(&resp.json());
+
+        let resp = app
+            .request(
+                Method::GET,
+                "/connections",
+                &[("authorization", &user)],
+                None,
+            )
+            .await;
+        assert_eq!(
+            resp.json().as_array().map(Vec::len),
+            Some(1),
+            "{}",
+            resp.body
+        );
+
+        let resp = app
+            .request(Method::POST, "/auth/register", &[], Some(creds.clone()))
+            .await;
+        assert_ne!(resp.status, StatusCode::OK);
+
+        let wrong = json!({ "email": "ada@example.com", "password": "wrong horse" });
+        let resp = app
+            .request(Method::POST, "/auth/login", &[], Some(wrong))
+            .await;
+        assert_eq!(resp.status, StatusCode::UNAUTHORIZED);
+
+        let resp = app
+            .request(Method::POST, "/auth/login", &[], Some(creds))
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        let resp = app
+            .request(
+                Method::GET,
+                "/connections",
+                &[("authorization", &bearer(&resp.json()))],
+                None,
+            )
+            .await;
+        assert_eq!(
+            resp.json().as_array().map(Vec::len),
+            Some(1),
+            "{}",
+            resp.body
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_end() {
+        let app = TestApp::new();
+        let first = app.register("ada@example.com").await;
+
+        // Refresh tokens are single use.
+        let refresh = json!({ "refresh_token": first["refresh_token"] });
+        let resp = app
+            .request(Method::POST, "/auth/refresh", &[], Some(refresh.clone()))
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+        let second = resp.json();
+
+        let resp = app
+            .request(Method::POST, "/auth/refresh", &[], Some(refresh))
+            .await;
+        assert_eq!(resp.status, StatusCode::UNAUTHORIZED);
+
+        // Reusing one ended the session, including the tokens refreshed from it.
+        let refresh = json!({ "refresh_token": second["refresh_token"] });
+        let resp = app
+            .request(Method::POST, "/auth/refresh", &[], Some(refresh))
+            .await;
+        assert_eq!(resp.status, StatusCode::UNAUTHORIZED);
+
+        // Access tokens are denied once logged out.
+        let third = app.register("bob@example.com").await;
+        let auth = bearer(&third);
+        let resp = app
+            .request(
+                Method::GET,
+                "/connections",
+                &[("authorization", &auth)],
+                None,
+            )
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        let logout = json!({ "refresh_token": third["refresh_token"] });
+        let resp = app
+            .request(
+                Method::POST,
+                "/auth/logout",
+                &[("authorization", &auth)],
+                Some(logout),
+            )
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        let resp = app
+            .request(
+                Method::GET,
+                "/connections",
+                &[("authorization", &auth)],
+                None,
+            )
+            .await;
+        assert_eq!(resp.status, StatusCode::UNAUTHORIZED);
+
+        let refresh = json!({ "refresh_token": third["refresh_token"] });
+        let resp = app
+            .request(Method::POST, "/auth/refresh", &[], Some(refresh))
+            .await;
+        assert_eq!(resp.status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,462 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, patch, post},
+    Json, Router,
+};
+use axum_macros::debug_handler;
+use base::config::ConfigRaw;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    http::middlewares::AuthExtractor,
+    local_db::{password_secret_id, RoleAssignment, SavedConnection},
+    roles::Role,
+    state::AppState,
+    user::User,
+    AppError,
+};
+
+/// A saved connection, as returned to users with a role on it. The password is never included,
+/// since [`ConfigRaw`] doesn't serialize it.
+#[derive(Serialize)]
+pub(crate) struct ConnectionDetails {
+    id: Uuid,
+    name: String,
+
+    /// Only shown to admins of the connection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config: Option<ConfigRaw>,
+
+    created_at: String,
+
+    /// Role of the user on the connection.
+    role: Role,
+
+    /// Email of the owner, for connections shared with the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shared_by: Option<String>,
+
+    /// Whether the connection is open. Saved connections are opened on first use.
+    connected: bool,
+}
+
+impl ConnectionDetails {
+    fn new(saved: SavedConnection, role: Role, connected: bool) -> Self {
+        ConnectionDetails {
+            id: saved.id,
+            name: saved.name,
+            config: (role == Role::Admin).then_some(saved.config),
+            created_at: saved.created_at,
+            role,
+            shared_by: None,
+            connected,
+        }
+    }
+}
+
+/// A user who worked on a connection lately.
+#[derive(Serialize)]
+pub(crate) struct ActiveUser {
+    user_id: String,
+
+    /// Email of the user, unless they are a guest.
+    email: Option<String>,
+
+    /// Unix timestamp the user last used the connection at.
+    last_seen: i64,
+}
+
+/// Changes to a saved connection. When `config` is given without a password, the saved
+/// password is kept.
+#[derive(Deserialize)]
+pub(crate) struct UpdateConnection {
+    name: Option<String>,
+    config: Option<ConfigRaw>,
+}
+
+/// A role to give a user on a connection, or on one of its tables if `table` is set.
+#[derive(Deserialize)]
+pub(crate) struct SetRole {
+    user_id: String,
+    table: Option<String>,
+    role: Role,
+}
+
+/// A registered user to give a role on a connection, by email.
+#[derive(Deserialize)]
+pub(crate) struct Invite {
+    email: String,
+    role: Role,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RoleScope {
+    table: Option<String>,
+}
+
+fn not_found() -> AppError {
+    AppError::HttpError(
+        StatusCode::NOT_FOUND,
+        "Connection instance not found".to_string(),
+    )
+}
+
+/// List the connections of the user, then those shared with them.
+#[debug_handler]
+pub(crate) async fn list_connections(
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ConnectionDetails>>, AppError> {
+    let owned = state.local_db.list_connections(&user.id)?;
+    let shared = state.local_db.list_shared_connections(&user.id)?;
+
+    let mut conns = Vec::with_capacity(owned.len() + shared.len());
+    let instance = state.instance.read().await;
+
+    for conn in owned {
+        let connected = instance.get_connection(&conn.id).is_some();
+        conns.push(ConnectionDetails::new(conn, Role::Admin, connected));
+    }
+
+    for (conn, role) in shared {
+        let connected = instance.get_connection(&conn.id).is_some();
+        let owner = state.local_db.get_account(&conn.user_id)?;
+
+        let mut details = ConnectionDetails::new(conn, role, connected);
+        details.shared_by = owner.map(|owner| owner.email);
+        conns.push(details);
+    }
+
+    Ok(Json(conns))
+}
+
+#[debug_handler]
+pub(crate) async fn update_connection(
+    Path(id): Path<Uuid>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+    Json(update): Json<UpdateConnection>,
+) -> Result<Json<ConnectionDetails>, AppError> {
+    require_admin(&state, &id, &user)?;
+
+    let mut saved = state
+        .local_db
+        .get_connection(&id)?
+        .filter(|saved| saved.user_id == user.id)
+        .ok_or_else(not_found)?;
+
+    if let Some(name) = update.name {
+        saved.name = name;
+    }
+
+    let mut connected = state.instance.read().await.get_connection(&id).is_some();
+
+    if let Some(mut config) = update.config {
+        let secret_id = password_secret_id(&id);
+
+        if config.password.is_none() {
+            config.password = state.vault.get(&secret_id)?;
+        }
+
+        // The new configuration is only saved if it connects. It then replaces the open
+        // connection, so the next request uses it.
+        let db = state
+            .create_connection(id, config.clone(), user.id.clone())
+            .await?;
+
+        let mut instance = state.instance.write().await;
+        instance.remove_connection(&id);
+        instance.add_connection(&db);
+        connected = true;
+        drop(instance);
+
+        if let Some(password) = &config.password {
+            state.vault.put(&secret_id, password)?;
+        }
+        saved.config = config;
+    }
+
+    state.local_db.update_connection(&saved)?;
+
+    Ok(Json(ConnectionDetails::new(saved, Role::Admin, connected)))
+}
+
+#[debug_handler]
+pub(crate) async fn delete_connection(
+    Path(id): Path<Uuid>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<String, AppError> {
+    require_admin(&state, &id, &user)?;
+
+    if !state.local_db.delete_connection(&id, &user.id)? {
+        return Err(not_found());
+    }
+
+    state.instance.write().await.remove_connection(&id);
+    Ok("Operation successful".to_string())
+}
+
+/// Only admins of a connection may change it, or see and manage the roles of others on it.
+fn require_admin(state: &AppState, id: &Uuid, user: &User) -> Result<(), AppError> {
+    let role = state.local_db.role(id, &user.id, None)?;
+    match user.effective_role(role) {
+        Some(Role::Admin) => Ok(()),
+        Some(_) => Err(AppError::HttpError(
+            StatusCode::FORBIDDEN,
+            "This requires the admin role on the connection".to_string(),
+        )),
+        None => Err(not_found()),
+    }
+}
+
+fn assign_role(
+    state: &AppState,
+    id: &Uuid,
+    user_id: &str,
+    table: Option<&str>,
+    role: Role,
+) -> Result<(), AppError> {
+    let is_owner = state
+        .local_db
+        .get_connection(id)?
+        .is_some_and(|saved| saved.user_id == user_id);
+    if is_owner {
+        return Err(AppError::HttpError(
+            StatusCode::BAD_REQUEST,
+            "The owner of a connection is always its admin".to_string(),
+        ));
+    }
+
+    state.local_db.set_role(id, user_id, table, role)?;
+    Ok(())
+}
+
+#[debug_handler]
+pub(crate) async fn list_roles(
+    Path(id): Path<Uuid>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<RoleAssignment>>, AppError> {
+    require_admin(&state, &id, &user)?;
+
+    Ok(Json(state.local_db.list_roles(&id)?))
+}
+
+#[debug_handler]
+pub(crate) async fn set_role(
+    Path(id): Path<Uuid>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+    Json(opts): Json<SetRole>,
+) -> Result<String, AppError> {
+    require_admin(&state, &id, &user)?;
+
+    // Like invites, roles are only given to registered users.
+    let account = state.local_db.get_account(&opts.user_id)?.ok_or_else(|| {
+        AppError::HttpError(
+            StatusCode::NOT_FOUND,
+            "No user is registered with this id".to_string(),
+        )
+    })?;
+    assign_role(&state, &id, &account.id, opts.table.as_deref(), opts.role)?;
+
+    Ok("Operation successful".to_string())
+}
+
+/// Share the connection with a registered user, by giving them a role on it.
+#[debug_handler]
+pub(crate) async fn invite(
+    Path(id): Path<Uuid>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+    Json(invite): Json<Invite>,
+) -> Result<Json<RoleAssignment>, AppError> {
+    require_admin(&state, &id, &user)?;
+
+    let account = state
+        .local_db
+        .get_account_by_email(invite.email.trim())?
+        .ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::NOT_FOUND,
+                "No user is registered with this email".to_string(),
+            )
+        })?;
+    assign_role(&state, &id, &account.id, None, invite.role)?;
+
+    Ok(Json(RoleAssignment {
+        user_id: account.id,
+        email: Some(account.email),
+        table: None,
+        role: invite.role,
+    }))
+}
+
+/// Users other than the current one who worked on the connection lately, most recent first.
+#[debug_handler]
+pub(crate) async fn presence(
+    Path(id): Path<Uuid>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ActiveUser>>, AppError> {
+    if state.local_db.role(&id, &user.id, None)?.is_none() {
+        return Err(not_found());
+    }
+
+    let mut active = vec![];
+    for (user_id, last_seen) in state.presence.active(&id) {
+        if user_id == user.id {
+            continue;
+        }
+
+        let email = state.local_db.get_account(&user_id)?.map(|account| account.email);
+        active.push(ActiveUser {
+            user_id,
+            email,
+            last_seen,
+        });
+    }
+
+    Ok(Json(active))
+}
+
+#[debug_handler]
+pub(crate) async fn remove_role(
+    Path((id, user_id)): Path<(Uuid, String)>,
+    Query(scope): Query<RoleScope>,
+    AuthExtractor(user): AuthExtractor,
+    State(state): State<AppState>,
+) -> Result<String, AppError> {
+    require_admin(&state, &id, &user)?;
+
+    if !state
+        .local_db
+        .remove_role(&id, &user_id, scope.table.as_deref())?
+    {
+        return Err(AppError::HttpError(
+            StatusCode::NOT_FOUND,
+            "The user has no such role".to_string(),
+        ));
+    }
+
+    Ok("Operation successful".to_string())
+}
+
+/// Routes for managing the connections of a user, and sharing them with others.
+pub(super) fn connection_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_connections))
+        .route("/:id", patch(update_connection).delete(delete_connection))
+        .route("/:id/roles", get(list_roles).put(set_role))
+        .route("/:id/roles/:user_id", delete(remove_role))
+        .route("/:id/invites", post(invite))
+        .route("/:id/presence", get(presence))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use rusqlite::Connection;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::http::test_app::{bearer, TestApp};
+
+    #[tokio::test]
+    async fn test_share_connection() {
+        let app = TestApp::new();
+        let owner = bearer(&app.register("ada@example.com").await);
+        let bob = bearer(&app.register("bob@example.com").await);
+        let conn_id = app.connect(&owner).await;
+
+        let invite = json!({ "email": "bob@example.com", "role": "viewer" });
+        let uri = format!("/connections/{conn_id}/invites");
+        let resp = app
+            .request(Method::POST, &uri, &[("authorization", &owner)], Some(invite))
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+        let bob_id = resp.json()["user_id"].as_str().unwrap().to_string();
+
+        let resp = app
+            .request(Method::GET, "/connections", &[("authorization", &bob)], None)
+            .await;
+        let conns = resp.json();
+        assert_eq!(conns[0]["role"], "viewer", "{}", resp.body);
+        assert_eq!(conns[0]["shared_by"], "ada@example.com");
+        assert!(conns[0].get("config").is_none());
+
+        let headers = [
+            ("authorization", bob.as_str()),
+            ("connection-id", conn_id.as_str()),
+        ];
+        let resp = app.request(Method::GET, "/tables", &headers, None).await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        // Viewers may neither change the connection nor edit its data.
+        let uri = format!("/connections/{conn_id}");
+        let rename = json!({ "name": "mine" });
+        let resp = app
+            .request(Method::PATCH, &uri, &[("authorization", &bob)], Some(rename))
+            .await;
+        assert_eq!(resp.status, StatusCode::FORBIDDEN);
+
+        let row = json!({ "customer": "eve", "total": "5" });
+        let resp = app
+            .request(Method::POST, "/tables/data/orders", &headers, Some(row))
+            .await;
+        assert_eq!(resp.status, StatusCode::FORBIDDEN);
+
+        // Roles can't be given to anyone but registered users.
+        let uri = format!("/connections/{conn_id}/roles");
+        let role = json!({ "user_id": Uuid::new_v4().to_string(), "role": "editor" });
+        let resp = app
+            .request(Method::PUT, &uri, &[("authorization", &owner)], Some(role))
+            .await;
+        assert_eq!(resp.status, StatusCode::NOT_FOUND, "{}", resp.body);
+        assert!(resp.body.contains("No user is registered"), "{}", resp.body);
+
+        let uri = format!("/connections/{conn_id}/roles/{bob_id}");
+        let resp = app
+            .request(Method::DELETE, &uri, &[("authorization", &owner)], None)
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        let resp = app.request(Method::GET, "/tables", &headers, None).await;
+        assert_eq!(resp.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_paths_outside_data_dir() {
+        let app = TestApp::new();
+        let owner = bearer(&app.register("ada@example.com").await);
+        let conn_id = app.connect(&owner).await;
+
+        let outside = std::env::temp_dir().join(format!("basable-{}.db", Uuid::new_v4()));
+        Connection::open(&outside)
+            .unwrap()
+            .execute_batch("CREATE TABLE secrets (value TEXT);")
+            .unwrap();
+
+        let paths = [outside.to_string_lossy().into_owned(), "../shop.db".to_string()];
+        for path in paths {
+            let config = json!({ "source_type": "database", "source": "sqlite", "path": path });
+
+            let auth = [("authorization", owner.as_str())];
+            let resp = app
+                .request(Method::POST, "/connect", &auth, Some(config.clone()))
+                .await;
+            assert_eq!(resp.status, StatusCode::BAD_REQUEST, "{}", resp.body);
+
+            // Nor can a saved connection be moved outside of it.
+            let uri = format!("/connections/{conn_id}");
+            let update = json!({ "config": config });
+            let resp = app.request(Method::PATCH, &uri, &auth, Some(update)).await;
+            assert_eq!(resp.status, StatusCode::BAD_REQUEST, "{}", resp.body);
+        }
+
+        let _ = std::fs::remove_file(outside);
+    }
+}
@@ -6,21 +6,32 @@ use axum::{
     Json, Router,
 };
 use axum_macros::debug_handler;
-use base::graphs::{category::CategoryGraphOpts, chrono::ChronoAnalysisOpts, geo::GeoGraphOpts, trend::TrendGraphOpts, AnalysisResults, FromQueryParams};
+use base::graphs::{category::CategoryGraphOpts, chrono::ChronoAnalysisOpts, geo::GeoGraphOpts, series::GraphResults, trend::TrendGraphOpts, AnalysisResults, FromQueryParams};
 
 use crate::{
-    http::middlewares::{AuthExtractor, DbExtractor}, state::AppState, AppError
+    http::middlewares::{AuthExtractor, DbExtractor, RequireRole},
+    roles::Analyst,
+    state::AppState,
+    AppError,
 };
 
 #[debug_handler]
 pub async fn chrono_graph(
     Query(params): Query<HashMap<String, String>>,
     AuthExtractor(_): AuthExtractor,
+    _: RequireRole<Analyst>,
     DbExtractor(db): DbExtractor,
-    State(_): State<AppState>,
-) -> Result<Json<AnalysisResults>, AppError> {
+    State(state): State<AppState>,
+) -> Result<Json<GraphResults>, AppError> {
     let opts = ChronoAnalysisOpts::from_query_params(params)?;
-    let results = db.chrono_graph(opts)?;
+
+    let results = state
+        .blocking
+        .run(move || {
+            opts.validate(db.as_ref())?;
+            db.chrono_graph(opts)
+        })
+        .await?;
 
     Ok(Json(results))
 }
@@ -29,11 +40,19 @@ pub async fn chrono_graph(
 pub async fn trend_graph(
     Query(params): Query<HashMap<String, String>>,
     AuthExtractor(_): AuthExtractor,
+    _: RequireRole<Analyst>,
     DbExtractor(db): DbExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<Json<AnalysisResults>, AppError> {
     let opts = TrendGraphOpts::from_query_params(params)?;
-    let graph = db.trend_graph(opts)?;
+
+    let graph = state
+        .blocking
+        .run(move || {
+            opts.validate(db.as_ref())?;
+            db.trend_graph(opts)
+        })
+        .await?;
 
     Ok(Json(graph))
 }
@@ -42,11 +61,19 @@ pub async fn trend_graph(
 pub async fn category_graph(
     Query(params): Query<HashMap<String, String>>,
     AuthExtractor(_): AuthExtractor,
+    _: RequireRole<Analyst>,
     DbExtractor(db): DbExtractor,
-    State(_): State<AppState>,
-) -> Result<Json<AnalysisResults>, AppError> {
+    State(state): State<AppState>,
+) -> Result<Json<GraphResults>, AppError> {
     let opts = CategoryGraphOpts::from_query_params(params)?;
-    let graph = db.category_graph(opts)?;
+
+    let graph = state
+        .blocking
+        .run(move || {
+            opts.validate(db.as_ref())?;
+            db.category_graph(opts)
+        })
+        .await?;
 
     Ok(Json(graph))
 }
@@ -55,11 +82,19 @@ pub async fn category_graph(
 pub async fn geo_graph(
     Query(params): Query<HashMap<String, String>>,
     AuthExtractor(_): AuthExtractor,
+    _: RequireRole<Analyst>,
     DbExtractor(db): DbExtractor,
-    State(_): State<AppState>,
-) -> Result<Json<AnalysisResults>, AppError> {
+    State(state): State<AppState>,
+) -> Result<Json<GraphResults>, AppError> {
     let opts = GeoGraphOpts::from_query_params(params)?;
-    let graph = db.geo_graph(opts)?;
+
+    let graph = state
+        .blocking
+        .run(move || {
+            opts.validate(db.as_ref())?;
+            db.geo_graph(opts)
+        })
+        .await?;
 
     Ok(Json(graph))
 }
@@ -72,3 +107,64 @@ pub(super) fn graphs_routes() -> Router<AppState> {
         .route("/category", get(category_graph))
         .route("/geo", get(geo_graph))
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::http::test_app::{bearer, TestApp};
+
+    #[tokio::test]
+    async fn test_table_roles() {
+        let app = TestApp::new();
+        let owner = bearer(&app.register("ada@example.com").await);
+        let conn_id = app.connect(&owner).await;
+
+        // Bob is an analyst of the connection, but may only view customers.
+        let bob = app.register("bob@example.com").await;
+        let bob_id = {
+            let invite = json!({ "email": "bob@example.com", "role": "analyst" });
+            let uri = format!("/connections/{conn_id}/invites");
+            let resp = app
+                .request(Method::POST, &uri, &[("authorization", &owner)], Some(invite))
+                .await;
+            assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+            resp.json()["user_id"].as_str().unwrap().to_string()
+        };
+
+        let role = json!({ "user_id": bob_id, "table": "customers", "role": "viewer" });
+        let uri = format!("/connections/{conn_id}/roles");
+        let resp = app
+            .request(Method::PUT, &uri, &[("authorization", &owner)], Some(role))
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        let bob = bearer(&bob);
+        let headers = [
+            ("authorization", bob.as_str()),
+            ("connection-id", conn_id.as_str()),
+        ];
+
+        let uri = "/graphs/category?table=orders&analysis=simple&target_column=customer";
+        let resp = app.request(Method::GET, uri, &headers, None).await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        let uri = "/graphs/category?table=customers&analysis=simple&target_column=name";
+        let resp = app.request(Method::GET, uri, &headers, None).await;
+        assert_eq!(resp.status, StatusCode::FORBIDDEN, "{}", resp.body);
+
+        // Joined tables need the role as well.
+        let uri = "/graphs/trend?table=orders&graph_type=cross&xcol=customer&ycol=name\
+            &foreign_table=customers&target_column=customer";
+        let resp = app.request(Method::GET, uri, &headers, None).await;
+        assert_eq!(resp.status, StatusCode::FORBIDDEN, "{}", resp.body);
+
+        let owner_headers = [
+            ("authorization", owner.as_str()),
+            ("connection-id", conn_id.as_str()),
+        ];
+        let resp = app.request(Method::GET, uri, &owner_headers, None).await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+    }
+}
@@ -1,59 +1,112 @@
+use std::path::Path;
+
 use axum::routing::{get, post};
+use axum::http::StatusCode;
 use axum::Router;
 use base::config::ConfigRaw;
 use common::DbServerDetails;
 use graphs::graphs_routes;
+use serde::Deserialize;
+use uuid::Uuid;
 
-use crate::foundation::Basable;
 use crate::http::middlewares::AuthExtractor;
+use crate::local_db::{password_secret_id, SavedConnection};
 use crate::state::AppState;
 use crate::AppError;
 use axum::{extract::State, Json};
 use axum_macros::debug_handler;
 
+use self::api_keys::api_key_routes;
+use self::audit::audit_routes;
 use self::auth::auth_routes;
+use self::connections::connection_routes;
 use self::table::table_routes;
 
 use super::middlewares::DbExtractor;
 
+pub(super) mod api_keys;
+pub(super) mod audit;
 pub(super) mod auth;
+pub(super) mod connections;
 pub(super) mod graphs;
 pub(super) mod table;
 
+/// Options to connect with: the source's [`ConfigRaw`], and optionally a name to save the
+/// connection under.
+#[derive(Deserialize)]
+struct ConnectOpts {
+    name: Option<String>,
+
+    #[serde(flatten)]
+    config: ConfigRaw,
+}
+
 #[debug_handler]
 async fn connect(
     State(state): State<AppState>,
     AuthExtractor(user): AuthExtractor,
-    Json(config): Json<ConfigRaw>,
+    Json(opts): Json<ConnectOpts>,
 ) -> Result<Json<String>, AppError> {
-    let mut bsbl = state.instance.lock().unwrap();
-    let storage = state.local_db;
+    if user.is_read_only() {
+        return Err(AppError::HttpError(
+            StatusCode::FORBIDDEN,
+            "Read-only API keys can't create connections".to_string(),
+        ));
+    }
 
-    let user_id = user.id.clone();
-    let db = Basable::create_connection(&config, user_id)?;
+    let ConnectOpts { name, config } = opts;
+    let id = Uuid::new_v4();
 
-    bsbl.add_connection(&db);
-    std::mem::drop(bsbl); // release Mutex lock
+    let db = state
+        .open_connection(id, config.clone(), user.id.clone())
+        .await?;
 
-    let conn_id = db.id().to_string();
-    let tables = db.tables();
-    if !tables.is_empty() {
-        for tbl in tables {
-            if let Some(config) = tbl.init_config() {
-                storage.create_table_config(&conn_id, config)?;
-            }
-        }
+    // Only connections that work are saved.
+    let saved = SavedConnection {
+        id,
+        user_id: user.id,
+        name: name.unwrap_or_else(|| default_name(&config)),
+        config,
+        created_at: String::new(),
+    };
+
+    // The password is kept apart from the configuration, encrypted.
+    let save = state.local_db.save_connection(&saved).and_then(|_| match &saved.config.password {
+        Some(password) => state.vault.put(&password_secret_id(&id), password),
+        None => Ok(()),
+    });
+
+    if let Err(err) = save {
+        state.instance.write().await.remove_connection(&id);
+        let _ = state.local_db.delete_connection(&id, &saved.user_id);
+        return Err(err);
     }
 
+    let conn_id = db.id().to_string();
     Ok(Json(conn_id))
 }
 
+/// Name of a saved connection when none is given: the database or file name, or the source.
+fn default_name(config: &ConfigRaw) -> String {
+    let file_name = config.path.as_deref().and_then(|path| {
+        Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    });
+
+    config
+        .db_name
+        .clone()
+        .or(file_name)
+        .unwrap_or_else(|| config.source.clone())
+}
+
 async fn server_details(
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<Json<DbServerDetails>, AppError> {
-    let details = db.details()?;
+    let details = state.blocking.run(move || db.details()).await?;
 
     Ok(Json(details))
 }
@@ -62,7 +115,10 @@ pub(super) fn core_routes() -> Router<AppState> {
     Router::new()
         .route("/connect", post(connect))
         .route("/server", get(server_details))
+        .nest("/api-keys", api_key_routes())
+        .nest("/audit", audit_routes())
         .nest("/auth", auth_routes())
+        .nest("/connections", connection_routes())
         .nest("/tables", table_routes())
         .nest("/graphs", graphs_routes())
 }
@@ -1,24 +1,36 @@
 use std::collections::HashMap;
 
 use axum::{
+    body::Body,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
+    response::{IntoResponse, Response},
     routing::{delete, get, patch, post},
     Json, Router,
 };
 use axum_macros::debug_handler;
-use base::mysql_plugin::ColumnValue;
+use futures_util::{stream, StreamExt};
+use common::data::row::ColumnValue;
 use common::data::{columns::ColumnList, table::{TableConfig, TableExportOpts, TableExportResponse, TableQueryOpts, TableSummaries, UpdateTableData}};
+use base::SharedDB;
 use uuid::Uuid;
 
 use crate::{
-    http::middlewares::{AuthExtractor, DbExtractor, TableExtractor}, state::AppState, AppError
+    audit::{self, Change},
+    http::middlewares::{AuthExtractor, DbExtractor, RequireRole, TableExtractor},
+    roles::{Admin, Analyst, Editor},
+    state::AppState,
+    AppError,
 };
 
 #[debug_handler]
 pub(crate) async fn save_configuration(
     Path(table_name): Path<String>,
     AuthExtractor(_): AuthExtractor,
+    _: RequireRole<Admin>,
     DbExtractor(db): DbExtractor,
     TableExtractor(_): TableExtractor,
     State(state): State<AppState>,
@@ -52,24 +64,60 @@ pub(crate) async fn get_columns(
     AuthExtractor(_): AuthExtractor,
     DbExtractor(_): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<Json<ColumnList>, AppError> {
-    let cols = table.query_columns()?;
+    let cols = state.blocking.run(move || table.query_columns()).await?;
 
     Ok(Json(cols))
 }
 
+/// Media type of newline-delimited JSON, which [`query_data`] streams rows as when requested.
+const NDJSON: &str = "application/x-ndjson";
+
+/// Query table rows as a JSON list. If the `Accept` header asks for [`NDJSON`], rows are
+/// instead streamed one per line as they're read, so large results never have to fit in memory.
 #[debug_handler]
 pub(crate) async fn query_data(
     Path(_): Path<String>,
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
     Json(opts): Json<TableQueryOpts>,
-) -> Result<Json<Vec<HashMap<String, ColumnValue>>>, AppError> {
-    let data = table.query_data(opts, &db)?;
-    Ok(Json(data))
+) -> Result<Response, AppError> {
+    let wants_ndjson = headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(NDJSON));
+
+    if !wants_ndjson {
+        let data = state.blocking.run(move || table.query_data(opts, &db)).await?;
+        return Ok(Json(data).into_response());
+    }
+
+    let mut rows = state
+        .blocking
+        .stream(move |sink| table.stream_data(opts, &db, sink))
+        .await?;
+
+    // Errors raised before the first row, such as unknown columns, are still sent as error
+    // responses. Later errors abort the response.
+    let first = rows.recv().await.transpose()?;
+    let rest = stream::unfold(rows, |mut rows| async move {
+        rows.recv().await.map(|row| (row, rows))
+    });
+
+    let lines = stream::iter(first.map(Ok)).chain(rest).map(|row| {
+        let row: HashMap<String, ColumnValue> = row?;
+        let mut line = serde_json::to_vec(&row)
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+        line.push(b'\n');
+
+        Ok::<_, AppError>(line)
+    });
+
+    Ok(([(CONTENT_TYPE, NDJSON)], Body::from_stream(lines)).into_response())
 }
 
 #[debug_handler]
@@ -78,46 +126,63 @@ pub(crate) async fn query_result_count(
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
     Json(filter): Json<TableQueryOpts>,
 ) -> Result<Json<usize>, AppError> {
-    let count = table.query_result_count(filter, &db)?;
+    let count = state
+        .blocking
+        .run(move || table.query_result_count(filter, &db))
+        .await?;
     Ok(Json(count))
 }
 
 #[debug_handler]
 pub(crate) async fn insert_data(
-    Path(_): Path<String>,
-    AuthExtractor(_): AuthExtractor,
-    DbExtractor(_): DbExtractor,
+    Path(table_name): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    _: RequireRole<Editor>,
+    DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
     Json(data): Json<HashMap<String, String>>,
 ) -> Result<String, AppError> {
-    table.insert_data(data)?;
-    Ok("Operation successful".to_string())
+    let pk = pk_column(&state, &db, &table_name);
+    let change = state
+        .blocking
+        .run(move || audit::insert(&table, pk, data))
+        .await?;
+
+    Ok(record_change(&state, change, &user.id, &db, &table_name))
 }
 
 #[debug_handler]
 pub(crate) async fn update_data(
-    Path(_): Path<String>,
-    AuthExtractor(_): AuthExtractor,
-    DbExtractor(_): DbExtractor,
+    Path(table_name): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    _: RequireRole<Editor>,
+    DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
     Json(options): Json<UpdateTableData>,
 ) -> Result<String, AppError> {
-    table.update_data(options)?;
-    Ok("Operation successful".to_string())
+    let pk = pk_column(&state, &db, &table_name);
+    let conn = db.clone();
+    let change = state
+        .blocking
+        .run(move || audit::update(&table, &conn, pk, options))
+        .await?;
+
+    Ok(record_change(&state, change, &user.id, &db, &table_name))
 }
 
 pub(crate) async fn delete_data(
     Query(params): Query<HashMap<String, String>>,
-    Path(_): Path<String>,
-    AuthExtractor(_): AuthExtractor,
-    DbExtractor(_): DbExtractor,
+    Path(table_name): Path<String>,
+    AuthExtractor(user): AuthExtractor,
+    _: RequireRole<Editor>,
+    DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<String, AppError> {
     let col = params.get("col");
     let value = params.get("value");
@@ -129,22 +194,57 @@ pub(crate) async fn delete_data(
         (None, Some(_)) => Err(err("Please provide 'value' query param.")),
         (Some(_), None) => Err(err("Please provide 'col' query param.")),
         (Some(col), Some(value)) => {
-            table.delete_data(col.clone(), value.clone())?;
-            Ok("Operation successful".to_string())
+            let (col, value) = (col.clone(), value.clone());
+            let pk = pk_column(&state, &db, &table_name);
+            let conn = db.clone();
+            let change = state
+                .blocking
+                .run(move || audit::delete(&table, &conn, pk, col, value))
+                .await?;
+
+            Ok(record_change(&state, change, &user.id, &db, &table_name))
         }
     }
 }
 
+/// The primary key column configured for the table, if any.
+fn pk_column(state: &AppState, db: &SharedDB, table_name: &str) -> Option<String> {
+    state
+        .local_db
+        .get_table_config(table_name, &db.id().to_string())
+        .ok()
+        .and_then(|config| config.pk_column)
+}
+
+/// Record a change already made to the data in the audit log. Recording is best effort: the
+/// change is committed by then, so failing to record it is logged rather than returned as an
+/// error, which would have the client retry a change that was made.
+fn record_change(
+    state: &AppState,
+    change: Change,
+    user_id: &str,
+    db: &SharedDB,
+    table_name: &str,
+) -> String {
+    let entry = change.into_entry(user_id, db.id(), table_name);
+    if let Err(err) = state.local_db.record_audit_entry(&entry) {
+        tracing::error!("error recording a change to '{table_name}' in the audit log: {err}");
+    }
+
+    "Operation successful".to_string()
+}
+
 pub(crate) async fn export(
     Path(_): Path<String>,
     AuthExtractor(_): AuthExtractor,
+    _: RequireRole<Analyst>,
     DbExtractor(db): DbExtractor,
     TableExtractor(table): TableExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
     Json(opts): Json<TableExportOpts>,
 ) -> Result<Json<TableExportResponse>, AppError> {
     let format = opts.format.clone();
-    let data = table.export(opts, &db)?;
+    let data = state.blocking.run(move || table.export(opts, &db)).await?;
     let resp = TableExportResponse {
         data,
         mimetype: format.as_mimetype(),
@@ -158,9 +258,9 @@ pub(crate) async fn export(
 pub(crate) async fn load_tables(
     AuthExtractor(_): AuthExtractor,
     DbExtractor(db): DbExtractor,
-    State(_): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<Json<TableSummaries>, AppError> {
-    let tables = db.build_table_list()?;
+    let tables = state.blocking.run(move || db.build_table_list()).await?;
 
     Ok(Json(tables))
 }
@@ -179,3 +279,55 @@ pub(super) fn table_routes() -> Router<AppState> {
         .route("/data/:table_name", delete(delete_data))
         .route("/data/export/:table_name", post(export))
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::http::test_app::TestApp;
+
+    #[tokio::test]
+    async fn test_query_other_table() {
+        let app = TestApp::new();
+        let auth = app.guest().await;
+        let conn_id = app.connect(&auth).await;
+        let headers = [
+            ("authorization", auth.as_str()),
+            ("connection-id", conn_id.as_str()),
+        ];
+
+        // The table in the body is not the one of the route, whose rows are returned instead.
+        let opts = json!({
+            "table": "customers",
+            "offset": 0,
+            "row_count": 10,
+            "filters": null,
+            "columns": null,
+            "order_by": null,
+            "search_opts": null,
+        });
+
+        let uri = "/tables/query-data/orders";
+        let resp = app
+            .request(Method::POST, uri, &headers, Some(opts.clone()))
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+        let rows = resp.json();
+        assert_eq!(rows.as_array().map(Vec::len), Some(2));
+        assert!(rows[0].get("email").is_none());
+
+        let uri = "/tables/query-result-count/orders";
+        let resp = app
+            .request(Method::POST, uri, &headers, Some(opts.clone()))
+            .await;
+        assert_eq!(resp.json(), json!(2), "{}", resp.body);
+
+        let export = json!({ "format": "CSV", "query_opts": opts, "trim": null });
+        let uri = "/tables/data/export/orders";
+        let resp = app.request(Method::POST, uri, &headers, Some(export)).await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+        let csv = resp.json()["data"].as_str().unwrap().to_string();
+        assert!(csv.contains("bob") && !csv.contains("ada@example.com"), "{csv}");
+    }
+}
@@ -0,0 +1,166 @@
+use std::{fs, path::PathBuf, sync::Arc};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use base::{config::DataDir, registry::BackendRegistry};
+use rusqlite::Connection;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use super::routes::core_routes;
+use crate::{
+    blocking::BlockingPool, local_db::LocalDB, session::Sessions, state::AppState, vault::Vault,
+};
+
+/// Secret shared with the other tests setting `BASABLE_JWT_SECRET`, since tests of the crate
+/// run at the same time in a single process.
+const JWT_SECRET: &str = "test secret";
+
+const VAULT_SECRET: &str = "a master secret for tests of at least 32 characters";
+
+/// The core routes with their own in-memory [`LocalDB`], and a data directory holding
+/// `shop.db`, an SQLite database with `orders` and `customers` tables.
+pub(crate) struct TestApp {
+    data_dir: PathBuf,
+    router: Router,
+}
+
+/// Status and body of a response.
+pub(crate) struct TestResponse {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body)
+            .unwrap_or_else(|err| panic!("invalid JSON response '{}': {err}", self.body))
+    }
+}
+
+impl TestApp {
+    pub fn new() -> Self {
+        std::env::set_var("BASABLE_JWT_SECRET", JWT_SECRET);
+        std::env::set_var("BASABLE_JWT_BEARER", "Bearer");
+
+        let data_dir = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(&data_dir).unwrap();
+        Connection::open(data_dir.join("shop.db"))
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer TEXT, total REAL);
+                CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT, email TEXT);
+                INSERT INTO orders (customer, total) VALUES ('ada', 20.5), ('bob', 10);
+                INSERT INTO customers (name, email) VALUES ('ada', 'ada@example.com');",
+            )
+            .unwrap();
+
+        let local_db = LocalDB::memory().unwrap();
+        local_db.setup().unwrap();
+
+        let mut registry = BackendRegistry::default();
+        registry.set_data_dir(DataDir::new(&data_dir, &[]).unwrap());
+
+        let state = AppState {
+            instance: Default::default(),
+            registry: Arc::new(registry),
+            vault: Vault::new(&[VAULT_SECRET.to_string()], local_db.clone()).unwrap(),
+            sessions: Sessions::new(local_db.clone(), 60, 600),
+            local_db,
+            blocking: BlockingPool::new(4),
+            presence: Default::default(),
+        };
+
+        TestApp {
+            router: core_routes().with_state(state),
+            data_dir,
+        }
+    }
+
+    /// Send a request to `uri`, with a JSON `body` if any.
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let body = match body {
+            Some(body) => {
+                request = request.header("content-type", "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+
+        let response = self
+            .router
+            .clone()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        TestResponse {
+            status,
+            body: String::from_utf8(body.to_vec()).unwrap(),
+        }
+    }
+
+    /// Start a guest session, and return its Authorization header value.
+    pub async fn guest(&self) -> String {
+        let resp = self.request(Method::POST, "/auth/guest", &[], None).await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        bearer(&resp.json())
+    }
+
+    /// Register a user, and return the tokens of their session.
+    pub async fn register(&self, email: &str) -> Value {
+        let creds = json!({ "email": email, "password": "correct horse" });
+        let resp = self
+            .request(Method::POST, "/auth/register", &[], Some(creds))
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        resp.json()
+    }
+
+    /// Connect to `shop.db` as the user authenticated by `auth`, and return the connection id.
+    pub async fn connect(&self, auth: &str) -> String {
+        let config = json!({ "source_type": "database", "source": "sqlite", "path": "shop.db" });
+        let resp = self
+            .request(
+                Method::POST,
+                "/connect",
+                &[("authorization", auth)],
+                Some(config),
+            )
+            .await;
+        assert_eq!(resp.status, StatusCode::OK, "{}", resp.body);
+
+        resp.json().as_str().unwrap().to_string()
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.data_dir);
+    }
+}
+
+/// Authorization header value of the access token of a session.
+pub(crate) fn bearer(session: &Value) -> String {
+    format!("Bearer {}", session["token"].as_str().unwrap())
+}
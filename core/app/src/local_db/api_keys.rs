@@ -0,0 +1,122 @@
+use common::error::AppError;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::api_key::ApiKeyScope;
+
+use super::LocalDB;
+
+/// An API key of a user, identified by the hash of its secret, which isn't kept.
+#[derive(Serialize)]
+pub(crate) struct ApiKey {
+    pub id: String,
+
+    #[serde(skip)]
+    pub user_id: String,
+
+    pub name: String,
+    pub scope: ApiKeyScope,
+
+    /// Unix timestamp the key expires at, if it does.
+    pub expires_at: Option<i64>,
+
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+}
+
+impl ApiKey {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let scope: String = row.get(3)?;
+        let scope = scope
+            .parse()
+            .map_err(|_| rusqlite::Error::InvalidColumnName("scope".to_string()))?;
+
+        Ok(ApiKey {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            name: row.get(2)?,
+            scope,
+            expires_at: row.get(4)?,
+            created_at: row.get(5)?,
+            last_used_at: row.get(6)?,
+        })
+    }
+}
+
+fn storage_err(err: rusqlite::Error) -> AppError {
+    AppError::PersistentStorageError(err.to_string())
+}
+
+impl LocalDB {
+    pub fn create_api_key(&self, key: &ApiKey, key_hash: &str) -> Result<usize, AppError> {
+        self.pool()?
+            .execute(
+                "INSERT INTO api_keys (id, user_id, name, key_hash, scope, expires_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    key.id,
+                    key.user_id,
+                    key.name,
+                    key_hash,
+                    key.scope.as_str(),
+                    key.expires_at,
+                    key.created_at
+                ],
+            )
+            .map_err(storage_err)
+    }
+
+    /// API keys of the user, oldest first.
+    pub fn list_api_keys(&self, user_id: &str) -> Result<Vec<ApiKey>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare(
+                "SELECT id, user_id, name, scope, expires_at, created_at, last_used_at FROM api_keys WHERE user_id = ?1 ORDER BY created_at, rowid",
+            )
+            .map_err(storage_err)?;
+
+        let keys = stmt
+            .query_map(params![user_id], ApiKey::from_row)
+            .map_err(storage_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_err)?;
+
+        Ok(keys)
+    }
+
+    /// Delete the API key with the given id, if it belongs to the user. Returns whether it was
+    /// found.
+    pub fn revoke_api_key(&self, id: &str, user_id: &str) -> Result<bool, AppError> {
+        self.pool()?
+            .execute(
+                "DELETE FROM api_keys WHERE id = ?1 AND user_id = ?2",
+                params![id, user_id],
+            )
+            .map(|deleted| deleted > 0)
+            .map_err(storage_err)
+    }
+
+    /// Get the API key with hash `key_hash` if it's valid at `now`, and record its use.
+    pub fn use_api_key(&self, key_hash: &str, now: i64) -> Result<Option<ApiKey>, AppError> {
+        let conn = self.pool()?;
+
+        let key = conn
+            .query_row(
+                "SELECT id, user_id, name, scope, expires_at, created_at, last_used_at FROM api_keys
+                WHERE key_hash = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+                params![key_hash, now],
+                ApiKey::from_row,
+            )
+            .optional()
+            .map_err(storage_err)?;
+
+        if let Some(key) = &key {
+            conn.execute(
+                "UPDATE api_keys SET last_used_at = ?1 WHERE id = ?2",
+                params![now, key.id],
+            )
+            .map_err(storage_err)?;
+        }
+
+        Ok(key)
+    }
+}
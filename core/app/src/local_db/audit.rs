@@ -0,0 +1,211 @@
+use std::str::FromStr;
+
+use common::error::AppError;
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::{AuditOperation, Snapshot};
+
+use super::LocalDB;
+
+/// Most entries [`LocalDB::list_audit_entries`] returns at once.
+const MAX_AUDIT_ENTRIES: usize = 1000;
+
+/// A change made to the data of a table.
+#[derive(Serialize)]
+pub(crate) struct AuditEntry {
+    /// Set once recorded.
+    pub id: i64,
+
+    pub user_id: String,
+    pub conn_id: Uuid,
+    pub table: String,
+    pub operation: AuditOperation,
+
+    /// The column `keys` are values of, which identify the affected rows.
+    pub key_column: Option<String>,
+    pub keys: Vec<String>,
+
+    /// The affected rows before and after the change, when the table has a primary key column
+    /// configured.
+    pub before: Option<Snapshot>,
+    pub after: Option<Snapshot>,
+
+    /// Unix timestamp of the change.
+    pub created_at: i64,
+}
+
+impl AuditEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let json_err = |col: &str| rusqlite::Error::InvalidColumnName(col.to_string());
+
+        let conn_id: String = row.get(2)?;
+        let operation: String = row.get(4)?;
+        let keys: String = row.get(6)?;
+        let before: Option<String> = row.get(7)?;
+        let after: Option<String> = row.get(8)?;
+
+        let snapshot = |value: Option<String>, col: &str| {
+            value
+                .map(|value| serde_json::from_str(&value).map_err(|_| json_err(col)))
+                .transpose()
+        };
+
+        Ok(AuditEntry {
+            id: row.get(0)?,
+            user_id: row.get(1)?,
+            conn_id: Uuid::from_str(&conn_id).map_err(|_| json_err("conn_id"))?,
+            table: row.get(3)?,
+            operation: operation.parse().map_err(|_| json_err("operation"))?,
+            key_column: row.get(5)?,
+            keys: serde_json::from_str(&keys).map_err(|_| json_err("keys"))?,
+            before: snapshot(before, "before")?,
+            after: snapshot(after, "after")?,
+            created_at: row.get(9)?,
+        })
+    }
+}
+
+/// Which entries of a connection's audit log to list. Time bounds are Unix timestamps, `to`
+/// being excluded.
+#[derive(Deserialize)]
+pub(crate) struct AuditFilter {
+    pub user_id: Option<String>,
+    pub table: Option<String>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+
+    #[serde(default)]
+    pub offset: usize,
+}
+
+fn default_limit() -> usize {
+    100
+}
+
+fn storage_err(err: impl ToString) -> AppError {
+    AppError::PersistentStorageError(err.to_string())
+}
+
+impl LocalDB {
+    /// Record `entry`, and return its id.
+    pub fn record_audit_entry(&self, entry: &AuditEntry) -> Result<i64, AppError> {
+        let to_json = |value: &Option<Snapshot>| {
+            value
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(storage_err)
+        };
+
+        let keys = serde_json::to_string(&entry.keys).map_err(storage_err)?;
+        let before = to_json(&entry.before)?;
+        let after = to_json(&entry.after)?;
+
+        let conn = self.pool()?;
+        conn.execute(
+            "INSERT INTO audit_log (user_id, conn_id, table_name, operation, key_column, keys, before, after, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                entry.user_id,
+                entry.conn_id.to_string(),
+                entry.table,
+                entry.operation.as_str(),
+                entry.key_column,
+                keys,
+                before,
+                after,
+                entry.created_at
+            ],
+        )
+        .map_err(storage_err)?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Entries of the connection's audit log that match `filter`, most recent first.
+    pub fn list_audit_entries(&self, conn_id: &Uuid, filter: &AuditFilter) -> Result<Vec<AuditEntry>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare(
+                "SELECT id, user_id, conn_id, table_name, operation, key_column, keys, before, after, created_at
+                FROM audit_log
+                WHERE conn_id = ?1
+                    AND (?2 IS NULL OR user_id = ?2)
+                    AND (?3 IS NULL OR table_name = ?3)
+                    AND (?4 IS NULL OR created_at >= ?4)
+                    AND (?5 IS NULL OR created_at < ?5)
+                ORDER BY id DESC LIMIT ?6 OFFSET ?7",
+            )
+            .map_err(storage_err)?;
+
+        let entries = stmt
+            .query_map(
+                params![
+                    conn_id.to_string(),
+                    filter.user_id,
+                    filter.table,
+                    filter.from,
+                    filter.to,
+                    filter.limit.min(MAX_AUDIT_ENTRIES),
+                    filter.offset
+                ],
+                AuditEntry::from_row,
+            )
+            .map_err(storage_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_err)?;
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::{audit::AuditOperation, local_db::LocalDB};
+
+    use super::{AuditEntry, AuditFilter};
+
+    #[test]
+    fn test_audit_filter() {
+        let db = LocalDB::memory().unwrap();
+        db.setup().unwrap();
+
+        let conn_id = Uuid::new_v4();
+        let entry = |user_id: &str, table: &str, created_at: i64| AuditEntry {
+            id: 0,
+            user_id: user_id.to_string(),
+            conn_id,
+            table: table.to_string(),
+            operation: AuditOperation::Delete,
+            key_column: Some("id".to_string()),
+            keys: vec!["1".to_string()],
+            before: None,
+            after: None,
+            created_at,
+        };
+
+        db.record_audit_entry(&entry("ada", "orders", 100)).unwrap();
+        db.record_audit_entry(&entry("ada", "users", 200)).unwrap();
+        db.record_audit_entry(&entry("bob", "orders", 300)).unwrap();
+
+        let filter = AuditFilter {
+            user_id: None,
+            table: Some("orders".to_string()),
+            from: Some(100),
+            to: Some(300),
+            limit: 10,
+            offset: 0,
+        };
+        let found = db.list_audit_entries(&conn_id, &filter).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].user_id, "ada");
+        assert_eq!(found[0].keys, vec!["1"]);
+    }
+}
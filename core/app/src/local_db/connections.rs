@@ -0,0 +1,238 @@
+use std::str::FromStr;
+
+use base::config::ConfigRaw;
+use common::error::AppError;
+use rusqlite::{params, OptionalExtension, Row};
+use uuid::Uuid;
+
+use crate::roles::Role;
+
+use super::LocalDB;
+
+/// Id of the [`Vault`](crate::vault::Vault) secret holding the password of a saved connection.
+pub(crate) fn password_secret_id(conn_id: &Uuid) -> String {
+    format!("connections/{conn_id}/password")
+}
+
+/// A connection saved by a user, so it can be restored with the same id after a restart.
+///
+/// The password of `config` is never stored along with it, but kept in the vault under
+/// [`password_secret_id`].
+#[derive(Clone)]
+pub(crate) struct SavedConnection {
+    pub id: Uuid,
+    pub user_id: String,
+    pub name: String,
+    pub config: ConfigRaw,
+    pub created_at: String,
+}
+
+impl SavedConnection {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let id: String = row.get(0)?;
+        let config: String = row.get(3)?;
+
+        let id = Uuid::from_str(&id).map_err(|_| rusqlite::Error::InvalidColumnName("id".to_string()))?;
+        let config = serde_json::from_str(&config)
+            .map_err(|_| rusqlite::Error::InvalidColumnName("config".to_string()))?;
+
+        Ok(SavedConnection {
+            id,
+            user_id: row.get(1)?,
+            name: row.get(2)?,
+            config,
+            created_at: row.get(4)?,
+        })
+    }
+}
+
+fn storage_err(err: impl ToString) -> AppError {
+    AppError::PersistentStorageError(err.to_string())
+}
+
+impl LocalDB {
+    pub fn save_connection(&self, conn: &SavedConnection) -> Result<usize, AppError> {
+        let config = serde_json::to_string(&conn.config).map_err(storage_err)?;
+
+        self.pool()?
+            .execute(
+                "INSERT INTO connections (id, user_id, name, config) VALUES (?1, ?2, ?3, ?4)",
+                params![conn.id.to_string(), conn.user_id, conn.name, config],
+            )
+            .map_err(storage_err)
+    }
+
+    /// Connections saved by the user, oldest first.
+    pub fn list_connections(&self, user_id: &str) -> Result<Vec<SavedConnection>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare(
+                "SELECT id, user_id, name, config, created_at FROM connections WHERE user_id = ?1 ORDER BY created_at, rowid",
+            )
+            .map_err(storage_err)?;
+
+        let conns = stmt
+            .query_map(params![user_id], SavedConnection::from_row)
+            .map_err(storage_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_err)?;
+
+        Ok(conns)
+    }
+
+    /// Connections of others the user was given a role on, with that role, oldest first.
+    pub fn list_shared_connections(&self, user_id: &str) -> Result<Vec<(SavedConnection, Role)>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare(
+                "SELECT c.id, c.user_id, c.name, c.config, c.created_at, r.role FROM connections c
+                JOIN connection_roles r ON r.conn_id = c.id
+                WHERE r.user_id = ?1 AND c.user_id != ?1
+                ORDER BY c.created_at, c.rowid",
+            )
+            .map_err(storage_err)?;
+
+        let conns = stmt
+            .query_map(params![user_id], |row| {
+                let role: String = row.get(5)?;
+                let role = role
+                    .parse()
+                    .map_err(|_| rusqlite::Error::InvalidColumnName("role".to_string()))?;
+
+                Ok((SavedConnection::from_row(row)?, role))
+            })
+            .map_err(storage_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_err)?;
+
+        Ok(conns)
+    }
+
+    /// Get the saved connection with the given id, whoever it belongs to.
+    pub fn get_connection(&self, id: &Uuid) -> Result<Option<SavedConnection>, AppError> {
+        self.pool()?
+            .query_row(
+                "SELECT id, user_id, name, config, created_at FROM connections WHERE id = ?1",
+                params![id.to_string()],
+                SavedConnection::from_row,
+            )
+            .optional()
+            .map_err(storage_err)
+    }
+
+    /// Save the name and configuration of `conn`.
+    pub fn update_connection(&self, conn: &SavedConnection) -> Result<usize, AppError> {
+        let config = serde_json::to_string(&conn.config).map_err(storage_err)?;
+
+        self.pool()?
+            .execute(
+                "UPDATE connections SET name = ?1, config = ?2 WHERE id = ?3 AND user_id = ?4",
+                params![conn.name, config, conn.id.to_string(), conn.user_id],
+            )
+            .map_err(storage_err)
+    }
+
+    /// Give every connection saved by the user `from` to the user `to`, and return how many
+    /// there were.
+    pub fn transfer_connections(&self, from: &str, to: &str) -> Result<usize, AppError> {
+        self.pool()?
+            .execute(
+                "UPDATE connections SET user_id = ?1 WHERE user_id = ?2",
+                params![to, from],
+            )
+            .map_err(storage_err)
+    }
+
+    /// Saved connections whose configuration still has a password, from before passwords were
+    /// kept in the vault.
+    pub fn connections_with_password(&self) -> Result<Vec<SavedConnection>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare(
+                "SELECT id, user_id, name, config, created_at FROM connections WHERE json_extract(config, '$.password') IS NOT NULL",
+            )
+            .map_err(storage_err)?;
+
+        let conns = stmt
+            .query_map([], SavedConnection::from_row)
+            .map_err(storage_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_err)?;
+
+        Ok(conns)
+    }
+
+    /// Delete the saved connection with the given id along with its password, table
+    /// configurations, roles and audit log, if it belongs to the user. Returns whether it was
+    /// found.
+    pub fn delete_connection(&self, conn_id: &Uuid, user_id: &str) -> Result<bool, AppError> {
+        let mut pool = self.pool()?;
+        let tx = pool.transaction().map_err(storage_err)?;
+        let id = conn_id.to_string();
+
+        let deleted = tx
+            .execute(
+                "DELETE FROM connections WHERE id = ?1 AND user_id = ?2",
+                params![id, user_id],
+            )
+            .map_err(storage_err)?;
+
+        if deleted > 0 {
+            tx.execute("DELETE FROM table_configs WHERE conn_id = ?1", params![id])
+                .map_err(storage_err)?;
+            tx.execute("DELETE FROM secrets WHERE id = ?1", params![password_secret_id(conn_id)])
+                .map_err(storage_err)?;
+            tx.execute("DELETE FROM connection_roles WHERE conn_id = ?1", params![id])
+                .map_err(storage_err)?;
+            tx.execute("DELETE FROM table_roles WHERE conn_id = ?1", params![id])
+                .map_err(storage_err)?;
+            tx.execute("DELETE FROM audit_log WHERE conn_id = ?1", params![id])
+                .map_err(storage_err)?;
+        }
+
+        tx.commit().map_err(storage_err)?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::config::ConfigRaw;
+    use uuid::Uuid;
+
+    use crate::local_db::LocalDB;
+
+    use super::SavedConnection;
+
+    #[test]
+    fn test_saved_connections() {
+        let db = LocalDB::memory().unwrap();
+        db.setup().unwrap();
+
+        let mut conn = SavedConnection {
+            id: Uuid::new_v4(),
+            user_id: "user".to_string(),
+            name: "shop".to_string(),
+            config: ConfigRaw {
+                password: Some("secret".to_string()),
+                ..Default::default()
+            },
+            created_at: String::new(),
+        };
+        db.save_connection(&conn).unwrap();
+
+        conn.name = "store".to_string();
+        db.update_connection(&conn).unwrap();
+
+        let saved = db.get_connection(&conn.id).unwrap().unwrap();
+        assert_eq!(saved.name, "store");
+        // Passwords are kept in the vault instead.
+        assert!(saved.config.password.is_none());
+
+        assert!(!db.delete_connection(&conn.id, "other").unwrap());
+        assert_eq!(db.list_connections("user").unwrap().len(), 1);
+
+        assert!(db.delete_connection(&conn.id, "user").unwrap());
+        assert!(db.list_connections("user").unwrap().is_empty());
+    }
+}
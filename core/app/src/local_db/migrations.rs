@@ -0,0 +1,152 @@
+use common::error::AppError;
+use rusqlite::Connection;
+
+/// Schema migrations of [`LocalDB`](super::LocalDB), applied in order. The number of applied
+/// migrations is kept as the schema version in SQLite's `user_version` pragma, so each of them
+/// only ever runs once per database.
+///
+/// Released migrations must never be edited or reordered. Schema changes go into a new migration
+/// at the end of the list.
+const MIGRATIONS: &[&str] = &[
+    // 1: table configurations. Databases created before migrations were introduced already
+    // have this table.
+    "CREATE TABLE IF NOT EXISTS table_configs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        conn_id TEXT NOT NULL,
+        label TEXT,
+        pk_column TEXT,
+        ipp INTEGER,
+        exclude_columns TEXT
+    );",
+    // 2: saved connections. Table configurations are created again whenever a saved connection
+    // is restored, so they are made unique per connection, keeping the oldest of any duplicates.
+    "CREATE TABLE connections (
+        id TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        config TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX connections_user_id ON connections (user_id);
+    DELETE FROM table_configs
+        WHERE id NOT IN (SELECT MIN(id) FROM table_configs GROUP BY conn_id, name);
+    CREATE UNIQUE INDEX table_configs_conn_id_name ON table_configs (conn_id, name);",
+    // 3: credentials encrypted by the vault. Passwords saved in connection configurations
+    // before are moved here by `Vault::seal_saved_passwords`.
+    "CREATE TABLE secrets (
+        id TEXT PRIMARY KEY,
+        key_id TEXT NOT NULL,
+        value TEXT NOT NULL
+    );",
+    // 4: registered users. Guests aren't stored.
+    "CREATE TABLE users (
+        id TEXT PRIMARY KEY,
+        email TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        password_hash TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );",
+    // 5: refresh tokens, stored hashed, and revoked access tokens. Expiry times are Unix
+    // timestamps.
+    "CREATE TABLE refresh_tokens (
+        token_hash TEXT PRIMARY KEY,
+        family TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        is_guest INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL,
+        used INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX refresh_tokens_family ON refresh_tokens (family);
+    CREATE TABLE revoked_tokens (
+        jti TEXT PRIMARY KEY,
+        expires_at INTEGER NOT NULL
+    );",
+    // 6: roles of users on connections they don't own, and on their tables.
+    "CREATE TABLE connection_roles (
+        conn_id TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        role TEXT NOT NULL CHECK (role IN ('viewer', 'analyst', 'editor', 'admin')),
+        PRIMARY KEY (conn_id, user_id)
+    );
+    CREATE TABLE table_roles (
+        conn_id TEXT NOT NULL,
+        user_id TEXT NOT NULL,
+        table_name TEXT NOT NULL,
+        role TEXT NOT NULL CHECK (role IN ('viewer', 'analyst', 'editor', 'admin')),
+        PRIMARY KEY (conn_id, user_id, table_name)
+    );",
+    // 7: record of data changes made through Basable.
+    "CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id TEXT NOT NULL,
+        conn_id TEXT NOT NULL,
+        table_name TEXT NOT NULL,
+        operation TEXT NOT NULL,
+        key_column TEXT,
+        keys TEXT NOT NULL,
+        before TEXT,
+        after TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX audit_log_conn_id ON audit_log (conn_id, created_at);",
+    // 8: API keys, for programmatic access.
+    "CREATE TABLE api_keys (
+        id TEXT PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        key_hash TEXT NOT NULL UNIQUE,
+        scope TEXT NOT NULL CHECK (scope IN ('read_only', 'full')),
+        expires_at INTEGER,
+        created_at INTEGER NOT NULL,
+        last_used_at INTEGER
+    );
+    CREATE INDEX api_keys_user_id ON api_keys (user_id);",
+];
+
+/// Apply migrations newer than the schema version of `conn`, and return how many were applied.
+///
+/// All pending migrations run in a single transaction, so a failing migration leaves the
+/// database as it was.
+pub(super) fn migrate(conn: &mut Connection) -> Result<usize, AppError> {
+    let map_err = |err: rusqlite::Error| AppError::PersistentStorageError(err.to_string());
+
+    let version: usize = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(map_err)?;
+
+    if version > MIGRATIONS.len() {
+        return Err(AppError::PersistentStorageError(format!(
+            "Local database schema version {version} is newer than this release of Basable supports ({})",
+            MIGRATIONS.len()
+        )));
+    }
+
+    let tx = conn.transaction().map_err(map_err)?;
+
+    for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(migration).map_err(|err| {
+            AppError::PersistentStorageError(format!("Migration {} failed: {err}", idx + 1))
+        })?;
+    }
+
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())
+        .map_err(map_err)?;
+    tx.commit().map_err(map_err)?;
+
+    Ok(MIGRATIONS.len() - version)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::local_db::LocalDB;
+
+    use super::MIGRATIONS;
+
+    #[test]
+    fn test_migrate() {
+        let db = LocalDB::memory().unwrap();
+
+        assert_eq!(db.setup().unwrap(), MIGRATIONS.len());
+        assert_eq!(db.setup().unwrap(), 0);
+    }
+}
@@ -0,0 +1,79 @@
+use common::error::AppError;
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+
+use crate::utils::get_env;
+
+mod api_keys;
+mod audit;
+mod connections;
+mod migrations;
+mod roles;
+mod secrets;
+mod sessions;
+mod table_configs;
+mod users;
+
+pub(crate) use api_keys::ApiKey;
+pub(crate) use audit::{AuditEntry, AuditFilter};
+pub(crate) use connections::{password_secret_id, SavedConnection};
+pub(crate) use roles::RoleAssignment;
+pub(crate) use secrets::SealedSecret;
+pub(crate) use sessions::{RefreshToken, RefreshTokenUse};
+pub(crate) use users::Account;
+
+/// Where [`LocalDB`] is stored, when `BASABLE_LOCAL_DB` is not set.
+const DEFAULT_LOCAL_DB_PATH: &str = "basable.db";
+
+/// Basable's own storage, such as user accounts, sessions and API keys, saved connections,
+/// their encrypted credentials, table configurations and the audit log. It is kept in an
+/// SQLite file, whose schema is brought up to date by [`LocalDB::setup`].
+#[derive(Clone)]
+pub struct LocalDB(pub Pool<SqliteConnectionManager>);
+
+impl LocalDB {
+    /// Open the SQLite file at `path`, creating it if it doesn't exist.
+    pub fn open(path: &str) -> Result<Self, AppError> {
+        let manager = SqliteConnectionManager::file(path).with_init(|conn| {
+            conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA busy_timeout = 5000;")
+        });
+
+        let pool = Pool::new(manager).map_err(|err| AppError::InitError(err.to_string()))?;
+        Ok(LocalDB(pool))
+    }
+
+    /// Open [`LocalDB`] at the path set by the `BASABLE_LOCAL_DB` environment variable.
+    pub fn from_env() -> Result<Self, AppError> {
+        Self::open(&Self::env_path())
+    }
+
+    /// The path set by the `BASABLE_LOCAL_DB` environment variable, or the default one.
+    pub fn env_path() -> String {
+        get_env("BASABLE_LOCAL_DB").unwrap_or_else(|_| DEFAULT_LOCAL_DB_PATH.to_string())
+    }
+
+    /// Open a private in-memory database, which is lost once dropped.
+    #[cfg(test)]
+    pub fn memory() -> Result<Self, AppError> {
+        // A single connection, since each in-memory connection would get its own database.
+        let manager = SqliteConnectionManager::memory();
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(manager)
+            .map_err(|err| AppError::InitError(err.to_string()))?;
+
+        Ok(LocalDB(pool))
+    }
+
+    fn pool(&self) -> Result<PooledConnection<SqliteConnectionManager>, AppError> {
+        self.0
+            .get()
+            .map_err(|err| AppError::PersistentStorageError(err.to_string()))
+    }
+
+    /// Apply pending schema migrations, and return how many were applied.
+    pub fn setup(&self) -> Result<usize, AppError> {
+        let mut conn = self.pool()?;
+        migrations::migrate(&mut conn)
+    }
+}
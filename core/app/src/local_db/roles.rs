@@ -0,0 +1,206 @@
+use common::error::AppError;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::roles::Role;
+
+use super::LocalDB;
+
+/// A role given to a user on a connection, or on one of its tables if `table` is set.
+#[derive(Serialize)]
+pub(crate) struct RoleAssignment {
+    pub user_id: String,
+
+    /// Email of the user, unless their account was deleted.
+    pub email: Option<String>,
+
+    pub table: Option<String>,
+    pub role: Role,
+}
+
+impl RoleAssignment {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let role: String = row.get(3)?;
+        let role = role
+            .parse()
+            .map_err(|_| rusqlite::Error::InvalidColumnName("role".to_string()))?;
+
+        Ok(RoleAssignment {
+            user_id: row.get(0)?,
+            email: row.get(1)?,
+            table: row.get(2)?,
+            role,
+        })
+    }
+}
+
+fn storage_err(err: rusqlite::Error) -> AppError {
+    AppError::PersistentStorageError(err.to_string())
+}
+
+fn parse_role(role: Option<String>) -> rusqlite::Result<Option<Role>> {
+    role.map(|role| {
+        role.parse()
+            .map_err(|_| rusqlite::Error::InvalidColumnName("role".to_string()))
+    })
+    .transpose()
+}
+
+impl LocalDB {
+    /// Give the user `role` on the connection, or on its `table`, replacing any role they had
+    /// there.
+    pub fn set_role(
+        &self,
+        conn_id: &Uuid,
+        user_id: &str,
+        table: Option<&str>,
+        role: Role,
+    ) -> Result<usize, AppError> {
+        let conn = self.pool()?;
+
+        match table {
+            Some(table) => conn.execute(
+                "INSERT OR REPLACE INTO table_roles (conn_id, user_id, table_name, role) VALUES (?1, ?2, ?3, ?4)",
+                params![conn_id.to_string(), user_id, table, role.as_str()],
+            ),
+            None => conn.execute(
+                "INSERT OR REPLACE INTO connection_roles (conn_id, user_id, role) VALUES (?1, ?2, ?3)",
+                params![conn_id.to_string(), user_id, role.as_str()],
+            ),
+        }
+        .map_err(storage_err)
+    }
+
+    /// Take back the role of the user on the connection, or on its `table`. Without a role on
+    /// the connection, the user loses access to it, along with their roles on its tables.
+    pub fn remove_role(&self, conn_id: &Uuid, user_id: &str, table: Option<&str>) -> Result<bool, AppError> {
+        let mut conn = self.pool()?;
+        let id = conn_id.to_string();
+
+        let removed = match table {
+            Some(table) => conn
+                .execute(
+                    "DELETE FROM table_roles WHERE conn_id = ?1 AND user_id = ?2 AND table_name = ?3",
+                    params![id, user_id, table],
+                )
+                .map_err(storage_err)?,
+            None => {
+                let tx = conn.transaction().map_err(storage_err)?;
+                let removed = tx
+                    .execute(
+                        "DELETE FROM connection_roles WHERE conn_id = ?1 AND user_id = ?2",
+                        params![id, user_id],
+                    )
+                    .map_err(storage_err)?;
+                tx.execute(
+                    "DELETE FROM table_roles WHERE conn_id = ?1 AND user_id = ?2",
+                    params![id, user_id],
+                )
+                .map_err(storage_err)?;
+                tx.commit().map_err(storage_err)?;
+
+                removed
+            }
+        };
+
+        Ok(removed > 0)
+    }
+
+    /// Roles given on the connection and its tables, by user.
+    pub fn list_roles(&self, conn_id: &Uuid) -> Result<Vec<RoleAssignment>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare(
+                "SELECT r.user_id, u.email, r.table_name, r.role FROM (
+                    SELECT user_id, NULL AS table_name, role FROM connection_roles WHERE conn_id = ?1
+                    UNION ALL
+                    SELECT user_id, table_name, role FROM table_roles WHERE conn_id = ?1
+                ) r
+                LEFT JOIN users u ON u.id = r.user_id
+                ORDER BY u.email, r.user_id, r.table_name",
+            )
+            .map_err(storage_err)?;
+
+        let roles = stmt
+            .query_map(params![conn_id.to_string()], RoleAssignment::from_row)
+            .map_err(storage_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_err)?;
+
+        Ok(roles)
+    }
+
+    /// The role of the user on the saved connection, or on its `table` if set. The owner is
+    /// admin of the connection and all its tables. Other users have no role unless given one
+    /// on the connection, which their role on the table overrides.
+    pub fn role(&self, conn_id: &Uuid, user_id: &str, table: Option<&str>) -> Result<Option<Role>, AppError> {
+        let found = self
+            .pool()?
+            .query_row(
+                "SELECT c.user_id = ?2, cr.role, tr.role FROM connections c
+                LEFT JOIN connection_roles cr ON cr.conn_id = c.id AND cr.user_id = ?2
+                LEFT JOIN table_roles tr ON tr.conn_id = c.id AND tr.user_id = ?2 AND tr.table_name = ?3
+                WHERE c.id = ?1",
+                params![conn_id.to_string(), user_id, table],
+                |row| {
+                    Ok((
+                        row.get::<_, bool>(0)?,
+                        parse_role(row.get(1)?)?,
+                        parse_role(row.get(2)?)?,
+                    ))
+                },
+            )
+            .optional()
+            .map_err(storage_err)?;
+
+        let role = match found {
+            Some((true, _, _)) => Some(Role::Admin),
+            Some((false, Some(conn_role), table_role)) => Some(table_role.unwrap_or(conn_role)),
+            _ => None,
+        };
+
+        Ok(role)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use base::config::ConfigRaw;
+    use uuid::Uuid;
+
+    use crate::{
+        local_db::{LocalDB, SavedConnection},
+        roles::Role,
+    };
+
+    #[test]
+    fn test_roles() {
+        let db = LocalDB::memory().unwrap();
+        db.setup().unwrap();
+
+        let conn = SavedConnection {
+            id: Uuid::new_v4(),
+            user_id: "owner".to_string(),
+            name: "Sales".to_string(),
+            config: ConfigRaw::default(),
+            created_at: String::new(),
+        };
+        db.save_connection(&conn).unwrap();
+
+        assert_eq!(db.role(&conn.id, "owner", Some("orders")).unwrap(), Some(Role::Admin));
+
+        // A role on a table is of no use without one on the connection.
+        db.set_role(&conn.id, "ada", Some("orders"), Role::Editor).unwrap();
+        assert_eq!(db.role(&conn.id, "ada", Some("orders")).unwrap(), None);
+
+        db.set_role(&conn.id, "ada", None, Role::Viewer).unwrap();
+        assert_eq!(db.role(&conn.id, "ada", None).unwrap(), Some(Role::Viewer));
+        assert_eq!(db.role(&conn.id, "ada", Some("orders")).unwrap(), Some(Role::Editor));
+        assert_eq!(db.role(&conn.id, "ada", Some("users")).unwrap(), Some(Role::Viewer));
+
+        assert!(db.remove_role(&conn.id, "ada", None).unwrap());
+        assert_eq!(db.role(&conn.id, "ada", Some("orders")).unwrap(), None);
+        assert!(db.list_roles(&conn.id).unwrap().is_empty());
+    }
+}
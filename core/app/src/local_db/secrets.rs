@@ -0,0 +1,66 @@
+use common::error::AppError;
+use rusqlite::{params, OptionalExtension, Row};
+
+use super::LocalDB;
+
+/// A secret encrypted by the [`Vault`](crate::vault::Vault).
+pub(crate) struct SealedSecret {
+    pub id: String,
+
+    /// Id of the key `value` is encrypted with.
+    pub key_id: String,
+    pub value: String,
+}
+
+impl SealedSecret {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(SealedSecret {
+            id: row.get(0)?,
+            key_id: row.get(1)?,
+            value: row.get(2)?,
+        })
+    }
+}
+
+fn storage_err(err: rusqlite::Error) -> AppError {
+    AppError::PersistentStorageError(err.to_string())
+}
+
+impl LocalDB {
+    /// Save `secret`, replacing any secret with the same id.
+    pub fn put_secret(&self, secret: &SealedSecret) -> Result<usize, AppError> {
+        self.pool()?
+            .execute(
+                "INSERT OR REPLACE INTO secrets (id, key_id, value) VALUES (?1, ?2, ?3)",
+                params![secret.id, secret.key_id, secret.value],
+            )
+            .map_err(storage_err)
+    }
+
+    pub fn get_secret(&self, id: &str) -> Result<Option<SealedSecret>, AppError> {
+        self.pool()?
+            .query_row(
+                "SELECT id, key_id, value FROM secrets WHERE id = ?1",
+                params![id],
+                SealedSecret::from_row,
+            )
+            .optional()
+            .map_err(storage_err)
+    }
+
+    /// Secrets not encrypted with the key `key_id`.
+    pub fn secrets_not_sealed_with(&self, key_id: &str) -> Result<Vec<SealedSecret>, AppError> {
+        let pool = self.pool()?;
+        let mut stmt = pool
+            .prepare("SELECT id, key_id, value FROM secrets WHERE key_id != ?1")
+            .map_err(storage_err)?;
+
+        let secrets = stmt
+            .query_map(params![key_id], SealedSecret::from_row)
+            .map_err(storage_err)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(storage_err)?;
+
+        Ok(secrets)
+    }
+}
@@ -0,0 +1,140 @@
+use common::error::AppError;
+use rusqlite::{params, OptionalExtension, Row, TransactionBehavior};
+
+use super::LocalDB;
+
+/// A refresh token, identified by its hash. Tokens refreshed from one another share a family.
+pub(crate) struct RefreshToken {
+    pub token_hash: String,
+    pub family: String,
+    pub user_id: String,
+    pub is_guest: bool,
+
+    /// Unix timestamp the token expires at.
+    pub expires_at: i64,
+}
+
+impl RefreshToken {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(RefreshToken {
+            token_hash: row.get(0)?,
+            family: row.get(1)?,
+            user_id: row.get(2)?,
+            is_guest: row.get(3)?,
+            expires_at: row.get(4)?,
+        })
+    }
+}
+
+/// Outcome of [`LocalDB::use_refresh_token`].
+pub(crate) enum RefreshTokenUse {
+    /// The token was valid, and can't be used again.
+    Used(RefreshToken),
+
+    /// The token was used before, so its whole family was revoked.
+    Reused,
+
+    /// There's no such token, or it expired.
+    Invalid,
+}
+
+fn storage_err(err: rusqlite::Error) -> AppError {
+    AppError::PersistentStorageError(err.to_string())
+}
+
+impl LocalDB {
+    /// Save `token`, and drop refresh tokens that expired before `now`.
+    pub fn create_refresh_token(&self, token: &RefreshToken, now: i64) -> Result<(), AppError> {
+        let conn = self.pool()?;
+
+        conn.execute("DELETE FROM refresh_tokens WHERE expires_at <= ?1", params![now])
+            .map_err(storage_err)?;
+        conn.execute(
+            "INSERT INTO refresh_tokens (token_hash, family, user_id, is_guest, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![token.token_hash, token.family, token.user_id, token.is_guest, token.expires_at],
+        )
+        .map_err(storage_err)?;
+
+        Ok(())
+    }
+
+    /// Mark the refresh token with hash `token_hash` as used, if it's valid at `now`.
+    ///
+    /// A refresh token used twice was likely stolen, so its family is revoked, which ends the
+    /// session for both the legitimate user and the thief.
+    pub fn use_refresh_token(&self, token_hash: &str, now: i64) -> Result<RefreshTokenUse, AppError> {
+        let mut conn = self.pool()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(storage_err)?;
+
+        let token = tx
+            .query_row(
+                "SELECT token_hash, family, user_id, is_guest, expires_at, used FROM refresh_tokens WHERE token_hash = ?1",
+                params![token_hash],
+                |row| Ok((RefreshToken::from_row(row)?, row.get::<_, bool>(5)?)),
+            )
+            .optional()
+            .map_err(storage_err)?;
+
+        let outcome = match token {
+            Some((token, true)) => {
+                tx.execute("DELETE FROM refresh_tokens WHERE family = ?1", params![token.family])
+                    .map_err(storage_err)?;
+                RefreshTokenUse::Reused
+            }
+            Some((token, false)) if token.expires_at > now => {
+                tx.execute(
+                    "UPDATE refresh_tokens SET used = 1 WHERE token_hash = ?1",
+                    params![token_hash],
+                )
+                .map_err(storage_err)?;
+                RefreshTokenUse::Used(token)
+            }
+            _ => RefreshTokenUse::Invalid,
+        };
+
+        tx.commit().map_err(storage_err)?;
+        Ok(outcome)
+    }
+
+    /// Revoke the family of the refresh token with hash `token_hash`, if it belongs to the user.
+    pub fn revoke_refresh_token(&self, token_hash: &str, user_id: &str) -> Result<usize, AppError> {
+        self.pool()?
+            .execute(
+                "DELETE FROM refresh_tokens WHERE family = (
+                    SELECT family FROM refresh_tokens WHERE token_hash = ?1 AND user_id = ?2
+                )",
+                params![token_hash, user_id],
+            )
+            .map_err(storage_err)
+    }
+
+    /// Deny the access token `jti` until it expires at `expires_at`, and drop denied tokens
+    /// that expired before `now`.
+    pub fn revoke_access_token(&self, jti: &str, expires_at: i64, now: i64) -> Result<(), AppError> {
+        let conn = self.pool()?;
+
+        conn.execute("DELETE FROM revoked_tokens WHERE expires_at <= ?1", params![now])
+            .map_err(storage_err)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO revoked_tokens (jti, expires_at) VALUES (?1, ?2)",
+            params![jti, expires_at],
+        )
+        .map_err(storage_err)?;
+
+        Ok(())
+    }
+
+    pub fn is_access_token_revoked(&self, jti: &str) -> Result<bool, AppError> {
+        self.pool()?
+            .query_row(
+                "SELECT 1 FROM revoked_tokens WHERE jti = ?1",
+                params![jti],
+                |_| Ok(()),
+            )
+            .optional()
+            .map(|found| found.is_some())
+            .map_err(storage_err)
+    }
+}
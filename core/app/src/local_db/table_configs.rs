@@ -0,0 +1,87 @@
+use axum::http::StatusCode;
+use common::{data::table::TableConfig, error::AppError};
+use rusqlite::params;
+
+use super::LocalDB;
+
+impl LocalDB {
+    /// Save the configuration of a table, unless the connection already has one for it.
+    pub fn create_table_config(&self, conn_id: &str, tc: TableConfig) -> Result<usize, AppError> {
+        match self.pool() {
+            Ok(pool) => {
+                let exclude_columns =
+                    serde_json::to_string(&tc.exclude_columns).map_err(|err| {
+                        AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+                    })?;
+
+                let exec = pool.execute(
+                    "
+                    INSERT OR IGNORE INTO table_configs (conn_id, label, pk_column, name, ipp, exclude_columns)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ",
+                    params![conn_id, tc.label, tc.pk_column, tc.name, tc.items_per_page, exclude_columns],
+                );
+
+                exec.map_err(|err| {
+                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+                })
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn update_table_config(
+        &self,
+        name: &str,
+        conn_id: &str,
+        tc: TableConfig,
+    ) -> Result<usize, AppError> {
+        match self.pool() {
+            Ok(pool) => {
+                let exclude_columns =
+                    serde_json::to_string(&tc.exclude_columns).map_err(|err| {
+                        AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+                    })?;
+
+                let exec = pool.execute(
+                    "UPDATE table_configs SET name = ?, label = ?, pk_column = ?, ipp = ?, exclude_columns = ? WHERE name = ? AND conn_id = ?", 
+                    params![tc.name, tc.label, tc.pk_column, tc.items_per_page, exclude_columns, name, conn_id]
+                );
+
+                exec.map_err(|err| {
+                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+                })
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn get_table_config(&self, id: &str, conn_id: &str) -> Result<TableConfig, AppError> {
+        match self.pool() {
+            Ok(pool) => {
+                let tc = pool.query_row(
+                    "SELECT name, label, pk_column, ipp, exclude_columns FROM table_configs WHERE (name = ?1 OR label = ?1) AND conn_id = ?2 LIMIT 1",
+                    params![id, conn_id],
+                    |row| {
+                        let excs: String = row.get(4)?;
+                        let exclude_columns: Option<Vec<String>> = serde_json::from_str(&excs).map_err(|_| rusqlite::Error::InvalidColumnName("exclude_columns".to_string()))?;
+                        
+                        Ok(TableConfig {
+                            name: row.get(0)?,
+                            label: row.get(1)?,
+                            pk_column: row.get(2)?,
+                            items_per_page: row.get(3)?,
+                            exclude_columns,
+                            ..Default::default()
+                        })
+                    },
+                );
+
+                tc.map_err(|err| {
+                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+                })
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use axum::http::StatusCode;
+use common::error::AppError;
+use rusqlite::{params, ErrorCode, OptionalExtension, Row};
+
+use super::LocalDB;
+
+/// A registered user.
+pub(crate) struct Account {
+    pub id: String,
+
+    /// Unique regardless of case.
+    pub email: String,
+
+    /// Argon2 hash of the password, in PHC string format.
+    pub password_hash: String,
+}
+
+impl Account {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Account {
+            id: row.get(0)?,
+            email: row.get(1)?,
+            password_hash: row.get(2)?,
+        })
+    }
+}
+
+fn storage_err(err: rusqlite::Error) -> AppError {
+    AppError::PersistentStorageError(err.to_string())
+}
+
+impl LocalDB {
+    pub fn create_account(&self, account: &Account) -> Result<usize, AppError> {
+        self.pool()?
+            .execute(
+                "INSERT INTO users (id, email, password_hash) VALUES (?1, ?2, ?3)",
+                params![account.id, account.email, account.password_hash],
+            )
+            .map_err(|err| match err.sqlite_error_code() {
+                Some(ErrorCode::ConstraintViolation) => AppError::HttpError(
+                    StatusCode::CONFLICT,
+                    "An account with this email already exists".to_string(),
+                ),
+                _ => storage_err(err),
+            })
+    }
+
+    pub fn get_account(&self, id: &str) -> Result<Option<Account>, AppError> {
+        self.pool()?
+            .query_row(
+                "SELECT id, email, password_hash FROM users WHERE id = ?1",
+                params![id],
+                Account::from_row,
+            )
+            .optional()
+            .map_err(storage_err)
+    }
+
+    /// Get the account registered with `email`, in any case.
+    pub fn get_account_by_email(&self, email: &str) -> Result<Option<Account>, AppError> {
+        self.pool()?
+            .query_row(
+                "SELECT id, email, password_hash FROM users WHERE email = ?1",
+                params![email],
+                Account::from_row,
+            )
+            .optional()
+            .map_err(storage_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::local_db::LocalDB;
+
+    use super::Account;
+
+    #[test]
+    fn test_unique_email() {
+        let db = LocalDB::memory().unwrap();
+        db.setup().unwrap();
+
+        let account = |id: &str, email: &str| Account {
+            id: id.to_string(),
+            email: email.to_string(),
+            password_hash: String::new(),
+        };
+
+        db.create_account(&account("a", "ada@example.com")).unwrap();
+        assert!(db.create_account(&account("b", "Ada@Example.com")).is_err());
+
+        let found = db.get_account_by_email("ADA@example.com").unwrap().unwrap();
+        assert_eq!(found.id, "a");
+    }
+}
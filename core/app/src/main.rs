@@ -1,15 +1,25 @@
+use std::env;
+
 use common::error::AppError;
 use dotenv::dotenv;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utils::get_env;
 
-use crate::http::app::app;
+use crate::{http::app::app, local_db::LocalDB, vault::Vault};
 
+mod api_key;
+mod audit;
+mod blocking;
 mod foundation;
 mod http;
+mod local_db;
+mod presence;
+mod roles;
+mod session;
 mod state;
 mod user;
 mod utils;
+mod vault;
 
 enum DeploymentMode {
     Cloud,
@@ -17,10 +27,7 @@ enum DeploymentMode {
 }
 impl DeploymentMode {
     fn is_local(&self) -> bool {
-        match self {
-            DeploymentMode::Local => true,
-            _ => false,
-        }
+        matches!(self, DeploymentMode::Local)
     }
 }
 impl From<String> for DeploymentMode {
@@ -33,6 +40,19 @@ impl From<String> for DeploymentMode {
     }
 }
 
+/// Encrypt every stored secret with the current master key, so previous ones can be dropped.
+fn reencrypt() -> Result<(), AppError> {
+    let local_db = LocalDB::from_env()?;
+    local_db.setup()?;
+
+    let vault = Vault::from_env(local_db)?;
+    let sealed = vault.seal_saved_passwords()?;
+    let reencrypted = vault.reencrypt()?;
+
+    tracing::info!("Re-encrypted {reencrypted} secrets, and moved {sealed} saved passwords to the vault");
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
     dotenv().ok();
@@ -45,6 +65,10 @@ async fn main() -> Result<(), AppError> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    if env::args().nth(1).as_deref() == Some("re-encrypt") {
+        return reencrypt();
+    }
+
     let port = get_env("BASABLE_PORT").map_err(|err| AppError::InitError(err.to_string()))?;
     let app = app()?;
 
@@ -0,0 +1,54 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+/// How long in seconds a user is seen as working on a connection after their last request on it.
+const ACTIVE_WINDOW: i64 = 5 * 60;
+
+/// Keeps track of who is working on which connection, from when they last used it.
+///
+/// Presence only matters while users are around, so it's kept in memory and lost on restart.
+#[derive(Clone, Default)]
+pub(crate) struct Presence(Arc<Mutex<HashMap<Uuid, HashMap<String, i64>>>>);
+
+impl Presence {
+    /// Record that the user just used the connection.
+    pub fn touch(&self, conn_id: &Uuid, user_id: &str) {
+        let now = Utc::now().timestamp();
+        let mut conns = self.0.lock().unwrap_or_else(|err| err.into_inner());
+
+        // Users who left are dropped as others come by, so the map doesn't grow forever.
+        conns.retain(|_, users| {
+            users.retain(|_, last_seen| now - *last_seen < ACTIVE_WINDOW);
+            !users.is_empty()
+        });
+
+        conns
+            .entry(*conn_id)
+            .or_default()
+            .insert(user_id.to_string(), now);
+    }
+
+    /// Users who worked on the connection lately, with the Unix timestamp they were last seen
+    /// at, most recent first.
+    pub fn active(&self, conn_id: &Uuid) -> Vec<(String, i64)> {
+        let now = Utc::now().timestamp();
+        let conns = self.0.lock().unwrap_or_else(|err| err.into_inner());
+
+        let mut users: Vec<_> = conns
+            .get(conn_id)
+            .into_iter()
+            .flatten()
+            .filter(|(_, last_seen)| now - **last_seen < ACTIVE_WINDOW)
+            .map(|(user_id, last_seen)| (user_id.clone(), *last_seen))
+            .collect();
+
+        users.sort_by_key(|(_, last_seen)| Reverse(*last_seen));
+        users
+    }
+}
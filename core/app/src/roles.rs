@@ -0,0 +1,79 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// What a user may do on a connection, or on one of its tables. Each role may do everything
+/// the roles before it may.
+///
+/// The owner of a connection is its [`Role::Admin`]. Other users only have access to it through
+/// a role on the connection, which roles on its tables then override.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Role {
+    /// Browse tables and their data.
+    Viewer,
+
+    /// Also run graphs and export data.
+    Analyst,
+
+    /// Also insert, update and delete data.
+    Editor,
+
+    /// Also configure tables, and manage roles of other users.
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Analyst => "analyst",
+            Role::Editor => "editor",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "analyst" => Ok(Role::Analyst),
+            "editor" => Ok(Role::Editor),
+            "admin" => Ok(Role::Admin),
+            _ => Err(format!("Unknown role '{s}'")),
+        }
+    }
+}
+
+/// The least [`Role`] a route requires, as a type for
+/// [`RequireRole`](crate::http::middlewares::RequireRole).
+pub(crate) trait RequiredRole {
+    const ROLE: Role;
+}
+
+pub(crate) struct Analyst;
+
+impl RequiredRole for Analyst {
+    const ROLE: Role = Role::Analyst;
+}
+
+pub(crate) struct Editor;
+
+impl RequiredRole for Editor {
+    const ROLE: Role = Role::Editor;
+}
+
+pub(crate) struct Admin;
+
+impl RequiredRole for Admin {
+    const ROLE: Role = Role::Admin;
+}
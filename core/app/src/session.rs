@@ -0,0 +1,178 @@
+use aes_gcm::aead::{rand_core::RngCore, OsRng};
+use axum::http::StatusCode;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use common::error::AppError;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    local_db::{LocalDB, RefreshToken, RefreshTokenUse},
+    user::{create_jwt, Claims, JwtSession, User},
+    utils::get_env,
+};
+
+/// Lifetime of access tokens in seconds, when `BASABLE_ACCESS_TOKEN_TTL` is not set.
+const DEFAULT_ACCESS_TOKEN_TTL: i64 = 2 * 60 * 60;
+
+/// Lifetime of refresh tokens in seconds, when `BASABLE_REFRESH_TOKEN_TTL` is not set.
+const DEFAULT_REFRESH_TOKEN_TTL: i64 = 30 * 24 * 60 * 60;
+
+/// Starts, refreshes and ends user sessions.
+///
+/// A session is made of a short-lived JWT access token, and a refresh token to get new tokens
+/// with. Refresh tokens are random, single-use, and only their hash is kept in [`LocalDB`]. Each
+/// refresh replaces the refresh token with a new one of the same family. Access tokens can't be
+/// taken back once issued, so those of ended sessions are denied until they expire.
+#[derive(Clone)]
+pub(crate) struct Sessions {
+    db: LocalDB,
+
+    /// Lifetime of access tokens, in seconds.
+    access_ttl: i64,
+
+    /// Lifetime of refresh tokens, in seconds.
+    refresh_ttl: i64,
+}
+
+impl Sessions {
+    pub fn new(db: LocalDB, access_ttl: i64, refresh_ttl: i64) -> Self {
+        Sessions {
+            db,
+            access_ttl,
+            refresh_ttl,
+        }
+    }
+
+    /// Create [`Sessions`] with token lifetimes set by the `BASABLE_ACCESS_TOKEN_TTL` and
+    /// `BASABLE_REFRESH_TOKEN_TTL` environment variables, in seconds.
+    pub fn from_env(db: LocalDB) -> Result<Self, AppError> {
+        let access_ttl = ttl_from_env("BASABLE_ACCESS_TOKEN_TTL", DEFAULT_ACCESS_TOKEN_TTL)?;
+        let refresh_ttl = ttl_from_env("BASABLE_REFRESH_TOKEN_TTL", DEFAULT_REFRESH_TOKEN_TTL)?;
+
+        Ok(Self::new(db, access_ttl, refresh_ttl))
+    }
+
+    /// Start a new session for `user`.
+    pub fn start(&self, user: &User) -> Result<JwtSession, AppError> {
+        self.issue(user, Uuid::new_v4().to_string())
+    }
+
+    /// Get new tokens for the session of `refresh_token`, which can't be used again.
+    pub fn refresh(&self, refresh_token: &str) -> Result<JwtSession, AppError> {
+        let now = Utc::now().timestamp();
+
+        match self.db.use_refresh_token(&hash_token(refresh_token), now)? {
+            RefreshTokenUse::Used(token) => {
+                let user = User {
+                    id: token.user_id,
+                    is_guest: token.is_guest,
+                    max_role: None,
+                };
+                self.issue(&user, token.family)
+            }
+            RefreshTokenUse::Reused => Err(AppError::HttpError(
+                StatusCode::UNAUTHORIZED,
+                "Refresh token was already used. The session has been ended.".to_string(),
+            )),
+            RefreshTokenUse::Invalid => Err(AppError::HttpError(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired refresh token".to_string(),
+            )),
+        }
+    }
+
+    /// End the session the access token with `claims` belongs to. Its `refresh_token` is
+    /// revoked along with every token refreshed from the same one.
+    pub fn end(&self, claims: &Claims, refresh_token: Option<&str>) -> Result<(), AppError> {
+        if let Some(refresh_token) = refresh_token {
+            self.db
+                .revoke_refresh_token(&hash_token(refresh_token), &claims.sub)?;
+        }
+
+        let now = Utc::now().timestamp();
+        self.db
+            .revoke_access_token(&claims.jti, claims.exp as i64, now)
+    }
+
+    /// Whether the access token with `claims` belongs to a session that was ended.
+    pub fn is_revoked(&self, claims: &Claims) -> Result<bool, AppError> {
+        self.db.is_access_token_revoked(&claims.jti)
+    }
+
+    fn issue(&self, user: &User, family: String) -> Result<JwtSession, AppError> {
+        let token = create_jwt(user, self.access_ttl)?;
+
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let refresh_token = URL_SAFE_NO_PAD.encode(bytes);
+
+        let now = Utc::now().timestamp();
+        self.db.create_refresh_token(
+            &RefreshToken {
+                token_hash: hash_token(&refresh_token),
+                family,
+                user_id: user.id.clone(),
+                is_guest: user.is_guest,
+                expires_at: now + self.refresh_ttl,
+            },
+            now,
+        )?;
+
+        Ok(JwtSession {
+            token,
+            exp: self.access_ttl as usize,
+            refresh_token,
+            refresh_exp: self.refresh_ttl as usize,
+        })
+    }
+}
+
+/// Refresh tokens and API keys are random, so a fast hash is enough to keep them from being
+/// usable if storage leaks.
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn ttl_from_env(key: &str, default: i64) -> Result<i64, AppError> {
+    match get_env(key) {
+        Ok(ttl) => ttl
+            .parse::<i64>()
+            .ok()
+            .filter(|ttl| *ttl > 0)
+            .ok_or_else(|| AppError::InitError(format!("Invalid {key} '{ttl}'"))),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{local_db::LocalDB, user::User};
+
+    use super::Sessions;
+
+    #[test]
+    fn test_refresh_rotation() {
+        std::env::set_var("BASABLE_JWT_SECRET", "test secret");
+
+        let db = LocalDB::memory().unwrap();
+        db.setup().unwrap();
+        let sessions = Sessions::new(db, 60, 600);
+
+        let user = User {
+            id: "ada".to_string(),
+            is_guest: false,
+            max_role: None,
+        };
+        let first = sessions.start(&user).unwrap();
+        let second = sessions.refresh(&first.refresh_token).unwrap();
+        assert_ne!(first.refresh_token, second.refresh_token);
+
+        // Reusing a refresh token ends the session, including tokens refreshed from it.
+        assert!(sessions.refresh(&first.refresh_token).is_err());
+        assert!(sessions.refresh(&second.refresh_token).is_err());
+    }
+}
@@ -1,137 +1,157 @@
-use std::sync::{Arc, Mutex};
+use std::{fs, path::PathBuf, str::FromStr, sync::Arc};
 
 use axum::http::StatusCode;
-use common::{data::table::TableConfig, error::AppError};
-use r2d2::{Pool, PooledConnection};
-use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::params;
-
-use crate::foundation::Basable;
+use base::{
+    config::{ConfigRaw, DataDir},
+    registry::BackendRegistry,
+    SharedDB,
+};
+use common::error::AppError;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    blocking::BlockingPool,
+    foundation::Basable,
+    local_db::{password_secret_id, LocalDB},
+    presence::Presence,
+    session::Sessions,
+    utils::get_env,
+    vault::Vault,
+};
+
+/// Where file sources are read from, when `BASABLE_DATA_DIR` is not set.
+const DEFAULT_DATA_DIR: &str = "data";
 
 #[derive(Clone)]
-pub struct LocalDB(pub Pool<SqliteConnectionManager>);
+pub(crate) struct AppState {
+    pub instance: Arc<RwLock<Basable>>,
 
-impl LocalDB {
-    fn pool(&self) -> Result<PooledConnection<SqliteConnectionManager>, AppError> {
-        self.0
-            .get()
-            .map_err(|err| AppError::PersistentStorageError(err.to_string()))
-    }
+    /// Backends new connections are created with. It never changes after startup, so it's kept
+    /// out of `instance` and connecting never holds its lock.
+    pub registry: Arc<BackendRegistry>,
 
-    pub fn setup(&self) -> Result<usize, AppError> {
-        let pool = self.pool()?;
-
-        pool.execute(
-            "CREATE TABLE IF NOT EXISTS table_configs (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT NOT NULL,
-                conn_id TEXT NOT NULL,
-                label TEXT,
-                pk_column TEXT,
-                ipp INTEGER,
-                exclude_columns TEXT
-            )",
-            params![],
-        )
-        .map_err(|err| AppError::PersistentStorageError(err.to_string()))
-    }
+    pub local_db: LocalDB,
 
-    pub fn create_table_config(&self, conn_id: &str, tc: TableConfig) -> Result<usize, AppError> {
-        match self.pool() {
-            Ok(pool) => {
-                let exclude_columns =
-                    serde_json::to_string(&tc.exclude_columns).map_err(|err| {
-                        AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                    })?;
-
-                let exec = pool.execute(
-                    "
-                    INSERT INTO table_configs (conn_id, label, pk_column, name, ipp, exclude_columns)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
-                ",
-                    params![conn_id, tc.label, tc.pk_column, tc.name, tc.items_per_page, exclude_columns],
-                );
-
-                exec.map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })
-            }
-            Err(err) => Err(err.into()),
-        }
-    }
+    /// Encrypts credentials kept in `local_db`.
+    pub vault: Vault,
 
-    pub fn update_table_config(
-        &self,
-        name: &str,
-        conn_id: &str,
-        tc: TableConfig,
-    ) -> Result<usize, AppError> {
-        match self.pool() {
-            Ok(pool) => {
-                let exclude_columns =
-                    serde_json::to_string(&tc.exclude_columns).map_err(|err| {
-                        AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                    })?;
-
-                let exec = pool.execute(
-                    "UPDATE table_configs SET name = ?, label = ?, pk_column = ?, ipp = ?, exclude_columns = ? WHERE name = ? AND conn_id = ?", 
-                    params![tc.name, tc.label, tc.pk_column, tc.items_per_page, exclude_columns, name, conn_id]
-                );
-
-                exec.map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })
-            }
-            Err(err) => Err(err.into()),
-        }
-    }
+    /// Issues and revokes session tokens.
+    pub sessions: Sessions,
 
-    pub fn get_table_config(&self, id: &str, conn_id: &str) -> Result<TableConfig, AppError> {
-        match self.pool() {
-            Ok(pool) => {
-                let tc = pool.query_row(
-                    "SELECT name, label, pk_column, ipp, exclude_columns FROM table_configs WHERE (name = ?1 OR label = ?1) AND conn_id = ?2 LIMIT 1",
-                    params![id, conn_id],
-                    |row| {
-                        let excs: String = row.get(4)?;
-                        let exclude_columns: Option<Vec<String>> = serde_json::from_str(&excs).map_err(|_| rusqlite::Error::InvalidColumnName("exclude_columns".to_string()))?;
-                        
-                        Ok(TableConfig {
-                            name: row.get(0)?,
-                            label: row.get(1)?,
-                            pk_column: row.get(2)?,
-                            items_per_page: row.get(3)?,
-                            exclude_columns,
-                            ..Default::default()
-                        })
-                    },
-                );
-
-                tc.map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })
-            }
-            Err(err) => Err(err.into()),
-        }
-    }
-}
+    /// Runs backend queries, which are blocking, off the async runtime.
+    pub blocking: BlockingPool,
 
-#[derive(Clone)]
-pub(crate) struct AppState {
-    pub instance: Arc<Mutex<Basable>>,
-    pub local_db: LocalDB,
+    /// Who is working on which connection.
+    pub presence: Presence,
 }
 
 impl AppState {
     pub fn create() -> Result<Self, AppError> {
-        let manager = SqliteConnectionManager::memory();
-        let pool = r2d2::Pool::new(manager).map_err(|err| AppError::InitError(err.to_string()))?;
+        let local_db = LocalDB::from_env()?;
+
+        let mut registry = BackendRegistry::default();
+        registry.set_data_dir(data_dir()?);
 
         let s = Self {
             instance: Default::default(),
-            local_db: LocalDB(pool),
+            registry: Arc::new(registry),
+            vault: Vault::from_env(local_db.clone())?,
+            sessions: Sessions::from_env(local_db.clone())?,
+            local_db,
+            blocking: BlockingPool::from_env()?,
+            presence: Default::default(),
         };
 
         Ok(s)
     }
+
+    /// Connect to the source in `config` as the connection `id` of the user, using the backend
+    /// registered for its source, and save configurations for tables that don't have one yet.
+    /// The connection isn't added to the instance, and no lock is held while connecting, which
+    /// may take as long as the backend's connection timeout.
+    pub async fn create_connection(
+        &self,
+        id: Uuid,
+        config: ConfigRaw,
+        user_id: String,
+    ) -> Result<SharedDB, AppError> {
+        let storage = self.local_db.clone();
+
+        let registry = self.registry.clone();
+        self.blocking
+            .run(move || {
+                let db = registry.create(&config, id, user_id)?;
+
+                let conn_id = db.id().to_string();
+                for tbl in db.tables() {
+                    if let Some(config) = tbl.init_config() {
+                        storage.create_table_config(&conn_id, config)?;
+                    }
+                }
+
+                Ok(db)
+            })
+            .await
+    }
+
+    /// Create a connection like [`AppState::create_connection`], and add it to the instance.
+    pub async fn open_connection(
+        &self,
+        id: Uuid,
+        config: ConfigRaw,
+        user_id: String,
+    ) -> Result<SharedDB, AppError> {
+        let db = self.create_connection(id, config, user_id).await?;
+        let mut instance = self.instance.write().await;
+
+        // Another request may have restored the same connection in the meantime.
+        if let Some(open) = instance.get_connection(&id) {
+            return Ok(open);
+        }
+
+        instance.add_connection(&db);
+        Ok(db)
+    }
+
+    /// Get the connection with the given id, if the user has a role on it. Saved connections
+    /// that aren't open yet, such as after a restart, are reconnected on first use.
+    pub async fn connection(&self, id: &str, user_id: &str) -> Result<SharedDB, AppError> {
+        let not_found = || {
+            AppError::HttpError(
+                StatusCode::NOT_FOUND,
+                "Connection instance not found".to_string(),
+            )
+        };
+
+        let id = Uuid::from_str(id).map_err(|_| not_found())?;
+
+        // Connections a user may not use are as good as missing to them.
+        if self.local_db.role(&id, user_id, None)?.is_none() {
+            return Err(not_found());
+        }
+
+        if let Some(db) = self.instance.read().await.get_connection(&id) {
+            return Ok(db);
+        }
+
+        let saved = self.local_db.get_connection(&id)?.ok_or_else(not_found)?;
+
+        let mut config = saved.config;
+        config.password = self.vault.get(&password_secret_id(&id))?;
+
+        self.open_connection(id, config, saved.user_id).await
+    }
+}
+
+/// The data directory set by the `BASABLE_DATA_DIR` environment variable, created if it doesn't
+/// exist. The files of [`LocalDB`] and the [`Vault`] are never served from it.
+fn data_dir() -> Result<DataDir, AppError> {
+    let root = get_env("BASABLE_DATA_DIR").unwrap_or_else(|_| DEFAULT_DATA_DIR.to_string());
+    fs::create_dir_all(&root).map_err(|err| {
+        AppError::InitError(format!("Unable to create the data directory '{root}': {err}"))
+    })?;
+
+    let reserved = [LocalDB::env_path(), Vault::key_file_path()].map(PathBuf::from);
+    DataDir::new(root, &reserved)
 }
@@ -1,14 +1,39 @@
 use std::str::from_utf8;
 
-use crate::{utils::get_env, AppError};
+use crate::{roles::Role, utils::get_env, AppError};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
 use axum::http::{HeaderValue, StatusCode};
 use chrono::Utc;
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+#[derive(Clone)]
 pub(crate) struct User {
     pub id: String,
     pub is_guest: bool,
+
+    /// The highest role the user may act with on connections, whatever their role there. Set
+    /// for requests authenticated with a read-only API key.
+    pub max_role: Option<Role>,
+}
+
+impl User {
+    /// Whether the user may only read, such as with a read-only API key.
+    pub fn is_read_only(&self) -> bool {
+        self.max_role.is_some_and(|role| role < Role::Editor)
+    }
+
+    /// The role the user may act with, given their `role` on a connection or table.
+    pub fn effective_role(&self, role: Option<Role>) -> Option<Role> {
+        match self.max_role {
+            Some(max_role) => role.map(|role| role.min(max_role)),
+            None => role,
+        }
+    }
 }
 
 impl Default for User {
@@ -16,36 +41,59 @@ impl Default for User {
         Self {
             id: String::new(),
             is_guest: true,
+            max_role: None,
         }
     }
 }
 
 #[derive(Deserialize, Serialize)]
 pub(crate) struct Claims {
-    sub: String,
-    is_guest: bool,
-    exp: usize,
+    pub sub: String,
+    pub is_guest: bool,
+    pub exp: usize,
+
+    /// Unique id of the token, by which it's revoked.
+    pub jti: String,
 }
 
+impl Claims {
+    pub fn user(&self) -> User {
+        User {
+            id: self.sub.clone(),
+            is_guest: self.is_guest,
+            max_role: None,
+        }
+    }
+}
+
+/// Tokens of a session, returned when it starts or is refreshed.
 #[derive(Serialize)]
 pub(crate) struct JwtSession {
+    /// Access token, passed to authenticate requests.
     pub token: String,
+
+    /// Lifetime of `token`, in seconds.
     pub exp: usize,
-}
 
-pub(crate) fn create_jwt(user: User) -> Result<JwtSession, AppError> {
-    let exp_time = 7200;
+    /// Single-use token to get new tokens with, before `token` expires or once it has.
+    pub refresh_token: String,
+
+    /// Lifetime of `refresh_token`, in seconds.
+    pub refresh_exp: usize,
+}
 
-    // Token expiration is two hours
+/// Create an access token for `user`, valid for `lifetime` seconds.
+pub(crate) fn create_jwt(user: &User, lifetime: i64) -> Result<String, AppError> {
     let exp = Utc::now()
-        .checked_add_signed(chrono::Duration::seconds(exp_time.clone()))
+        .checked_add_signed(chrono::Duration::seconds(lifetime))
         .expect("Invalid timestamp")
         .timestamp() as usize;
 
     let claims = Claims {
-        sub: user.id,
+        sub: user.id.clone(),
         is_guest: user.is_guest,
         exp,
+        jti: Uuid::new_v4().to_string(),
     };
 
     let header = Header::new(Algorithm::HS512);
@@ -54,16 +102,33 @@ pub(crate) fn create_jwt(user: User) -> Result<JwtSession, AppError> {
         .map_err(|err| AppError::HttpError(StatusCode::UNAUTHORIZED, err.to_string()))?;
     let secret = secret.as_bytes();
 
-    let token = encode(&header, &claims, &EncodingKey::from_secret(secret))
-        .map_err(|e| AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    encode(&header, &claims, &EncodingKey::from_secret(secret))
+        .map_err(|e| AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Hash `password` with Argon2 and a random salt. The hash is returned as a PHC string, which
+/// includes the salt and parameters it was computed with.
+pub(crate) fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
 
-    Ok(JwtSession {
-        token,
-        exp: exp_time as usize,
-    })
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| AppError::ServerError(err.to_string()))
 }
 
-pub(crate) fn decode_jwt(header_value: &HeaderValue) -> Result<User, AppError> {
+/// Whether `password` matches `hash`, as computed by [`hash_password`].
+pub(crate) fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+pub(crate) fn decode_jwt(header_value: &HeaderValue) -> Result<Claims, AppError> {
     let token = extract_jwt(header_value)?;
 
     let secret = get_env("BASABLE_JWT_SECRET")
@@ -77,9 +142,7 @@ pub(crate) fn decode_jwt(header_value: &HeaderValue) -> Result<User, AppError> {
     )
     .map_err(|e| AppError::HttpError(StatusCode::UNAUTHORIZED, e.to_string()))?;
 
-    let Claims { sub, is_guest, .. } = decoded.claims;
-
-    Ok(User { id: sub, is_guest })
+    Ok(decoded.claims)
 }
 
 fn extract_jwt(header_value: &HeaderValue) -> Result<String, AppError> {
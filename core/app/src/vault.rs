@@ -0,0 +1,299 @@
+use std::{fs, io::ErrorKind, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::error::AppError;
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+use crate::{
+    local_db::{password_secret_id, LocalDB, SealedSecret},
+    utils::get_env,
+};
+
+/// Where master secrets are read from, when neither `BASABLE_MASTER_KEY` nor
+/// `BASABLE_MASTER_KEY_FILE` is set.
+const DEFAULT_KEY_FILE: &str = "basable.key";
+
+/// Master secrets shorter than this are rejected.
+const MIN_SECRET_LEN: usize = 32;
+
+const NONCE_LEN: usize = 12;
+
+/// An encryption key derived from a master secret.
+struct VaultKey {
+    /// Identifies the key secrets were encrypted with, without revealing it.
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl VaultKey {
+    fn derive(secret: &str) -> Result<Self, AppError> {
+        if secret.len() < MIN_SECRET_LEN {
+            return Err(AppError::InitError(format!(
+                "Master secrets must be at least {MIN_SECRET_LEN} characters long"
+            )));
+        }
+
+        let hkdf = Hkdf::<Sha256>::new(Some(b"basable-vault"), secret.as_bytes());
+        let derive_err = |err: hkdf::InvalidLength| AppError::InitError(err.to_string());
+
+        let mut key = [0u8; 32];
+        hkdf.expand(b"credentials aes-256-gcm", &mut key)
+            .map_err(derive_err)?;
+
+        let mut id = [0u8; 4];
+        hkdf.expand(b"key id", &mut id).map_err(derive_err)?;
+
+        Ok(VaultKey {
+            id: id.iter().map(|b| format!("{b:02x}")).collect(),
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        })
+    }
+}
+
+/// Keeps credentials, such as passwords of saved connections, encrypted in [`LocalDB`].
+///
+/// Secrets are encrypted with AES-256-GCM, using a key derived from the current master secret.
+/// Previous master secrets may be configured after the current one, so that secrets encrypted
+/// with them can still be read while keys are rotated. [`Vault::reencrypt`] then moves every
+/// secret to the current key, after which previous master secrets can be dropped.
+#[derive(Clone)]
+pub(crate) struct Vault {
+    /// The current key first, then previous ones.
+    keys: Arc<Vec<VaultKey>>,
+    db: LocalDB,
+}
+
+impl Vault {
+    /// Create a [`Vault`] storing secrets in `db`, with keys derived from `secrets`. The first
+    /// of them is the current master secret.
+    pub fn new(secrets: &[String], db: LocalDB) -> Result<Self, AppError> {
+        if secrets.is_empty() {
+            return Err(AppError::InitError("No master secret was provided".to_string()));
+        }
+
+        let keys = secrets
+            .iter()
+            .map(|secret| VaultKey::derive(secret))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Vault {
+            keys: Arc::new(keys),
+            db,
+        })
+    }
+
+    /// Create a [`Vault`] with the master secrets set by the `BASABLE_MASTER_KEY` environment
+    /// variable, separated by commas. Otherwise they're read from the file set by
+    /// `BASABLE_MASTER_KEY_FILE`, one per line, which is created with a random secret if it
+    /// doesn't exist.
+    pub fn from_env(db: LocalDB) -> Result<Self, AppError> {
+        let secrets = match get_env("BASABLE_MASTER_KEY") {
+            Ok(secrets) => split_secrets(&secrets, ','),
+            Err(_) => read_or_create_key_file(&Self::key_file_path())?,
+        };
+
+        Self::new(&secrets, db)
+    }
+
+    /// The path set by the `BASABLE_MASTER_KEY_FILE` environment variable, or the default one.
+    pub fn key_file_path() -> String {
+        get_env("BASABLE_MASTER_KEY_FILE").unwrap_or_else(|_| DEFAULT_KEY_FILE.to_string())
+    }
+
+    fn current_key(&self) -> &VaultKey {
+        &self.keys[0]
+    }
+
+    fn seal(&self, id: &str, plaintext: &str) -> Result<SealedSecret, AppError> {
+        let key = self.current_key();
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        // The id is authenticated along with the secret, so a secret can't be passed off as
+        // another by swapping them in storage.
+        let payload = Payload {
+            msg: plaintext.as_bytes(),
+            aad: id.as_bytes(),
+        };
+        let ciphertext = key
+            .cipher
+            .encrypt(&nonce, payload)
+            .map_err(|_| AppError::ServerError(format!("Unable to encrypt secret '{id}'")))?;
+
+        let mut value = nonce.to_vec();
+        value.extend(ciphertext);
+
+        Ok(SealedSecret {
+            id: id.to_string(),
+            key_id: key.id.clone(),
+            value: STANDARD.encode(value),
+        })
+    }
+
+    fn open(&self, secret: &SealedSecret) -> Result<String, AppError> {
+        let id = &secret.id;
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.id == secret.key_id)
+            .ok_or_else(|| {
+                AppError::ServerError(format!(
+                    "Secret '{id}' is encrypted with key {}, which isn't configured",
+                    secret.key_id
+                ))
+            })?;
+
+        let decrypt_err = || AppError::ServerError(format!("Unable to decrypt secret '{id}'"));
+
+        let value = STANDARD.decode(&secret.value).map_err(|_| decrypt_err())?;
+        if value.len() < NONCE_LEN {
+            return Err(decrypt_err());
+        }
+
+        let (nonce, ciphertext) = value.split_at(NONCE_LEN);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: id.as_bytes(),
+        };
+        let plaintext = key
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), payload)
+            .map_err(|_| decrypt_err())?;
+
+        String::from_utf8(plaintext).map_err(|_| decrypt_err())
+    }
+
+    /// Encrypt `plaintext` and store it as the secret `id`, replacing any previous value.
+    pub fn put(&self, id: &str, plaintext: &str) -> Result<(), AppError> {
+        let secret = self.seal(id, plaintext)?;
+        self.db.put_secret(&secret)?;
+        Ok(())
+    }
+
+    /// Get the decrypted value of the secret `id`, if it exists.
+    pub fn get(&self, id: &str) -> Result<Option<String>, AppError> {
+        match self.db.get_secret(id)? {
+            Some(secret) => self.open(&secret).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Encrypt every secret that isn't encrypted with the current key yet with it, and return
+    /// how many were.
+    pub fn reencrypt(&self) -> Result<usize, AppError> {
+        let stale = self.db.secrets_not_sealed_with(&self.current_key().id)?;
+
+        for secret in &stale {
+            let plaintext = self.open(secret)?;
+            self.put(&secret.id, &plaintext)?;
+        }
+
+        Ok(stale.len())
+    }
+
+    /// Move passwords still saved in plain text along with connection configurations into the
+    /// vault, and return how many were.
+    pub fn seal_saved_passwords(&self) -> Result<usize, AppError> {
+        let conns = self.db.connections_with_password()?;
+
+        for conn in &conns {
+            if let Some(password) = &conn.config.password {
+                self.put(&password_secret_id(&conn.id), password)?;
+            }
+
+            // Passwords aren't serialized, so saving the configuration again drops it.
+            self.db.update_connection(conn)?;
+        }
+
+        Ok(conns.len())
+    }
+}
+
+fn split_secrets(secrets: &str, separator: char) -> Vec<String> {
+    secrets
+        .split(separator)
+        .map(str::trim)
+        .filter(|secret| !secret.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn read_key_file(path: &str) -> Result<Vec<String>, AppError> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| AppError::InitError(format!("Unable to read key file '{path}': {err}")))?;
+
+    Ok(split_secrets(&content, '\n'))
+}
+
+/// Read the key file at `path`, or create it with a random master secret if it doesn't exist.
+fn read_or_create_key_file(path: &str) -> Result<Vec<String>, AppError> {
+    match fs::metadata(path) {
+        Ok(_) => read_key_file(path),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let secret = STANDARD.encode(Aes256Gcm::generate_key(&mut OsRng));
+            write_key_file(path, &secret)
+                .map_err(|err| AppError::InitError(format!("Unable to create key file '{path}': {err}")))?;
+
+            tracing::info!("Created master key file '{path}'");
+            Ok(vec![secret])
+        }
+        Err(err) => Err(AppError::InitError(format!("Unable to read key file '{path}': {err}"))),
+    }
+}
+
+#[cfg(unix)]
+fn write_key_file(path: &str, secret: &str) -> std::io::Result<()> {
+    use std::{io::Write, os::unix::fs::OpenOptionsExt};
+
+    // Only the owner may read the file.
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?;
+
+    writeln!(file, "{secret}")
+}
+
+#[cfg(not(unix))]
+fn write_key_file(path: &str, secret: &str) -> std::io::Result<()> {
+    fs::write(path, format!("{secret}\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::local_db::LocalDB;
+
+    use super::Vault;
+
+    const OLD: &str = "an old master secret of at least 32 characters";
+    const NEW: &str = "a new master secret of at least 32 characters";
+
+    #[test]
+    fn test_rotate_keys() {
+        let db = LocalDB::memory().unwrap();
+        db.setup().unwrap();
+
+        let vault = Vault::new(&[OLD.to_string()], db.clone()).unwrap();
+        vault.put("password", "hunter2").unwrap();
+
+        let stored = db.get_secret("password").unwrap().unwrap();
+        assert!(!stored.value.contains("hunter2"));
+
+        // Secrets encrypted with previous keys can be read until they're re-encrypted.
+        let vault = Vault::new(&[NEW.to_string(), OLD.to_string()], db.clone()).unwrap();
+        assert_eq!(vault.get("password").unwrap().as_deref(), Some("hunter2"));
+        assert_eq!(vault.reencrypt().unwrap(), 1);
+        assert_eq!(vault.reencrypt().unwrap(), 0);
+
+        let vault = Vault::new(&[NEW.to_string()], db.clone()).unwrap();
+        assert_eq!(vault.get("password").unwrap().as_deref(), Some("hunter2"));
+
+        let vault = Vault::new(&[OLD.to_string()], db).unwrap();
+        assert!(vault.get("password").is_err());
+    }
+}
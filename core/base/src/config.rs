@@ -1,5 +1,10 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
 use axum::http::StatusCode;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use urlencoding::encode;
 
 use common::error::AppError;
@@ -9,7 +14,34 @@ pub enum DatabaseType {
     Mysql,
     Postgres,
     Oracle,
-    Mongo
+    Mongo,
+    Sqlite,
+}
+
+impl DatabaseType {
+    /// Port the database server listens on by default. File databases have none.
+    pub fn default_port(&self) -> Option<u16> {
+        match self {
+            DatabaseType::Mysql => Some(3306),
+            DatabaseType::Postgres => Some(5432),
+            DatabaseType::Oracle => Some(1521),
+            DatabaseType::Mongo => Some(27017),
+            DatabaseType::Sqlite => None,
+        }
+    }
+
+    /// Whether the database lives in a local file given by [`ConfigRaw::path`].
+    pub fn is_file(&self) -> bool {
+        matches!(self, DatabaseType::Sqlite)
+    }
+
+    /// Username used when none is provided in [`ConfigRaw`].
+    pub fn default_username(&self) -> &'static str {
+        match self {
+            DatabaseType::Postgres => "postgres",
+            _ => "root",
+        }
+    }
 }
 
 impl TryFrom<&str> for DatabaseType {
@@ -22,17 +54,59 @@ impl TryFrom<&str> for DatabaseType {
             "oracle" => Ok(Self::Oracle),
             "mysql" => Ok(Self::Mysql),
             "mongo" => Ok(Self::Mongo),
+            "sqlite" => Ok(Self::Sqlite),
             &_ => Err(AppError::HttpError(StatusCode::EXPECTATION_FAILED, "Invalid database source type".to_string())),
         }
     }
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub enum FileType {
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    Parquet,
+    /// Arrow IPC files, including Feather v2.
+    Arrow,
+}
+
+impl FileType {
+    /// File extensions read for this type when [`ConfigRaw::path`] is a directory.
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            FileType::Csv => &["csv"],
+            FileType::Tsv => &["tsv", "tab"],
+            FileType::Json => &["json"],
+            FileType::Ndjson => &["ndjson", "jsonl"],
+            FileType::Parquet => &["parquet"],
+            FileType::Arrow => &["arrow", "feather", "ipc"],
+        }
+    }
+}
+
+impl TryFrom<&str> for FileType {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            "parquet" => Ok(Self::Parquet),
+            "arrow" | "feather" => Ok(Self::Arrow),
+            &_ => Err(AppError::HttpError(StatusCode::EXPECTATION_FAILED, "Invalid file source type".to_string())),
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub enum Cloud { Firebase }
 
 #[derive(Deserialize, Clone, Debug)]
 pub enum SourceType {
-    Database(DatabaseType), Cloud, File
+    Database(DatabaseType), Cloud, File(FileType)
 }
 
 impl SourceType {
@@ -40,22 +114,33 @@ impl SourceType {
         match src_type {
             "database" => Ok(Self::Database(src.try_into()?)),
             "cloud" => Ok(Self::Cloud),
-            "file" => Ok(Self::File),
+            "file" => Ok(Self::File(src.try_into()?)),
             &_ => Err(AppError::HttpError(StatusCode::EXPECTATION_FAILED, "Invalid source type".to_string()))
         }
     }
 }
 
 /// Configuration options for a new `BasableConnection`.
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct ConfigRaw {
     pub source_type: String,
     pub source: String,
     pub username: Option<String>,
+
+    /// Never serialized, so it can't be stored or returned along with the rest of the
+    /// configuration by accident.
+    #[serde(skip_serializing)]
     pub password: Option<String>,
     pub host: Option<String>,
     pub port: Option<u16>,
     pub db_name: Option<String>,
+    /// Path to the data source, for file-based sources. Relative paths are read from the
+    /// [`DataDir`], and no other paths are allowed.
+    pub path: Option<String>,
+
+    /// Whether a file database is opened for writing. Otherwise it's only read.
+    #[serde(default)]
+    pub writable: bool,
 }
 
 impl Default for ConfigRaw {
@@ -66,24 +151,46 @@ impl Default for ConfigRaw {
             host: None,
             port: None,
             db_name: None,
+            path: None,
+            writable: false,
             source_type: String::from("database"),
             source: String::from("mysql")
         }
     }
 }
 
+/// The password is redacted, so configurations can be logged.
+impl fmt::Debug for ConfigRaw {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigRaw")
+            .field("source_type", &self.source_type)
+            .field("source", &self.source)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("db_name", &self.db_name)
+            .field("path", &self.path)
+            .field("writable", &self.writable)
+            .finish()
+    }
+}
+
 impl ConfigRaw {
     pub fn build_url(&self) -> Result<String, AppError> {
         let src_type = SourceType::from_str(&self.source_type, &self.source)?;
 
         match src_type {
-            SourceType::Database(_) => {
+            SourceType::Database(db) if db.is_file() => self.file_path(),
+            SourceType::File(_) => self.file_path(),
+
+            SourceType::Database(db) => {
                 let dbtype = &self.source;
 
-                let username = self.username.clone().unwrap_or("root".to_string());
+                let username = self.username.clone().unwrap_or(db.default_username().to_string());
                 let password = self.password.clone().unwrap_or_default();
                 let host = self.host.clone().unwrap_or("localhost".to_string());
-                let port = self.port.unwrap_or(3306);
+                let port = self.port.or(db.default_port()).unwrap_or_default();
                 let db = self.db_name.clone().unwrap_or_default();
 
                 let url = format!(
@@ -104,7 +211,148 @@ impl ConfigRaw {
         
     }
 
+    fn file_path(&self) -> Result<String, AppError> {
+        self.path.clone().ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                "A file path is required for this source".to_string(),
+            )
+        })
+    }
+
     pub fn get_source(&self) -> Result<SourceType, AppError> {
         SourceType::from_str(&self.source_type, &self.source)
     }
+
+    /// Whether the source is read from [`ConfigRaw::path`], which must then be in the
+    /// [`DataDir`].
+    pub fn is_file_source(&self) -> Result<bool, AppError> {
+        let is_file = match self.get_source()? {
+            SourceType::Database(db) => db.is_file(),
+            SourceType::File(_) => true,
+            SourceType::Cloud => false,
+        };
+
+        Ok(is_file)
+    }
+}
+
+/// The directory file sources are read from.
+///
+/// Paths of file sources are resolved against it, and must stay inside it once symbolic links
+/// are followed. Files Basable keeps for itself, such as its own storage and master key, are
+/// refused even when they're inside it.
+#[derive(Clone, Debug)]
+pub struct DataDir {
+    root: PathBuf,
+
+    /// Canonical paths of the files that may not be opened.
+    reserved: Vec<PathBuf>,
+}
+
+impl DataDir {
+    /// Serve files from `root`, which must exist, except for the `reserved` ones.
+    pub fn new(root: impl AsRef<Path>, reserved: &[PathBuf]) -> Result<Self, AppError> {
+        let root = fs::canonicalize(root.as_ref()).map_err(|err| {
+            AppError::InitError(format!(
+                "Invalid data directory '{}': {err}",
+                root.as_ref().display()
+            ))
+        })?;
+
+        // Reserved files may not have been created yet, in which case their directory is
+        // resolved instead.
+        let reserved = reserved
+            .iter()
+            .filter_map(|path| {
+                let dir = match path.parent() {
+                    Some(dir) if !dir.as_os_str().is_empty() => dir,
+                    _ => Path::new("."),
+                };
+                Some(fs::canonicalize(dir).ok()?.join(path.file_name()?))
+            })
+            .collect();
+
+        Ok(DataDir { root, reserved })
+    }
+
+    /// The canonical path of the file or directory at `path`, relative to the data directory
+    /// unless absolute. Paths outside of it are refused the same as missing ones, so they
+    /// don't tell which files exist on the server.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, AppError> {
+        let refused = || {
+            AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("No such file or directory in the data directory: '{path}'"),
+            )
+        };
+
+        let resolved = fs::canonicalize(self.root.join(path)).map_err(|_| refused())?;
+        if !resolved.starts_with(&self.root) || self.is_reserved(&resolved) {
+            return Err(refused());
+        }
+
+        Ok(resolved)
+    }
+
+    /// Whether `path` is a reserved file, one of the journals SQLite keeps next to it, or a
+    /// directory holding any of them.
+    fn is_reserved(&self, path: &Path) -> bool {
+        self.reserved.iter().any(|reserved| {
+            if reserved.parent() == Some(path) {
+                return true;
+            }
+
+            let (Some(name), Some(reserved_name)) = (path.file_name(), reserved.file_name()) else {
+                return false;
+            };
+            let journal = format!("{}-", reserved_name.to_string_lossy());
+
+            path.parent() == reserved.parent()
+                && (name == reserved_name || name.to_string_lossy().starts_with(&journal))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use uuid::Uuid;
+
+    use super::DataDir;
+
+    #[test]
+    fn test_data_dir() {
+        let root = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("data")).unwrap();
+        for file in ["data/shop.db", "data/basable.db", "secret.db"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let data_dir = DataDir::new(root.join("data"), &[root.join("data/basable.db")]).unwrap();
+
+        let shop = data_dir.resolve("shop.db").unwrap();
+        assert_eq!(shop, fs::canonicalize(root.join("data/shop.db")).unwrap());
+        assert_eq!(data_dir.resolve(&shop.to_string_lossy()).unwrap(), shop);
+
+        let outside = root.join("secret.db");
+        assert!(data_dir.resolve("../secret.db").is_err());
+        assert!(data_dir.resolve(&outside.to_string_lossy()).is_err());
+        assert!(data_dir.resolve("missing.db").is_err());
+
+        // Basable's own files are refused, along with their journals and directory.
+        fs::write(root.join("data/basable.db-wal"), "").unwrap();
+        assert!(data_dir.resolve("basable.db").is_err());
+        assert!(data_dir.resolve("basable.db-wal").is_err());
+        assert!(data_dir.resolve(".").is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, root.join("data/link.db")).unwrap();
+            assert!(data_dir.resolve("link.db").is_err());
+        }
+
+        fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -1,10 +1,13 @@
-use common::error::AppError;
+use common::{data::row::ColumnValue, error::AppError};
 
-use crate::config::ConfigRaw;
+use crate::{config::ConfigRaw, db::SqlQuery};
+
+/// Receives rows one at a time, as they're read from a database.
+pub type RowSink<'a, R> = dyn FnMut(R) -> Result<(), AppError> + Send + 'a;
 
 /// Facilitates connection and run queries between `Basable` instance and a databse server
 pub trait Connector: Send + Sync {
-    type Row;
+    type Row: Send;
     // type Error;
     /// Create a new connector
     fn new(conn: ConfigRaw) -> Result<Self, AppError>
@@ -12,7 +15,40 @@ pub trait Connector: Send + Sync {
         Self: Sized;
 
     /// Execute a database query and return results
-    fn exec_query(&self, query: &str) -> Result<Vec<Self::Row>, AppError>;
+    fn exec_query(&self, query: &str) -> Result<Vec<Self::Row>, AppError> {
+        self.exec_params(query, &[])
+    }
+
+    /// Execute a database query with `params` bound to its placeholders, and return results
+    fn exec_params(&self, query: &str, params: &[ColumnValue]) -> Result<Vec<Self::Row>, AppError> {
+        let mut rows = Vec::new();
+        self.stream_params(query, params, &mut |row| {
+            rows.push(row);
+            Ok(())
+        })?;
+
+        Ok(rows)
+    }
+
+    /// Execute a generated [`SqlQuery`] and return results
+    fn exec_sql(&self, query: &SqlQuery) -> Result<Vec<Self::Row>, AppError> {
+        self.exec_params(&query.sql, &query.params)
+    }
+
+    /// Execute a database query with `params` bound to its placeholders, handing each row to
+    /// `sink` as soon as it's read rather than collecting results. If `sink` returns an error,
+    /// the query is abandoned and the error returned.
+    fn stream_params(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        sink: &mut RowSink<'_, Self::Row>,
+    ) -> Result<(), AppError>;
+
+    /// Execute a generated [`SqlQuery`], handing each row to `sink` as it's read.
+    fn stream_sql(&self, query: &SqlQuery, sink: &mut RowSink<'_, Self::Row>) -> Result<(), AppError> {
+        self.stream_params(&query.sql, &query.params, sink)
+    }
 
     fn config(&self) -> &ConfigRaw;
 }
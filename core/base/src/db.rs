@@ -2,41 +2,34 @@ use common::error::AppError;
 use common::DbServerDetails;
 use uuid::Uuid;
 
+use axum::http::StatusCode;
+use common::data::row::ColumnValue;
 use common::data::table::{TableSearchOpts, TableSummaries};
-use common::query::filter::{Filter, FilterChain};
-use common::query::{BasableQuery, QueryCommand};
-
-use crate::mysql_plugin::db::MySqlDB;
+use common::query::filter::{Filter, FilterChain, FilterCombinator, FilterExpression, FilterNode};
+use common::query::{BasableQuery, QueryCommand, QueryOrder};
 
+use super::dialect::Dialect;
 use super::graphs::VisualizeDB;
-use super::{ConnectorType, SharedTable};
+use super::SharedTable;
 
 pub type DBQueryResult<R, E> = Result<Vec<R>, E>;
 
 /// An abstraction of database connection.
+///
+/// Each backend keeps its own connector and row types internally, so that any [`DB`]
+/// implementation can be shared across the app as a [`SharedDB`](crate::SharedDB).
 pub trait DB: VisualizeDB + QuerySqlParser + Send + Sync {
-    type Row;
-
     fn id(&self) -> &Uuid;
 
     fn user_id(&self) -> &str;
 
-    /// Get the [`ConnectorType`] instance for [`DB`].
-    fn connector(&self) -> &ConnectorType;
-
-    /// Construct [`Table`](`crate::base::table::Table`) for all tables. This loads the constructed [`Table`](`crate::base::table::Table`) into `DB` instance.
-    /// Caller should provide a [`ConnectorType`] pointer whose copy is assigned to each [Table](`crate::base::table::Table`) that is created.
+    /// Construct [`Table`](`crate::table::Table`) for all tables. This loads the constructed [`Table`](`crate::table::Table`) into `DB` instance.
     ///
-    /// The [`ConnectorType`] will be used by the table for their own queries.
-    fn load_tables(&mut self, connector: ConnectorType) -> Result<(), AppError>;
+    /// Each [`Table`](`crate::table::Table`) gets a copy of the backend's connector, which it uses for its own queries.
+    fn load_tables(&mut self) -> Result<(), AppError>;
 
     fn tables(&self) -> &Vec<SharedTable>;
 
-    /// Query [`DB`] server for information about available tables. It only queries the database server and
-    /// return results as [`DB::Row`]. It is different from [`DB::load_tables`] which actually loads the [`Table`]
-    /// abstraction into memory.
-    fn query_tables(&self) -> DBQueryResult<Self::Row, AppError>;
-
     /// Get an instance of a [`SharedTable`], as a mutable thread-safe reference.
     fn get_table(&self, name: &str) -> Option<&SharedTable>;
 
@@ -48,33 +41,180 @@ pub trait DB: VisualizeDB + QuerySqlParser + Send + Sync {
 
     /// Get total number of columns
     fn query_column_count(&self, table_name: &str) -> Result<u32, AppError>;
+
+    /// Make sure `table` exists and has all of `columns`, before they are used as identifiers in a query.
+    fn validate_columns(&self, table: &str, columns: &[&str]) -> Result<(), AppError> {
+        let table = self.get_table(table).ok_or_else(|| {
+            AppError::HttpError(StatusCode::NOT_FOUND, format!("Table '{table}' not found"))
+        })?;
+
+        table.validate_columns(columns)
+    }
+}
+
+/// SQL with values bound to its placeholders, as produced by [`QuerySqlParser::generate_sql`].
+pub struct SqlQuery {
+    pub sql: String,
+    pub params: Vec<ColumnValue>,
+}
+
+impl SqlQuery {
+    pub fn new(sql: String, params: QueryParams) -> Self {
+        SqlQuery {
+            sql,
+            params: params.values,
+        }
+    }
+}
+
+/// How a backend writes the placeholder of a bound value.
+#[derive(Clone, Copy)]
+pub enum Placeholder {
+    /// `?`, as in MySQL and SQLite.
+    Question,
+
+    /// `$1`, `$2`..., as in PostgreSQL.
+    Numbered,
+}
+
+/// Values bound to a query while its SQL is being generated.
+pub struct QueryParams {
+    placeholder: Placeholder,
+    values: Vec<ColumnValue>,
+}
+
+impl QueryParams {
+    pub fn new(placeholder: Placeholder) -> Self {
+        QueryParams {
+            placeholder,
+            values: Vec::new(),
+        }
+    }
+
+    /// Bind `value` and return the placeholder to write in its place.
+    pub fn bind(&mut self, value: impl Into<ColumnValue>) -> String {
+        self.values.push(value.into());
+
+        match self.placeholder {
+            Placeholder::Question => "?".to_string(),
+            Placeholder::Numbered => format!("${}", self.values.len()),
+        }
+    }
+
+    /// Bind each of `values`, and return their placeholders as a list.
+    pub fn bind_list(&mut self, values: &[impl Into<ColumnValue> + Clone]) -> String {
+        let placeholders: Vec<String> = values.iter().map(|v| self.bind(v.clone())).collect();
+        placeholders.join(", ")
+    }
 }
 
+/// Generates SQL for a [`BasableQuery`] in the [`Dialect`] of the backend. Values from the
+/// query are never written into the SQL, but bound with [`QueryParams`].
 pub trait QuerySqlParser {
-    fn parse_filter(filter: &Filter) -> String
-    where
-        Self: Sized,
-    {
-        filter.to_string()
+    /// The SQL dialect queries are generated in.
+    fn dialect(&self) -> &dyn Dialect;
+
+    /// Render the condition of `filter`, binding its values to `params`. Its combinator is left
+    /// to [`QuerySqlParser::parse_filter_nodes`].
+    fn parse_filter(&self, filter: &Filter, params: &mut QueryParams) -> String {
+        let dialect = self.dialect();
+
+        let expr = match &filter.expression {
+            FilterExpression::Eq(v) => format!("= {}", params.bind(v.as_str())),
+            FilterExpression::NotEq(v) => format!("!= {}", params.bind(v.as_str())),
+            FilterExpression::Gt(v) => format!("> {}", params.bind(v.as_str())),
+            FilterExpression::Lt(v) => format!("< {}", params.bind(v.as_str())),
+            FilterExpression::Gte(v) => format!(">= {}", params.bind(v.as_str())),
+            FilterExpression::Lte(v) => format!("<= {}", params.bind(v.as_str())),
+            FilterExpression::Contains(v) => format!("LIKE {}", params.bind(format!("%{v}%"))),
+            FilterExpression::NotContains(v) => {
+                format!("NOT LIKE {}", params.bind(format!("%{v}%")))
+            }
+            FilterExpression::Regex(v) => {
+                format!("{} {}", dialect.regex_op(false), params.bind(v.as_str()))
+            }
+            FilterExpression::NotRegex(v) => {
+                format!("{} {}", dialect.regex_op(true), params.bind(v.as_str()))
+            }
+            FilterExpression::Btw(start, end) => {
+                let start = params.bind(start.as_str());
+                format!("BETWEEN {start} AND {}", params.bind(end.as_str()))
+            }
+            FilterExpression::NotBtw(start, end) => {
+                let start = params.bind(start.as_str());
+                format!("NOT BETWEEN {start} AND {}", params.bind(end.as_str()))
+            }
+            FilterExpression::Includes(values) => format!("IN ({})", params.bind_list(values)),
+            FilterExpression::NotInclude(values) => {
+                format!("NOT IN ({})", params.bind_list(values))
+            }
+            FilterExpression::IncludesValues(values) => {
+                format!("IN ({})", params.bind_list(values))
+            }
+            FilterExpression::NotIncludeValues(values) => {
+                format!("NOT IN ({})", params.bind_list(values))
+            }
+            FilterExpression::Null => "IS NULL".to_string(),
+            FilterExpression::NotNull => "IS NOT NULL".to_string(),
+        };
+
+        // Pattern matching may only be defined for text, so other column types are cast first.
+        let column = dialect.column_ref(filter.table.as_deref(), &filter.column);
+        let column = match &filter.expression {
+            FilterExpression::Contains(_)
+            | FilterExpression::NotContains(_)
+            | FilterExpression::Regex(_)
+            | FilterExpression::NotRegex(_) => dialect.text_expr(&column),
+            _ => column,
+        };
+
+        format!("{column} {expr}")
+    }
+
+    /// Render `nodes` joined by their combinators, with groups in parentheses. The first node
+    /// has no combinator, and others are joined with `AND` unless they're `OR`'d. Empty groups
+    /// are left out, so the result is empty when there's no condition at all.
+    fn parse_filter_nodes(&self, nodes: &[FilterNode], params: &mut QueryParams) -> String {
+        let mut sql = String::new();
+
+        for node in nodes {
+            let condition = match node {
+                FilterNode::Filter(filter) => self.parse_filter(filter, params),
+                FilterNode::Group(group) => {
+                    let inner = self.parse_filter_nodes(&group.filters, params);
+                    if inner.is_empty() {
+                        continue;
+                    }
+
+                    format!("({inner})")
+                }
+            };
+
+            if !sql.is_empty() {
+                let comb = match node.combinator() {
+                    FilterCombinator::OR => " OR ",
+                    FilterCombinator::AND | FilterCombinator::BASE => " AND ",
+                };
+                sql.push_str(comb);
+            }
+            sql.push_str(&condition);
+        }
+
+        sql
     }
 
-    fn parse_filter_chain(filters: &FilterChain) -> String
-    where
-        Self: Sized,
-    {
-        let filters: Vec<String> = filters
-            .all()
-            .iter()
-            .map(|f| Self::parse_filter(f))
-            .collect();
-        filters.join(" ")
+    fn parse_filter_chain(&self, filters: &FilterChain, params: &mut QueryParams) -> String {
+        self.parse_filter_nodes(filters.all(), params)
     }
 
-    fn generate_sql(&self, query: BasableQuery) -> Result<String, AppError> {
+    fn generate_sql(&self, query: BasableQuery) -> Result<SqlQuery, AppError> {
+        let dialect = self.dialect();
         let is_search_mode = query.is_search_mode();
+        let mut params = QueryParams::new(dialect.placeholder());
 
         let BasableQuery {
             table,
+            alias,
             command: operation,
             filters,
             row_count,
@@ -86,85 +226,73 @@ pub trait QuerySqlParser {
             search_opts,
         } = query;
 
-        // Parse query operation type
         let mut sql = match operation {
             QueryCommand::SelectData(cols) => {
-                let select_cols = cols.map_or_else(
-                    || "*".to_string(),
-                    |list| {
-                        if list.is_empty() {
-                            return "*".to_string();
-                        }
-
-                        let s: Vec<String> = list
-                            .iter()
-                            .map(|s| {
-                                if s.to_lowercase() == "count(*)" {
-                                    return format!("{s}");
-                                }
-
-                                format!("`{s}`")
-                            })
-                            .collect();
-                        s.join(", ")
-                    },
-                );
-
-                format!("SELECT {select_cols} FROM {table}")
+                let select_cols = cols
+                    .filter(|list| !list.is_empty())
+                    .map_or_else(
+                        || "*".to_string(),
+                        |list| {
+                            let s: Vec<String> =
+                                list.iter().map(|expr| dialect.expr(expr)).collect();
+                            s.join(", ")
+                        },
+                    );
+
+                format!("SELECT {select_cols} FROM {}", dialect.table_ref(&table, alias.as_deref()))
             }
         };
 
-        // Parse left join
         if let Some(left_join) = left_join {
-            sql.push_str(format!(" LEFT JOIN {left_join}").as_str())
+            sql.push_str(&format!(" LEFT JOIN {left_join}"));
         }
 
-        // Parse query filters
         if filters.not_empty() && !is_search_mode {
-            let filter_chain = <MySqlDB as QuerySqlParser>::parse_filter_chain(&filters);
-            sql.push_str(format!(" WHERE {filter_chain}").as_str())
+            let filter_chain = self.parse_filter_chain(&filters, &mut params);
+            if !filter_chain.is_empty() {
+                sql.push_str(&format!(" WHERE {filter_chain}"));
+            }
         }
 
-        // parse fulltext search mode
         if is_search_mode {
             if let Some(opts) = search_opts {
                 let TableSearchOpts {
                     search_cols, query, ..
                 } = opts;
 
-                let wrap_cols: Vec<String> =
-                    search_cols.iter().map(|col| format!("`{col}`")).collect();
-
-                let search_query =
-                    format!(" WHERE MATCH({}) AGAINST('{}')", wrap_cols.join(","), query);
-
-                sql.push_str(&search_query);
+                let search = dialect.search(&search_cols, &query, &mut params);
+                sql.push_str(&format!(" WHERE {search}"));
             }
         }
 
-        // Parse GROUP BY
         if let Some(group_by) = group_by {
-            let cols = group_by.join(", ");
-            sql.push_str(format!(" GROUP BY {cols}").as_str());
+            let cols: Vec<String> = group_by.iter().map(|expr| dialect.expr(expr)).collect();
+            sql.push_str(&format!(" GROUP BY {}", cols.join(", ")));
         }
 
-        // Parse HAVING
         if having.not_empty() {
-            let filter_chain = <MySqlDB as QuerySqlParser>::parse_filter_chain(&having);
-            sql.push_str(format!(" HAVING {filter_chain}").as_str())
+            let filter_chain = self.parse_filter_chain(&having, &mut params);
+            if !filter_chain.is_empty() {
+                sql.push_str(&format!(" HAVING {filter_chain}"));
+            }
         }
 
-        // Parse ORDER BY
         if let Some(order) = order_by {
-            sql.push_str(format!(" ORDER BY {order}").as_str());
+            let order = match order {
+                QueryOrder::ASC(col) => format!("{} ASC", dialect.quote_ident(&col)),
+                QueryOrder::DESC(col) => format!("{} DESC", dialect.quote_ident(&col)),
+                QueryOrder::DescNullsLast(col) => {
+                    dialect.desc_nulls_last(&dialect.quote_ident(&col))
+                }
+            };
+            sql.push_str(&format!(" ORDER BY {order}"));
         }
 
-        // Parse LIMIT
         if let Some(row_count) = row_count {
             let offset = offset.unwrap_or_default();
-            sql.push_str(format!(" LIMIT {offset}, {row_count}").as_str());
+            sql.push_str(&format!(" {}", dialect.limit(row_count, offset)));
         }
 
-        Ok(sql)
+        Ok(SqlQuery::new(sql, params))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,458 @@
+//! SQL syntax that differs between backends.
+//!
+//! [`QuerySqlParser`](crate::db::QuerySqlParser) renders every [`BasableQuery`](common::query::BasableQuery)
+//! through the [`Dialect`] of its backend, so the same query runs on MySQL, PostgreSQL and SQLite.
+
+use common::query::QueryExpr;
+
+use crate::{
+    db::{Placeholder, QueryParams},
+    graphs::chrono::ChronoAnalysisBasis,
+};
+
+/// Quote an SQL identifier (table or column name) with double quotes, as in standard SQL.
+pub fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+pub trait Dialect: Send + Sync {
+    /// Quote an identifier. Standard SQL double quotes are used by default.
+    fn quote_ident(&self, ident: &str) -> String {
+        quote_ident(ident)
+    }
+
+    /// How placeholders of bound values are written.
+    fn placeholder(&self) -> Placeholder {
+        Placeholder::Question
+    }
+
+    /// Render a selected or grouped expression. Columns are always quoted with
+    /// [`Dialect::quote_ident`], since their names may contain dots or spaces, as with flattened
+    /// file fields like `address.city`.
+    fn expr(&self, expr: &QueryExpr) -> String {
+        match expr {
+            QueryExpr::Column(col) => self.quote_ident(col),
+            QueryExpr::Sql(sql) => sql.clone(),
+        }
+    }
+
+    /// Quote a column name, qualified with the alias of its table if any.
+    fn column_ref(&self, table: Option<&str>, col: &str) -> String {
+        match table {
+            Some(table) => format!("{}.{}", self.quote_ident(table), self.quote_ident(col)),
+            None => self.quote_ident(col),
+        }
+    }
+
+    /// Quote a table name, followed by its alias if any.
+    fn table_ref(&self, table: &str, alias: Option<&str>) -> String {
+        match alias {
+            Some(alias) => format!("{} {}", self.quote_ident(table), self.quote_ident(alias)),
+            None => self.quote_ident(table),
+        }
+    }
+
+    /// The expression pattern matching (`LIKE` and regular expressions) is applied to, for
+    /// the quoted column `col`.
+    fn text_expr(&self, col: &str) -> String {
+        format!("CAST({col} AS TEXT)")
+    }
+
+    /// The regular expression match operator.
+    fn regex_op(&self, negated: bool) -> &'static str {
+        if negated {
+            "NOT REGEXP"
+        } else {
+            "REGEXP"
+        }
+    }
+
+    /// Order by the quoted column `col`, descending with nulls last. MySQL has no `NULLS LAST`, so
+    /// nulls are moved last by an extra key.
+    fn desc_nulls_last(&self, col: &str) -> String {
+        format!("{col} IS NULL, {col} DESC")
+    }
+
+    /// The `LIMIT` clause.
+    fn limit(&self, row_count: usize, offset: usize) -> String {
+        format!("LIMIT {row_count} OFFSET {offset}")
+    }
+
+    /// A condition matching rows where any of `cols` contains `query`. Without full-text
+    /// search, each column is matched with `LIKE`.
+    fn search(&self, cols: &[String], query: &str, params: &mut QueryParams) -> String {
+        let pattern = format!("%{query}%");
+        let conditions: Vec<String> = cols
+            .iter()
+            .map(|col| {
+                let col = self.text_expr(&self.quote_ident(col));
+                format!("{col} LIKE {}", params.bind(pattern.as_str()))
+            })
+            .collect();
+
+        format!("({})", conditions.join(" OR "))
+    }
+
+    /// The start of the [`ChronoAnalysisBasis`] period the quoted column `col` is in, which chrono
+    /// graphs group rows by. It must be the same as [`ChronoAnalysisBasis::period_start`], as a
+    /// date or timestamp, or as text [`ChronoBuckets`](crate::graphs::chrono::ChronoBuckets) can
+    /// read.
+    fn chrono_expr(&self, basis: &ChronoAnalysisBasis, col: &str) -> String;
+}
+
+pub struct MySqlDialect;
+
+impl Dialect for MySqlDialect {
+    fn quote_ident(&self, ident: &str) -> String {
+        format!("`{}`", ident.replace('`', "``"))
+    }
+
+    /// MySQL matches patterns against any type.
+    fn text_expr(&self, col: &str) -> String {
+        col.to_string()
+    }
+
+    fn limit(&self, row_count: usize, offset: usize) -> String {
+        format!("LIMIT {offset}, {row_count}")
+    }
+
+    /// Full-text search requires a `FULLTEXT` index on `cols`, which
+    /// [`MySqlTable`](crate::mysql_plugin::table::MySqlTable) creates for the query.
+    fn search(&self, cols: &[String], query: &str, params: &mut QueryParams) -> String {
+        let cols: Vec<String> = cols.iter().map(|col| self.quote_ident(col)).collect();
+        format!("MATCH({}) AGAINST({})", cols.join(","), params.bind(query))
+    }
+
+    fn chrono_expr(&self, basis: &ChronoAnalysisBasis, col: &str) -> String {
+        match basis {
+            ChronoAnalysisBasis::Hourly => format!("DATE_FORMAT({col}, '%Y-%m-%d %H:00:00')"),
+            ChronoAnalysisBasis::Daily => format!("DATE({col})"),
+            ChronoAnalysisBasis::Weekly => format!("DATE_SUB(DATE({col}), INTERVAL WEEKDAY({col}) DAY)"),
+            ChronoAnalysisBasis::Monthly => format!("DATE_FORMAT({col}, '%Y-%m-01')"),
+            ChronoAnalysisBasis::Quarterly => {
+                format!("MAKEDATE(YEAR({col}), 1) + INTERVAL (QUARTER({col}) - 1) QUARTER")
+            }
+            ChronoAnalysisBasis::Yearly => format!("MAKEDATE(YEAR({col}), 1)"),
+            // Seconds are counted without time zones, as `UNIX_TIMESTAMP()` would convert them.
+            ChronoAnalysisBasis::Interval(secs) => format!(
+                "TIMESTAMP('1970-01-01') + INTERVAL (FLOOR(TIMESTAMPDIFF(SECOND, '1970-01-01', {col}) / {secs}) * {secs}) SECOND"
+            ),
+        }
+    }
+}
+
+pub struct PostgresDialect;
+
+impl Dialect for PostgresDialect {
+    fn placeholder(&self) -> Placeholder {
+        Placeholder::Numbered
+    }
+
+    fn text_expr(&self, col: &str) -> String {
+        format!("{col}::text")
+    }
+
+    fn regex_op(&self, negated: bool) -> &'static str {
+        if negated {
+            "!~"
+        } else {
+            "~"
+        }
+    }
+
+    /// Nulls come first in descending orders of PostgreSQL by default.
+    fn desc_nulls_last(&self, col: &str) -> String {
+        format!("{col} DESC NULLS LAST")
+    }
+
+    /// Full-text search doesn't require an index in PostgreSQL.
+    fn search(&self, cols: &[String], query: &str, params: &mut QueryParams) -> String {
+        let cols: Vec<String> = cols
+            .iter()
+            .map(|col| self.text_expr(&self.quote_ident(col)))
+            .collect();
+
+        format!(
+            "to_tsvector(concat_ws(' ', {})) @@ plainto_tsquery({})",
+            cols.join(", "),
+            params.bind(query)
+        )
+    }
+
+    fn chrono_expr(&self, basis: &ChronoAnalysisBasis, col: &str) -> String {
+        match basis {
+            ChronoAnalysisBasis::Hourly => format!("date_trunc('hour', {col}::timestamp)"),
+            ChronoAnalysisBasis::Daily => format!("{col}::date"),
+            ChronoAnalysisBasis::Weekly => format!("date_trunc('week', {col}::timestamp)"),
+            ChronoAnalysisBasis::Monthly => format!("date_trunc('month', {col}::timestamp)"),
+            ChronoAnalysisBasis::Quarterly => format!("date_trunc('quarter', {col}::timestamp)"),
+            ChronoAnalysisBasis::Yearly => format!("date_trunc('year', {col}::timestamp)"),
+            ChronoAnalysisBasis::Interval(secs) => format!(
+                "TIMESTAMP 'epoch' + FLOOR(EXTRACT(EPOCH FROM {col}::timestamp) / {secs}) * {secs} * INTERVAL '1 second'"
+            ),
+        }
+    }
+}
+
+pub struct SqliteDialect;
+
+impl Dialect for SqliteDialect {
+    /// SQLite stores dates as text, which `date()` and `strftime()` parse.
+    fn chrono_expr(&self, basis: &ChronoAnalysisBasis, col: &str) -> String {
+        match basis {
+            ChronoAnalysisBasis::Hourly => format!("strftime('%Y-%m-%d %H:00:00', {col})"),
+            ChronoAnalysisBasis::Daily => format!("date({col})"),
+            // The next Sunday, or the day itself, is the end of its ISO week.
+            ChronoAnalysisBasis::Weekly => format!("date({col}, 'weekday 0', '-6 days')"),
+            ChronoAnalysisBasis::Monthly => format!("date({col}, 'start of month')"),
+            ChronoAnalysisBasis::Quarterly => format!(
+                "date({col}, 'start of month', '-' || ((CAST(strftime('%m', {col}) AS INTEGER) - 1) % 3) || ' months')"
+            ),
+            ChronoAnalysisBasis::Yearly => format!("date({col}, 'start of year')"),
+            ChronoAnalysisBasis::Interval(secs) => format!(
+                "datetime(CAST(strftime('%s', {col}) AS INTEGER) / {secs} * {secs}, 'unixepoch')"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::query::{
+        filter::{Filter, FilterChain, FilterCombinator, FilterExpression, FilterGroup, FilterNode},
+        BasableQuery, QueryCommand, QueryExpr, QueryOrder,
+    };
+
+    use super::{Dialect, MySqlDialect, PostgresDialect, SqliteDialect};
+    use crate::{
+        db::{QueryParams, QuerySqlParser, SqlQuery},
+        graphs::{chrono::ChronoAnalysisOpts, trend::TrendGraphOpts, FromQueryParams},
+    };
+
+    struct Parser<D: Dialect>(D);
+
+    impl<D: Dialect> QuerySqlParser for Parser<D> {
+        fn dialect(&self) -> &dyn Dialect {
+            &self.0
+        }
+    }
+
+    fn query() -> BasableQuery {
+        let mut filters = FilterChain::new();
+        filters.add_one(Filter {
+            combinator: FilterCombinator::BASE,
+            column: "name".to_string(),
+            expression: FilterExpression::Regex("^a".to_string()),
+            table: None,
+        });
+        filters.add_one(Filter {
+            combinator: FilterCombinator::AND,
+            column: "age".to_string(),
+            expression: FilterExpression::Gt("18".to_string()),
+            table: None,
+        });
+
+        BasableQuery {
+            table: "users".to_string(),
+            command: QueryCommand::SelectData(Some(vec!["name".into()])),
+            filters,
+            order_by: Some(QueryOrder::DESC("age".to_string())),
+            row_count: Some(10),
+            offset: Some(20),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_sql() {
+        let sql = Parser(MySqlDialect).generate_sql(query()).unwrap();
+        assert_eq!(
+            sql.sql,
+            "SELECT `name` FROM `users` WHERE `name` REGEXP ? AND `age` > ? ORDER BY `age` DESC LIMIT 20, 10"
+        );
+
+        let sql = Parser(PostgresDialect).generate_sql(query()).unwrap();
+        assert_eq!(
+            sql.sql,
+            r#"SELECT "name" FROM "users" WHERE "name"::text ~ $1 AND "age" > $2 ORDER BY "age" DESC LIMIT 10 OFFSET 20"#
+        );
+
+        let sql = Parser(SqliteDialect).generate_sql(query()).unwrap();
+        assert_eq!(
+            sql.sql,
+            r#"SELECT "name" FROM "users" WHERE CAST("name" AS TEXT) REGEXP ? AND "age" > ? ORDER BY "age" DESC LIMIT 10 OFFSET 20"#
+        );
+        assert_eq!(sql.params.len(), 2);
+
+        let query = BasableQuery {
+            order_by: Some(QueryOrder::DescNullsLast("age".to_string())),
+            row_count: None,
+            ..query()
+        };
+        let sql = Parser(MySqlDialect).generate_sql(query.clone()).unwrap();
+        assert!(sql.sql.ends_with("ORDER BY `age` IS NULL, `age` DESC"), "{}", sql.sql);
+
+        let sql = Parser(PostgresDialect).generate_sql(query).unwrap();
+        assert!(sql.sql.ends_with(r#"ORDER BY "age" DESC NULLS LAST"#), "{}", sql.sql);
+    }
+
+    #[test]
+    fn test_nested_filters() {
+        let condition = |combinator, column: &str, value: &str| Filter {
+            combinator,
+            column: column.to_string(),
+            expression: FilterExpression::Eq(value.to_string()),
+            table: None,
+        };
+
+        let mut filters = FilterChain::new();
+        filters.add_one(condition(FilterCombinator::BASE, "a", "1"));
+        filters.add_one(FilterGroup {
+            combinator: FilterCombinator::AND,
+            filters: vec![
+                condition(FilterCombinator::BASE, "b", "2").into(),
+                condition(FilterCombinator::OR, "c", "3").into(),
+                FilterGroup {
+                    combinator: FilterCombinator::OR,
+                    filters: vec![],
+                }
+                .into(),
+            ],
+        });
+
+        let query = BasableQuery {
+            table: "users".to_string(),
+            filters,
+            ..Default::default()
+        };
+
+        let sql = Parser(PostgresDialect).generate_sql(query).unwrap();
+        assert_eq!(
+            sql.sql,
+            r#"SELECT * FROM "users" WHERE "a" = $1 AND ("b" = $2 OR "c" = $3)"#
+        );
+        assert_eq!(sql.params.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_filter_nodes() {
+        let parse = |json: &str| {
+            let nodes: Vec<FilterNode> = serde_json::from_str(json).unwrap();
+            let mut params = QueryParams::new(PostgresDialect.placeholder());
+            let sql = Parser(PostgresDialect).parse_filter_nodes(&nodes, &mut params);
+            let query = SqlQuery::new(sql, params);
+            (query.sql, query.params.len())
+        };
+
+        // Groups nest, and their first node has no combinator, whatever it's set to.
+        let (sql, params) = parse(
+            r#"[
+                {"combinator": "BASE", "column": "a", "expression": {"Eq": "1"}},
+                {"combinator": "OR", "filters": [
+                    {"combinator": "AND", "column": "b", "expression": {"Eq": "2"}},
+                    {"combinator": "AND", "filters": [
+                        {"combinator": "BASE", "column": "c", "expression": {"Eq": "3"}},
+                        {"combinator": "OR", "column": "d", "expression": "Null"}
+                    ]}
+                ]}
+            ]"#,
+        );
+        assert_eq!(sql, r#""a" = $1 OR ("b" = $2 AND ("c" = $3 OR "d" IS NULL))"#);
+        assert_eq!(params, 3);
+
+        // Empty groups are left out, even as the first node.
+        let (sql, params) = parse(
+            r#"[
+                {"combinator": "BASE", "filters": []},
+                {"combinator": "OR", "column": "a", "expression": {"Eq": "1"}},
+                {"combinator": "AND", "filters": [{"combinator": "BASE", "filters": []}]}
+            ]"#,
+        );
+        assert_eq!(sql, r#""a" = $1"#);
+        assert_eq!(params, 1);
+
+        let (sql, params) = parse(r#"[{"combinator": "AND", "filters": []}]"#);
+        assert_eq!((sql.as_str(), params), ("", 0));
+    }
+
+    #[test]
+    fn test_graph_filters() {
+        let params = [
+            ("table", "orders"),
+            ("column", "created_at"),
+            ("basis", "Month"),
+            ("range", "2024-01-01 range 2024-12-31"),
+            (
+                "filters",
+                r#"[{ "combinator": "BASE", "column": "status", "expression": { "Eq": "shipped" } }]"#,
+            ),
+        ];
+        let params = params
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        let opts = ChronoAnalysisOpts::from_query_params(params).unwrap();
+        let sql = Parser(PostgresDialect)
+            .generate_sql(opts.query(&PostgresDialect))
+            .unwrap();
+
+        assert_eq!(
+            sql.sql,
+            r#"SELECT date_trunc('month', "created_at"::timestamp) AS "BASABLE_CHRONO_BASIS_VALUE", COUNT(*) AS "BASABLE_CHRONO_RESULT" FROM "orders" WHERE "created_at" >= $1 AND "created_at" < $2 AND ("status" = $3) GROUP BY date_trunc('month', "created_at"::timestamp) ORDER BY "BASABLE_CHRONO_BASIS_VALUE" ASC"#
+        );
+        assert_eq!(sql.params.len(), 3);
+    }
+
+    #[test]
+    fn test_column_quoting() {
+        // Columns are quoted whatever they hold, so they can't be read as SQL.
+        let query = BasableQuery {
+            table: "users".to_string(),
+            command: QueryCommand::SelectData(Some(vec![
+                "COUNT(*)".into(),
+                r#"id", "password"#.into(),
+                QueryExpr::Sql("COUNT(*)".to_string()),
+            ])),
+            group_by: Some(vec!["(SELECT 1)".into()]),
+            order_by: Some(QueryOrder::ASC("*".to_string())),
+            ..Default::default()
+        };
+
+        let sql = Parser(PostgresDialect).generate_sql(query).unwrap();
+        assert_eq!(
+            sql.sql,
+            r#"SELECT "COUNT(*)", "id"", ""password", COUNT(*) FROM "users" GROUP BY "(SELECT 1)" ORDER BY "*" ASC"#
+        );
+    }
+
+    #[test]
+    fn test_cross_trend() {
+        let params = [
+            ("table", "users"),
+            ("graph_type", "cross"),
+            ("xcol", "name"),
+            ("ycol", "user_id"),
+            ("foreign_table", "orders"),
+            ("target_column", "id"),
+            (
+                "filters",
+                r#"[{ "combinator": "BASE", "column": "x\".\"secret", "expression": "NotNull" }]"#,
+            ),
+        ];
+        let params = params
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        let opts = TrendGraphOpts::from_query_params(params).unwrap();
+        let sql = Parser(SqliteDialect)
+            .generate_sql(opts.query(&SqliteDialect).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            sql.sql,
+            r#"SELECT "x"."name" AS "name", COUNT("y"."user_id") AS "user_id" FROM "users" "x" LEFT JOIN "orders" "y" ON "x"."id" = "y"."user_id" WHERE "y"."user_id" IS NOT NULL AND ("x"."x"".""secret" IS NOT NULL) GROUP BY "x"."name" ORDER BY "user_id" DESC"#
+        );
+    }
+}
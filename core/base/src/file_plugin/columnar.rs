@@ -0,0 +1,152 @@
+use std::{fs::File, path::Path};
+
+use arrow_array::{
+    cast::AsArray,
+    types::{Float64Type, Int64Type},
+    Array, RecordBatch,
+};
+use arrow_cast::{
+    cast,
+    display::{ArrayFormatter, FormatOptions},
+};
+use arrow_ipc::reader::{FileReader, StreamReader};
+use arrow_schema::{ArrowError, DataType, SchemaRef};
+use axum::http::StatusCode;
+use common::error::AppError;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use rusqlite::types::Value;
+
+use super::{FileColumn, FileTable, Inferred};
+
+fn map_err(err: impl ToString) -> AppError {
+    AppError::HttpError(StatusCode::BAD_REQUEST, err.to_string())
+}
+
+/// Read a Parquet file, with columns taken from its schema.
+pub(super) fn read_parquet(name: String, path: &Path) -> Result<FileTable, AppError> {
+    let file = File::open(path).map_err(map_err)?;
+
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file).map_err(map_err)?;
+    let schema = builder.schema().clone();
+    let reader = builder.build().map_err(map_err)?;
+
+    build_table(name, schema, reader)
+}
+
+/// Read an Arrow IPC file, as written for Feather v2. IPC streams are read as well.
+pub(super) fn read_ipc(name: String, path: &Path) -> Result<FileTable, AppError> {
+    let file = File::open(path).map_err(map_err)?;
+
+    match FileReader::try_new(file, None) {
+        Ok(reader) => build_table(name, reader.schema(), reader),
+        Err(_) => {
+            let file = File::open(path).map_err(map_err)?;
+            let reader = StreamReader::try_new(file, None).map_err(map_err)?;
+            build_table(name, reader.schema(), reader)
+        }
+    }
+}
+
+/// The SQLite type a column of `data_type` is stored as.
+fn inferred(data_type: &DataType) -> Inferred {
+    match data_type {
+        DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => Inferred::Integer,
+        DataType::Float16
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Decimal128(..)
+        | DataType::Decimal256(..) => Inferred::Real,
+        DataType::Date32 | DataType::Date64 => Inferred::Date,
+        DataType::Timestamp(..) => Inferred::DateTime,
+        _ => Inferred::Text,
+    }
+}
+
+fn build_table(
+    name: String,
+    schema: SchemaRef,
+    batches: impl Iterator<Item = Result<RecordBatch, ArrowError>>,
+) -> Result<FileTable, AppError> {
+    let types: Vec<Inferred> = schema.fields().iter().map(|f| inferred(f.data_type())).collect();
+
+    let mut rows: Vec<Vec<Value>> = Vec::new();
+
+    for batch in batches {
+        let batch = batch.map_err(map_err)?;
+
+        let columns = batch
+            .columns()
+            .iter()
+            .zip(&types)
+            .map(|(array, ty)| values(array, ty))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for idx in 0..batch.num_rows() {
+            rows.push(columns.iter().map(|values| values[idx].clone()).collect());
+        }
+    }
+
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(&types)
+        .map(|(field, ty)| FileColumn {
+            name: field.name().clone(),
+            col_type: ty.col_type(),
+        })
+        .collect();
+
+    Ok(FileTable {
+        name,
+        columns,
+        rows,
+    })
+}
+
+/// Convert a column of a record batch to SQLite values.
+fn values(array: &dyn Array, ty: &Inferred) -> Result<Vec<Value>, AppError> {
+    let len = array.len();
+
+    let values = match ty {
+        Inferred::Integer => {
+            let array = cast(array, &DataType::Int64).map_err(map_err)?;
+            array
+                .as_primitive::<Int64Type>()
+                .iter()
+                .map(|v| v.map_or(Value::Null, Value::Integer))
+                .collect()
+        }
+        Inferred::Real => {
+            let array = cast(array, &DataType::Float64).map_err(map_err)?;
+            array
+                .as_primitive::<Float64Type>()
+                .iter()
+                .map(|v| v.map_or(Value::Null, Value::Real))
+                .collect()
+        }
+        _ => {
+            let options = FormatOptions::default();
+            let formatter = ArrayFormatter::try_new(array, &options).map_err(map_err)?;
+
+            (0..len)
+                .map(|idx| {
+                    if array.is_null(idx) {
+                        Value::Null
+                    } else {
+                        Value::Text(formatter.value(idx).to_string())
+                    }
+                })
+                .collect()
+        }
+    };
+
+    Ok(values)
+}
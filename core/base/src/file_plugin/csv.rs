@@ -0,0 +1,64 @@
+use std::path::Path;
+
+use axum::http::StatusCode;
+use common::error::AppError;
+
+use super::{column_names, FileColumn, FileTable, Inferred};
+
+/// Read a delimited text file whose first line holds the column names.
+/// Column types are inferred from the values, with empty fields read as NULL.
+pub(super) fn read(name: String, path: &Path, delimiter: u8) -> Result<FileTable, AppError> {
+    let map_err = |err: csv::Error| AppError::HttpError(StatusCode::BAD_REQUEST, err.to_string());
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)
+        .map_err(map_err)?;
+
+    let headers = reader.headers().map_err(map_err)?;
+    let names = column_names(headers.iter().map(String::from));
+
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(map_err)?;
+
+    // Columns with no values at all are read as text.
+    let types: Vec<Inferred> = (0..names.len())
+        .map(|idx| {
+            records
+                .iter()
+                .filter_map(|r| r.get(idx).map(str::trim).filter(|v| !v.is_empty()))
+                .map(Inferred::of)
+                .reduce(Inferred::merge)
+                .unwrap_or(Inferred::Text)
+        })
+        .collect();
+
+    let rows = records
+        .iter()
+        .map(|r| {
+            types
+                .iter()
+                .enumerate()
+                .map(|(idx, ty)| ty.value(r.get(idx).map(str::trim).unwrap_or_default()))
+                .collect()
+        })
+        .collect();
+
+    let columns = names
+        .into_iter()
+        .zip(&types)
+        .map(|(name, ty)| FileColumn {
+            name,
+            col_type: ty.col_type(),
+        })
+        .collect();
+
+    Ok(FileTable {
+        name,
+        columns,
+        rows,
+    })
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use axum::http::StatusCode;
+use common::error::AppError;
+use rusqlite::types::Value;
+use serde_json::Value as JsonValue;
+
+use super::{FileColumn, FileTable, Inferred};
+
+/// Read a file holding a JSON array of objects, or a single object.
+pub(super) fn read(name: String, path: &Path) -> Result<FileTable, AppError> {
+    let content = read_content(path)?;
+    let value: JsonValue = serde_json::from_str(&content).map_err(map_err)?;
+
+    let records = match value {
+        JsonValue::Array(records) => records,
+        record => vec![record],
+    };
+
+    Ok(build_table(name, records))
+}
+
+/// Read a newline-delimited JSON file, with one record per line.
+pub(super) fn read_lines(name: String, path: &Path) -> Result<FileTable, AppError> {
+    let content = read_content(path)?;
+
+    let records = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<JsonValue>, _>>()
+        .map_err(map_err)?;
+
+    Ok(build_table(name, records))
+}
+
+fn read_content(path: &Path) -> Result<String, AppError> {
+    fs::read_to_string(path).map_err(|err| AppError::HttpError(StatusCode::BAD_REQUEST, err.to_string()))
+}
+
+fn map_err(err: serde_json::Error) -> AppError {
+    AppError::HttpError(StatusCode::BAD_REQUEST, err.to_string())
+}
+
+/// Flatten nested objects into dotted keys, so `{"address": {"city": "Lagos"}}` becomes
+/// `address.city`. Arrays are kept whole and stored as JSON text.
+fn flatten(prefix: &str, value: JsonValue, out: &mut Vec<(String, JsonValue)>) {
+    match value {
+        JsonValue::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}.{key}")
+                };
+
+                flatten(&key, value, out);
+            }
+        }
+        value => out.push((prefix.to_string(), value)),
+    }
+}
+
+fn build_table(name: String, records: Vec<JsonValue>) -> FileTable {
+    let mut columns: Vec<String> = Vec::new();
+    let mut types: HashMap<String, Inferred> = HashMap::new();
+
+    // Records that aren't objects are kept under a single `value` column.
+    let records: Vec<HashMap<String, JsonValue>> = records
+        .into_iter()
+        .map(|record| {
+            let mut fields = Vec::new();
+            match record {
+                JsonValue::Object(_) => flatten("", record, &mut fields),
+                value => fields.push(("value".to_string(), value)),
+            }
+
+            for (key, value) in &fields {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+
+                if let Some(ty) = inferred(value) {
+                    types
+                        .entry(key.clone())
+                        .and_modify(|t| *t = t.merge(ty))
+                        .or_insert(ty);
+                }
+            }
+
+            fields.into_iter().collect()
+        })
+        .collect();
+
+    let types: Vec<Inferred> = columns
+        .iter()
+        .map(|c| types.get(c).copied().unwrap_or(Inferred::Text))
+        .collect();
+
+    let rows = records
+        .iter()
+        .map(|record| {
+            columns
+                .iter()
+                .zip(&types)
+                .map(|(col, ty)| record.get(col).map_or(Value::Null, |v| value(ty, v)))
+                .collect()
+        })
+        .collect();
+
+    let columns = columns
+        .into_iter()
+        .zip(&types)
+        .map(|(name, ty)| FileColumn {
+            name,
+            col_type: ty.col_type(),
+        })
+        .collect();
+
+    FileTable {
+        name,
+        columns,
+        rows,
+    }
+}
+
+/// Strings are only read as dates, never as numbers, since JSON already types its numbers.
+fn inferred(value: &JsonValue) -> Option<Inferred> {
+    match value {
+        JsonValue::Null => None,
+        JsonValue::Bool(_) => Some(Inferred::Integer),
+        JsonValue::Number(n) if n.is_i64() => Some(Inferred::Integer),
+        JsonValue::Number(_) => Some(Inferred::Real),
+        JsonValue::String(s) => match Inferred::of(s) {
+            ty @ (Inferred::Date | Inferred::DateTime) => Some(ty),
+            _ => Some(Inferred::Text),
+        },
+        _ => Some(Inferred::Text),
+    }
+}
+
+fn value(ty: &Inferred, value: &JsonValue) -> Value {
+    match (ty, value) {
+        (_, JsonValue::Null) => Value::Null,
+        (Inferred::Integer, JsonValue::Bool(b)) => Value::Integer(*b as i64),
+        (Inferred::Integer, JsonValue::Number(n)) => n.as_i64().map_or(Value::Null, Value::Integer),
+        (Inferred::Real, JsonValue::Number(n)) => n.as_f64().map_or(Value::Null, Value::Real),
+        (_, JsonValue::String(s)) => Value::Text(s.clone()),
+        (_, v) => Value::Text(v.to_string()),
+    }
+}
@@ -0,0 +1,345 @@
+//! Data files opened as tables.
+//!
+//! Files are read once when the connection is created and loaded into an in-memory SQLite
+//! database, which is then served by [`sqlite_plugin`](crate::sqlite_plugin).
+
+use std::{fs, path::Path, sync::Arc};
+
+use axum::http::StatusCode;
+use common::error::AppError;
+use rusqlite::{params_from_iter, types::Value, Connection};
+use time::{format_description::FormatItem, macros::format_description, Date, PrimitiveDateTime};
+
+use crate::{
+    config::FileType,
+    connector::Connector,
+    dialect::quote_ident,
+    registry::BackendRegistry,
+    sqlite_plugin::{connector::SqliteConnector, db::SqliteDB},
+};
+
+mod columnar;
+mod csv;
+mod json;
+
+/// Register `file` sources for every supported file type.
+pub fn register(registry: &mut BackendRegistry) {
+    for source in ["csv", "tsv", "json", "ndjson", "parquet", "arrow", "feather"] {
+        registry.register("file", source, |config, id, user_id| {
+            let conn = SqliteConnector::new(config.clone())?;
+            Ok(Box::new(SqliteDB::new(Arc::new(conn), id, user_id)))
+        });
+    }
+}
+
+const DATE_FORMAT: &[FormatItem<'static>] = format_description!("[year]-[month]-[day]");
+const DATETIME_FORMATS: [&[FormatItem<'static>]; 2] = [
+    format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+    format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]"),
+];
+
+/// Column types, from the most to the least specific.
+#[derive(Clone, Copy, PartialEq)]
+enum Inferred {
+    Integer,
+    Real,
+    Date,
+    DateTime,
+    Text,
+}
+
+impl Inferred {
+    /// The type of `value`. Numbers that wouldn't be written the same once stored, such as zip
+    /// codes or ids with leading zeros like `02134`, are text, as are `NaN` and infinities.
+    fn of(value: &str) -> Self {
+        let number = is_plain_number(value);
+
+        if number && value.parse::<i64>().is_ok() {
+            Inferred::Integer
+        } else if number && value.parse::<f64>().is_ok_and(f64::is_finite) {
+            Inferred::Real
+        } else if Date::parse(value, DATE_FORMAT).is_ok() {
+            Inferred::Date
+        } else if DATETIME_FORMATS
+            .iter()
+            .any(|f| PrimitiveDateTime::parse(value, f).is_ok())
+        {
+            Inferred::DateTime
+        } else {
+            Inferred::Text
+        }
+    }
+
+    /// The narrowest type that holds values of both `self` and `other`.
+    fn merge(self, other: Self) -> Self {
+        use Inferred::*;
+
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Integer, Real) | (Real, Integer) => Real,
+            (Date, DateTime) | (DateTime, Date) => DateTime,
+            _ => Text,
+        }
+    }
+
+    fn col_type(&self) -> &'static str {
+        match self {
+            Inferred::Integer => "INTEGER",
+            Inferred::Real => "REAL",
+            Inferred::Date => "DATE",
+            Inferred::DateTime => "DATETIME",
+            Inferred::Text => "TEXT",
+        }
+    }
+
+    fn value(&self, raw: &str) -> Value {
+        if raw.is_empty() {
+            return Value::Null;
+        }
+
+        match self {
+            Inferred::Integer => raw.parse().map_or(Value::Null, Value::Integer),
+            Inferred::Real => raw.parse().map_or(Value::Null, Value::Real),
+            _ => Value::Text(raw.to_string()),
+        }
+    }
+}
+
+/// Whether `value` starts like a number written without a sign or leading zeros, such as `0`,
+/// `0.5`, `-12` or `3e8`.
+fn is_plain_number(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let leading_zero = digits.starts_with('0') && digits.len() > 1 && !digits[1..].starts_with('.');
+
+    digits.starts_with(|c: char| c.is_ascii_digit()) && !leading_zero
+}
+
+/// A table read from a data file.
+pub(crate) struct FileTable {
+    pub name: String,
+    pub columns: Vec<FileColumn>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+pub(crate) struct FileColumn {
+    pub name: String,
+
+    /// SQLite type the column is declared with. This is what `query_columns` reports.
+    pub col_type: &'static str,
+}
+
+/// Read the file (or every file of `file_type` in the directory) at `path` into `conn`.
+/// Each file becomes a table named after the file. Table names are case-insensitive, so files
+/// of a directory named alike, such as `sales.json` and `Sales.json`, get a suffix like
+/// `sales_2`.
+///
+/// Symbolic links in a directory are skipped, since they could lead out of the data directory
+/// `path` was resolved in.
+pub(crate) fn load_files(conn: &Connection, file_type: &FileType, path: &str) -> Result<(), AppError> {
+    let path = Path::new(path);
+
+    let files = if path.is_dir() {
+        let mut files: Vec<_> = fs::read_dir(path)
+            .map_err(|err| AppError::HttpError(StatusCode::BAD_REQUEST, err.to_string()))?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+            .map(|entry| entry.path())
+            .filter(|p| {
+                p.extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| file_type.extensions().contains(&ext.to_lowercase().as_str()))
+            })
+            .collect();
+
+        files.sort();
+        files
+    } else {
+        vec![path.to_path_buf()]
+    };
+
+    let mut names: Vec<String> = Vec::new();
+    for file in files {
+        let base = file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+
+        let mut name = base.clone();
+        let mut n = 1;
+        while names.contains(&name.to_lowercase()) {
+            n += 1;
+            name = format!("{base}_{n}");
+        }
+
+        if name != base {
+            let file = file.display();
+            tracing::warn!("table '{base}' already exists, so '{file}' is read as '{name}'");
+        }
+
+        names.push(name.to_lowercase());
+        let table = read_file(file_type, &file, name)?;
+        create_table(conn, &table)?;
+    }
+
+    Ok(())
+}
+
+fn read_file(file_type: &FileType, path: &Path, name: String) -> Result<FileTable, AppError> {
+    match file_type {
+        FileType::Csv => csv::read(name, path, b','),
+        FileType::Tsv => csv::read(name, path, b'\t'),
+        FileType::Json => json::read(name, path),
+        FileType::Ndjson => json::read_lines(name, path),
+        FileType::Parquet => columnar::read_parquet(name, path),
+        FileType::Arrow => columnar::read_ipc(name, path),
+    }
+}
+
+fn create_table(conn: &Connection, table: &FileTable) -> Result<(), AppError> {
+    let map_err = |err: rusqlite::Error| AppError::ServerError(err.to_string());
+
+    let name = quote_ident(&table.name);
+    let columns: Vec<String> = table
+        .columns
+        .iter()
+        .map(|c| format!("{} {}", quote_ident(&c.name), c.col_type))
+        .collect();
+
+    let tx = conn.unchecked_transaction().map_err(map_err)?;
+    tx.execute(&format!("CREATE TABLE {name} ({})", columns.join(", ")), [])
+        .map_err(map_err)?;
+
+    {
+        let placeholders = vec!["?"; table.columns.len()].join(", ");
+        let mut stmt = tx
+            .prepare(&format!("INSERT INTO {name} VALUES ({placeholders})"))
+            .map_err(map_err)?;
+
+        for row in &table.rows {
+            stmt.execute(params_from_iter(row)).map_err(map_err)?;
+        }
+    }
+
+    tx.commit().map_err(map_err)
+}
+
+/// Make column names usable and unique: blanks get a positional name and repeats get a suffix.
+pub(crate) fn column_names(names: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+
+    for (idx, name) in names.into_iter().enumerate() {
+        let name = name.trim();
+        let base = if name.is_empty() {
+            format!("column_{}", idx + 1)
+        } else {
+            name.to_string()
+        };
+
+        let mut name = base.clone();
+        let mut n = 1;
+        while columns.contains(&name) {
+            n += 1;
+            name = format!("{base}_{n}");
+        }
+
+        columns.push(name);
+    }
+
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rusqlite::types::Value;
+    use uuid::Uuid;
+
+    use crate::{
+        config::{ConfigRaw, DataDir},
+        registry::BackendRegistry,
+    };
+
+    #[test]
+    fn test_file_source_paths() {
+        let root = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(root.join("data")).unwrap();
+        fs::write(root.join("data/orders.csv"), "id,total\n1,20\n").unwrap();
+        fs::write(root.join("secret.csv"), "key\nvalue\n").unwrap();
+
+        let mut registry = BackendRegistry::default();
+        registry.set_data_dir(DataDir::new(root.join("data"), &[]).unwrap());
+
+        let connect = |path: &str| {
+            let config = ConfigRaw {
+                source_type: "file".to_string(),
+                source: "csv".to_string(),
+                path: Some(path.to_string()),
+                ..Default::default()
+            };
+            registry.create(&config, Uuid::new_v4(), "user".to_string())
+        };
+
+        let db = connect("orders.csv").unwrap();
+        assert_eq!(db.tables().len(), 1);
+
+        let outside = root.join("secret.csv");
+        assert!(connect(&outside.to_string_lossy()).is_err());
+        assert!(connect("../secret.csv").is_err());
+
+        // Links out of the directory aren't followed when it's opened as a whole.
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, root.join("data/link.csv")).unwrap();
+            assert!(connect("link.csv").is_err());
+            assert_eq!(connect(".").unwrap().tables().len(), 1);
+        }
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_inferred_types() {
+        let root = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("places.csv");
+        fs::write(&path, "zip,people,area,code
+02134,0,0.5,NaN
+10001,-12,3e2,inf
+").unwrap();
+
+        let table = super::csv::read("places".to_string(), &path, b',').unwrap();
+        let types: Vec<_> = table.columns.iter().map(|c| c.col_type).collect();
+        assert_eq!(types, ["TEXT", "INTEGER", "REAL", "TEXT"]);
+        assert_eq!(table.rows[0][0], Value::Text("02134".to_string()));
+        assert_eq!(table.rows[1][3], Value::Text("inf".to_string()));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_table_names() {
+        let root = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        for file in ["Sales.csv", "sales.csv", "sales_2.csv"] {
+            fs::write(root.join(file), "id\n1\n").unwrap();
+        }
+
+        let mut registry = BackendRegistry::default();
+        registry.set_data_dir(DataDir::new(&root, &[]).unwrap());
+        let config = ConfigRaw {
+            source_type: "file".to_string(),
+            source: "csv".to_string(),
+            path: Some(".".to_string()),
+            ..Default::default()
+        };
+
+        let db = registry.create(&config, Uuid::new_v4(), "user".to_string()).unwrap();
+        let mut names: Vec<_> = db.tables().iter().map(|t| t.name().to_string()).collect();
+        names.sort();
+        assert_eq!(names, ["Sales", "sales_2", "sales_2_2"]);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
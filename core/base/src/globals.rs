@@ -1,2 +1,4 @@
 pub static BASABLE_CHRONO_XCOL: &str = "BASABLE_CHRONO_BASIS_VALUE";
-pub static BASABLE_CHRONO_YCOL: &str = "BASABLE_CHRONO_RESULT";
\ No newline at end of file
+pub static BASABLE_CHRONO_YCOL: &str = "BASABLE_CHRONO_RESULT";
+pub static BASABLE_GRAPH_YCOL: &str = "BASABLE_GRAPH_RESULT";
+pub static BASABLE_GRAPH_SERIES: &str = "BASABLE_GRAPH_SERIES";
@@ -0,0 +1,174 @@
+use std::{collections::HashMap, fmt::Display};
+
+use axum::http::StatusCode;
+use common::{data::row::ColumnValue, error::AppError};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use super::AnalysisValue;
+use crate::dialect::Dialect;
+
+/// How the rows of each point of a graph are summarized into its value.
+#[derive(Clone, Copy, Default, EnumIter)]
+pub enum Aggregate {
+    #[default]
+    Count,
+    CountDistinct,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Display for Aggregate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let aggregate = match self {
+            Aggregate::Count => "count",
+            Aggregate::CountDistinct => "count_distinct",
+            Aggregate::Sum => "sum",
+            Aggregate::Avg => "avg",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+        };
+
+        write!(f, "{aggregate}")
+    }
+}
+
+impl TryFrom<&String> for Aggregate {
+    type Error = AppError;
+
+    fn try_from(value: &String) -> Result<Self, Self::Error> {
+        for aggregate in Aggregate::iter() {
+            if &aggregate.to_string() == value {
+                return Ok(aggregate);
+            }
+        }
+
+        let iter: Vec<String> = Aggregate::iter().map(|a| a.to_string()).collect();
+        let aggregates = iter.join(", ");
+        let err = AppError::HttpError(
+            StatusCode::EXPECTATION_FAILED,
+            format!("Not a valid aggregate. Acceptable options are: {aggregates}."),
+        );
+        Err(err)
+    }
+}
+
+/// The value of each point of a graph: [`Aggregation::aggregate`] of
+/// [`Aggregation::value_column`]. Without a value column, rows are counted.
+#[derive(Clone, Default)]
+pub struct Aggregation {
+    pub aggregate: Aggregate,
+    pub value_column: Option<String>,
+}
+
+impl Aggregation {
+    /// Read the aggregation from the `aggregate` and `value_column` query parameters. Only
+    /// [`Aggregate::Count`], the default, can do without a value column.
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, AppError> {
+        let aggregate = params
+            .get("aggregate")
+            .map(Aggregate::try_from)
+            .transpose()?
+            .unwrap_or_default();
+        let value_column = params.get("value_column").cloned();
+
+        match (aggregate, &value_column) {
+            (Aggregate::Count, _) | (_, Some(_)) => Ok(Aggregation {
+                aggregate,
+                value_column,
+            }),
+            (aggregate, None) => Err(AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                format!("missing 'value_column' parameter for '{aggregate}'"),
+            )),
+        }
+    }
+
+    /// The SQL expression of the aggregation, written for `dialect`.
+    pub fn expr(&self, dialect: &dyn Dialect) -> String {
+        let Some(col) = &self.value_column else {
+            return "COUNT(*)".to_string();
+        };
+
+        let col = dialect.quote_ident(col);
+        match self.aggregate {
+            Aggregate::Count => format!("COUNT({col})"),
+            Aggregate::CountDistinct => format!("COUNT(DISTINCT {col})"),
+            Aggregate::Sum => format!("SUM({col})"),
+            Aggregate::Avg => format!("AVG({col})"),
+            Aggregate::Min => format!("MIN({col})"),
+            Aggregate::Max => format!("MAX({col})"),
+        }
+    }
+
+    /// The value of points without rows: zero for counts and sums, and null otherwise.
+    pub fn empty(&self) -> AnalysisValue {
+        match self.aggregate {
+            Aggregate::Count | Aggregate::CountDistinct | Aggregate::Sum => AnalysisValue::UInt(0),
+            Aggregate::Avg | Aggregate::Min | Aggregate::Max => AnalysisValue::NULL,
+        }
+    }
+
+    /// The graph value of an aggregated `value`.
+    ///
+    /// Counts, sums and averages are numbers, but backends sending decimals as text (such as
+    /// MySQL for `DECIMAL` sums) return them as such, so they're read back as exact numbers.
+    /// Minimums and maximums may be of any type, and are kept as they are.
+    pub fn result(&self, value: ColumnValue) -> AnalysisValue {
+        match (self.aggregate, value) {
+            (Aggregate::Min | Aggregate::Max, value) => value.into(),
+            (_, ColumnValue::Text(text)) => match text.trim().parse::<i64>() {
+                Ok(int) => ColumnValue::Int(int).into(),
+                Err(_) => ColumnValue::decimal(text.trim().to_string()).into(),
+            },
+            (_, value) => value.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use common::data::row::ColumnValue;
+
+    use super::Aggregation;
+    use crate::dialect::PostgresDialect;
+
+    #[test]
+    fn test_aggregation() {
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        let count = Aggregation::from_params(&params(&[])).unwrap();
+        assert_eq!(count.expr(&PostgresDialect), "COUNT(*)");
+
+        assert!(Aggregation::from_params(&params(&[("aggregate", "sum")])).is_err());
+        assert!(Aggregation::from_params(&params(&[("aggregate", "median")])).is_err());
+
+        let sum = params(&[("aggregate", "sum"), ("value_column", "amount")]);
+        let sum = Aggregation::from_params(&sum).unwrap();
+        assert_eq!(sum.expr(&PostgresDialect), r#"SUM("amount")"#);
+
+        // Decimals read as text are exact numbers again.
+        let value = sum.result(ColumnValue::Text("12345678901234567890.12".to_string()));
+        assert_eq!(serde_json::to_string(&value).unwrap(), "[12345678901234567890.12]");
+
+        let value = sum.result(ColumnValue::Decimal("-0.10".to_string()));
+        assert_eq!(serde_json::to_string(&value).unwrap(), "[-0.10]");
+
+        let value = sum.result(ColumnValue::Text("1e400".to_string()));
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#"["1e400"]"#);
+
+        let max = params(&[("aggregate", "max"), ("value_column", "code")]);
+        let max = Aggregation::from_params(&max).unwrap();
+        let value = max.result(ColumnValue::Text("007".to_string()));
+        assert_eq!(serde_json::to_string(&value).unwrap(), r#"["007"]"#);
+    }
+}
@@ -1,10 +1,11 @@
 use std::{collections::HashMap, fmt::Display};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{BasableQuery, QueryCommand}};
+use common::{error::AppError, query::{filter::{FilterChain, FilterNode}, BasableQuery, QueryCommand, QueryExpr}};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
-use super::FromQueryParams;
+use super::{aggregate::Aggregation, filters_param, series::SeriesOpts, FromQueryParams};
+use crate::{dialect::Dialect, globals::BASABLE_GRAPH_YCOL, DbType};
 
 #[derive(EnumIter)]
 pub enum CategoryAnalysis {
@@ -49,6 +50,27 @@ pub struct CategoryGraphOpts {
     pub analysis: CategoryAnalysis,
     pub target_column: String,
     pub limit: Option<usize>,
+
+    /// Filters of the analyzed rows.
+    pub filters: Vec<FilterNode>,
+
+    /// The value of each category, the number of rows by default.
+    pub aggregation: Aggregation,
+
+    /// Splits the analysis into several series.
+    pub series: Option<SeriesOpts>,
+}
+
+impl CategoryGraphOpts {
+    /// Make sure the analyzed table and columns exist in `db`.
+    pub fn validate(&self, db: &DbType) -> Result<(), AppError> {
+        let mut columns = vec![self.target_column.as_str()];
+        columns.extend(self.aggregation.value_column.as_deref());
+        columns.extend(self.series.as_ref().map(|series| series.column.as_str()));
+        columns.extend(self.filters.iter().flat_map(FilterNode::columns));
+
+        db.validate_columns(&self.table, &columns)
+    }
 }
 
 impl FromQueryParams for CategoryGraphOpts {
@@ -67,17 +89,21 @@ impl FromQueryParams for CategoryGraphOpts {
                 let analysis = graph_type.try_into()?;
                 let target_column = target_column.to_string();
 
-                let limit = cat_limit.map_or(Ok(0), |lmt| {
-                    lmt.parse::<usize>().map_err(|err| {
+                let limit = cat_limit
+                    .map(|lmt| lmt.parse::<usize>())
+                    .transpose()
+                    .map_err(|err| {
                         AppError::HttpError(StatusCode::EXPECTATION_FAILED, err.to_string())
-                    })
-                }).ok();
+                    })?;
 
                 let opts = CategoryGraphOpts {
                     table,
                     analysis,
                     target_column,
                     limit,
+                    filters: filters_param(&params)?,
+                    aggregation: Aggregation::from_params(&params)?,
+                    series: SeriesOpts::from_params(&params)?,
                 };
 
                 Ok(opts)
@@ -90,22 +116,38 @@ impl FromQueryParams for CategoryGraphOpts {
     }
 }
 
-impl From<CategoryGraphOpts> for BasableQuery {
-    fn from(value: CategoryGraphOpts) -> Self {
+impl CategoryGraphOpts {
+    /// Build the query aggregating rows of each value of the target column, written for
+    /// `dialect`.
+    pub fn query(self, dialect: &dyn Dialect) -> BasableQuery {
         let CategoryGraphOpts {
             table,
-            analysis,
+            analysis: _,
             target_column,
             limit,
-        } = value;
-
-        let selections = vec!["COUNT(*) as COUNT".to_string(), target_column.clone()];
+            filters,
+            aggregation,
+            series: _,
+        } = self;
+
+        let selections = vec![
+            QueryExpr::Sql(format!(
+                "{} AS {}",
+                aggregation.expr(dialect),
+                dialect.quote_ident(BASABLE_GRAPH_YCOL)
+            )),
+            target_column.as_str().into(),
+        ];
         let operation = QueryCommand::SelectData(Some(selections));
 
+        let mut chain = FilterChain::new();
+        chain.add_group(filters);
+
         BasableQuery {
             table,
             command: operation,
-            group_by: Some(vec![target_column]),
+            filters: chain,
+            group_by: Some(vec![target_column.into()]),
             row_count: limit,
             ..Default::default()
         }
@@ -1,78 +1,362 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{filter::{Filter, FilterChain, FilterCombinator, FilterExpression}, BasableQuery, QueryCommand, QueryOrder}};
-use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
-use super::FromQueryParams;
-use crate::globals::BASABLE_CHRONO_XCOL;
+use common::{data::row::ColumnValue, error::AppError, query::{filter::{Filter, FilterChain, FilterCombinator, FilterExpression, FilterNode}, BasableQuery, QueryCommand, QueryExpr, QueryOrder}};
+use time::{
+    format_description::FormatItem,
+    macros::{datetime, format_description, time},
+    Date, Duration, Month, PrimitiveDateTime, Time,
+};
+use super::{
+    aggregate::Aggregation, filters_param, series::SeriesOpts, AnalysisResult, AnalysisResults,
+    AnalysisValue, FromQueryParams,
+};
+use crate::{dialect::Dialect, globals::{BASABLE_CHRONO_XCOL, BASABLE_CHRONO_YCOL}, DbType};
 
-#[derive(Clone, EnumIter)]
+/// Most points of time a chrono analysis may have, so results stay small enough to send.
+const MAX_CHRONO_BUCKETS: usize = 100_000;
+
+/// Intervals are counted from there, in UTC, which dates without a time zone are taken to be in.
+const UNIX_EPOCH: PrimitiveDateTime = datetime!(1970-01-01 0:00);
+
+/// Periods of time rows are aggregated by. Each point of a chrono analysis is the start of a
+/// period.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ChronoAnalysisBasis {
+    Hourly,
     Daily,
+
+    /// ISO weeks, which start on Mondays.
+    Weekly,
+
     Monthly,
+    Quarterly,
     Yearly,
+
+    /// Periods of a fixed number of seconds, counted from the Unix epoch.
+    Interval(u32),
+}
+
+impl ChronoAnalysisBasis {
+    /// Start of the period `at` is in.
+    pub fn period_start(&self, at: PrimitiveDateTime) -> PrimitiveDateTime {
+        let date = at.date();
+        let midnight = |date: Date| date.midnight();
+
+        match self {
+            ChronoAnalysisBasis::Hourly => at.replace_time(Time::from_hms(at.hour(), 0, 0).unwrap_or(Time::MIDNIGHT)),
+            ChronoAnalysisBasis::Daily => midnight(date),
+            ChronoAnalysisBasis::Weekly => {
+                let days = date.weekday().number_days_from_monday();
+                midnight(date - Duration::days(days.into()))
+            }
+            ChronoAnalysisBasis::Monthly => midnight(date.replace_day(1).unwrap_or(date)),
+            ChronoAnalysisBasis::Quarterly => {
+                let month = (date.month() as u8 - 1) / 3 * 3 + 1;
+                let start = Month::try_from(month)
+                    .ok()
+                    .and_then(|month| Date::from_calendar_date(date.year(), month, 1).ok());
+                midnight(start.unwrap_or(date))
+            }
+            ChronoAnalysisBasis::Yearly => {
+                midnight(Date::from_ordinal_date(date.year(), 1).unwrap_or(date))
+            }
+            ChronoAnalysisBasis::Interval(secs) => {
+                let secs = i64::from(*secs);
+                let offset = (at - UNIX_EPOCH).whole_seconds();
+                UNIX_EPOCH + Duration::seconds(offset.div_euclid(secs) * secs)
+            }
+        }
+    }
+
+    /// Start of the period after the one starting at `start`.
+    fn next_period(&self, start: PrimitiveDateTime) -> Option<PrimitiveDateTime> {
+        match self {
+            ChronoAnalysisBasis::Hourly => start.checked_add(Duration::HOUR),
+            ChronoAnalysisBasis::Daily => start.checked_add(Duration::DAY),
+            ChronoAnalysisBasis::Weekly => start.checked_add(Duration::WEEK),
+            ChronoAnalysisBasis::Monthly => add_months(start.date(), 1).map(Date::midnight),
+            ChronoAnalysisBasis::Quarterly => add_months(start.date(), 3).map(Date::midnight),
+            ChronoAnalysisBasis::Yearly => add_months(start.date(), 12).map(Date::midnight),
+            ChronoAnalysisBasis::Interval(secs) => {
+                start.checked_add(Duration::seconds(i64::from(*secs)))
+            }
+        }
+    }
+
+    /// Whether periods start at times other than midnight.
+    fn has_time(&self) -> bool {
+        matches!(self, ChronoAnalysisBasis::Hourly | ChronoAnalysisBasis::Interval(_))
+    }
+}
+
+/// The first day of the month `months` after the month of `date`.
+fn add_months(date: Date, months: i32) -> Option<Date> {
+    let total = date.year() * 12 + date.month() as i32 - 1 + months;
+    let month = Month::try_from((total.rem_euclid(12) + 1) as u8).ok()?;
+
+    Date::from_calendar_date(total.div_euclid(12), month, 1).ok()
 }
 
 impl From<ChronoAnalysisBasis> for String {
     fn from(value: ChronoAnalysisBasis) -> Self {
         let basis = match value {
+            ChronoAnalysisBasis::Hourly => "Hour",
             ChronoAnalysisBasis::Daily => "Date",
+            ChronoAnalysisBasis::Weekly => "Week",
             ChronoAnalysisBasis::Monthly => "Month",
+            ChronoAnalysisBasis::Quarterly => "Quarter",
             ChronoAnalysisBasis::Yearly => "Year",
+            ChronoAnalysisBasis::Interval(secs) => {
+                let (count, unit) = match secs {
+                    s if s % 86400 == 0 => (s / 86400, "d"),
+                    s if s % 3600 == 0 => (s / 3600, "h"),
+                    s if s % 60 == 0 => (s / 60, "m"),
+                    s => (s, "s"),
+                };
+                return format!("{count}{unit}");
+            }
         };
 
         basis.to_string()
     }
 }
 
+/// Bases are named `Hour`, `Date`, `Week`, `Month`, `Quarter` and `Year`. Intervals are a number
+/// of seconds, minutes, hours or days, such as `15m`.
 impl TryFrom<String> for ChronoAnalysisBasis {
     type Error = String;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        for b in ChronoAnalysisBasis::iter() {
-            let test = String::from(b.clone());
-            if value == test {
-                return Ok(b);
+        let basis = match value.as_str() {
+            "Hour" => ChronoAnalysisBasis::Hourly,
+            "Date" => ChronoAnalysisBasis::Daily,
+            "Week" => ChronoAnalysisBasis::Weekly,
+            "Month" => ChronoAnalysisBasis::Monthly,
+            "Quarter" => ChronoAnalysisBasis::Quarterly,
+            "Year" => ChronoAnalysisBasis::Yearly,
+            interval => {
+                let unit = match interval.chars().last() {
+                    Some('s') => 1,
+                    Some('m') => 60,
+                    Some('h') => 3600,
+                    Some('d') => 86400,
+                    _ => return Err("error parsing analysis basis".to_string()),
+                };
+
+                let secs = interval[..interval.len() - 1]
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(|count| count.checked_mul(unit))
+                    .filter(|secs| *secs > 0)
+                    .ok_or_else(|| "error parsing analysis interval".to_string())?;
+
+                ChronoAnalysisBasis::Interval(secs)
             }
-        }
+        };
 
-        Err("error parsing analysis basis".to_string())
+        Ok(basis)
     }
 }
 
 impl Display for ChronoAnalysisBasis {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let basis: String = self.clone().into();
+        let basis: String = (*self).into();
         write!(f, "{}", basis)
     }
 }
 
-pub struct ChronoAnalysisRange(pub String, pub String);
+/// The period of time a chrono analysis covers, with both bounds included.
+pub struct ChronoAnalysisRange(pub RangeBound, pub RangeBound);
 impl ChronoAnalysisRange {
-    pub fn start(&self) -> &str {
-        &self.0
+    pub fn start(&self) -> RangeBound {
+        self.0
+    }
+
+    pub fn end(&self) -> RangeBound {
+        self.1
+    }
+
+    /// The first and last points of time of the range. An end date includes its whole day.
+    pub fn bounds(&self) -> (PrimitiveDateTime, PrimitiveDateTime) {
+        let start = match self.start() {
+            RangeBound::Date(date) => date.midnight(),
+            RangeBound::DateTime(at) => at,
+        };
+
+        let end = match self.end() {
+            RangeBound::Date(date) => date.with_time(time!(23:59:59.999_999_999)),
+            RangeBound::DateTime(at) => at,
+        };
+
+        (start, end)
+    }
+
+    /// Filters matching rows whose `column` is in the range. An end date is matched as anything
+    /// before the next day, so rows later on that day are included.
+    fn filters(&self, column: &str) -> [Filter; 2] {
+        let filter = |combinator, expression| Filter {
+            combinator,
+            column: column.to_string(),
+            expression,
+            table: None,
+        };
+
+        let end = match self.end() {
+            RangeBound::Date(date) => match date.next_day() {
+                Some(next) => FilterExpression::Lt(RangeBound::Date(next).to_string()),
+                None => FilterExpression::Lte(self.end().to_string()),
+            },
+            end @ RangeBound::DateTime(_) => FilterExpression::Lte(end.to_string()),
+        };
+
+        [
+            filter(FilterCombinator::BASE, FilterExpression::Gte(self.start().to_string())),
+            filter(FilterCombinator::AND, end),
+        ]
     }
+}
+
+/// A bound of a [`ChronoAnalysisRange`], in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeBound {
+    /// A whole day.
+    Date(Date),
+    DateTime(PrimitiveDateTime),
+}
+
+impl RangeBound {
+    /// Read a date such as `2024-01-31`, or a date and time such as `2024-01-31 10:30:00` or
+    /// `2024-01-31T10:30:00+01:00`. Times with an offset are converted to UTC, and fractions of
+    /// seconds are dropped.
+    fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let err = || {
+            format!(
+                "Can't read '{value}' as a date of the range. Use dates such as '2024-01-31', or dates and times such as '2024-01-31 10:30:00' or '2024-01-31T10:30:00Z'."
+            )
+        };
+
+        let Some((date, time)) = value.split_once([' ', 'T']) else {
+            return parse_date(value).map(RangeBound::Date).ok_or_else(err);
+        };
+
+        let date = parse_date(date).ok_or_else(err)?;
+        let (time, offset) = parse_time(time).ok_or_else(err)?;
+
+        PrimitiveDateTime::new(date, time)
+            .checked_sub(offset)
+            .map(RangeBound::DateTime)
+            .ok_or_else(err)
+    }
+}
+
+/// Bounds are written the way databases store dates and times, such as `2024-01-31` or
+/// `2024-01-31 10:30:00`, so they compare alike even where dates are stored as text, as in
+/// SQLite.
+impl Display for RangeBound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (date, time) = match self {
+            RangeBound::Date(date) => (*date, None),
+            RangeBound::DateTime(at) => (at.date(), Some(at.time())),
+        };
+
+        write!(f, "{:04}-{:02}-{:02}", date.year(), date.month() as u8, date.day())?;
+        if let Some(time) = time {
+            write!(f, " {:02}:{:02}:{:02}", time.hour(), time.minute(), time.second())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a date such as `2024-01-31`, or a date and time such as `2024-01-31 10:30:00` (or
+/// `2024-01-31T10:30:00`). Fractions of seconds and time zones after the time are ignored.
+fn parse_datetime(value: &str) -> Option<PrimitiveDateTime> {
+    const TIME: &[FormatItem<'static>] = format_description!("[hour]:[minute]:[second]");
+
+    let value = value.trim();
+    let (date, time) = match value.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Time::parse(time.get(..8)?, TIME).ok()?),
+        None => (value, Time::MIDNIGHT),
+    };
+
+    Some(PrimitiveDateTime::new(parse_date(date)?, time))
+}
+
+/// Read a date such as `2024-01-31`, which may also be written without leading zeros or with
+/// slashes, such as `2024/1/31`.
+fn parse_date(value: &str) -> Option<Date> {
+    let mut parts = value.split(['-', '/']);
+    let year = parts.next().filter(|year| year.len() == 4)?.parse().ok()?;
+    let month = Month::try_from(parts.next()?.parse::<u8>().ok()?).ok()?;
+    let day = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+/// Read a time such as `10:30:00`, with optional fractions of seconds, followed by an optional
+/// offset from UTC such as `Z`, `+01:00` or `-0530`. Times without an offset are in UTC.
+fn parse_time(value: &str) -> Option<(Time, Duration)> {
+    const TIME: &[FormatItem<'static>] = format_description!("[hour]:[minute]:[second]");
+
+    let time = Time::parse(value.get(..8)?, TIME).ok()?;
 
-    pub fn end(&self) -> &str {
-        &self.1
+    let mut rest = &value[8..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
     }
+
+    let offset = match rest {
+        "" | "Z" | "z" => Duration::ZERO,
+        _ => {
+            let sign = match rest.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+
+            let digits = rest[1..].replacen(':', "", 1);
+            let (hours, minutes) = match digits.len() {
+                2 => (digits.parse::<i64>().ok()?, 0),
+                4 => (digits[..2].parse::<i64>().ok()?, digits[2..].parse::<i64>().ok()?),
+                _ => return None,
+            };
+
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            Duration::minutes(sign * (hours * 60 + minutes))
+        }
+    };
+
+    Some((time, offset))
 }
 
+/// Ranges are two dates, or dates and times, separated by `range`, such as
+/// `2024-01-01 range 2024-12-31`.
 impl TryFrom<String> for ChronoAnalysisRange {
     type Error = String;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         let spl: Vec<&str> = value.split("range").collect();
         if spl.len() == 2 {
-            let start = spl.get(0).unwrap_or(&&"");
-            let end = spl.get(1).unwrap_or(&&"");
+            let start = spl.first().unwrap_or(&"");
+            let end = spl.get(1).unwrap_or(&"");
 
-            let range = ChronoAnalysisRange(start.trim().to_string(), end.trim().to_string());
+            let range = ChronoAnalysisRange(RangeBound::parse(start)?, RangeBound::parse(end)?);
             return Ok(range);
         }
 
-        Err("error parsing analysis range".to_string())
+        Err(format!(
+            "Can't read '{value}' as a range. Use two dates separated by 'range', such as '2024-01-01 range 2024-12-31'."
+        ))
     }
 }
 
@@ -81,6 +365,27 @@ pub struct ChronoAnalysisOpts {
     pub chrono_col: String,
     pub basis: ChronoAnalysisBasis,
     pub range: ChronoAnalysisRange,
+
+    /// Filters of the analyzed rows, on top of [`ChronoAnalysisOpts::range`].
+    pub filters: Vec<FilterNode>,
+
+    /// The value at each point of time, the number of rows by default.
+    pub aggregation: Aggregation,
+
+    /// Splits the analysis into several series.
+    pub series: Option<SeriesOpts>,
+}
+
+impl ChronoAnalysisOpts {
+    /// Make sure the analyzed table and columns exist in `db`.
+    pub fn validate(&self, db: &DbType) -> Result<(), AppError> {
+        let mut columns = vec![self.chrono_col.as_str()];
+        columns.extend(self.aggregation.value_column.as_deref());
+        columns.extend(self.series.as_ref().map(|series| series.column.as_str()));
+        columns.extend(self.filters.iter().flat_map(FilterNode::columns));
+
+        db.validate_columns(&self.table, &columns)
+    }
 }
 
 impl FromQueryParams for ChronoAnalysisOpts {
@@ -101,15 +406,17 @@ impl FromQueryParams for ChronoAnalysisOpts {
                 });
 
                 let range = range.to_owned().try_into();
-                let range = range.map_err(|err: String| {
-                    AppError::HttpError(StatusCode::EXPECTATION_FAILED, err)
-                });
+                let range =
+                    range.map_err(|err: String| AppError::HttpError(StatusCode::BAD_REQUEST, err));
 
                 let opts = ChronoAnalysisOpts {
                     table: table.to_owned(),
                     chrono_col: column.to_owned(),
                     basis: basis?,
                     range: range?,
+                    filters: filters_param(&params)?,
+                    aggregation: Aggregation::from_params(&params)?,
+                    series: SeriesOpts::from_params(&params)?,
                 };
 
                 Ok(opts)
@@ -126,36 +433,43 @@ impl FromQueryParams for ChronoAnalysisOpts {
     }
 }
 
-impl From<ChronoAnalysisOpts> for BasableQuery {
-    fn from(value: ChronoAnalysisOpts) -> Self {
+impl ChronoAnalysisOpts {
+    /// Build the query aggregating rows of [`ChronoAnalysisOpts::table`] at each point of time,
+    /// written for `dialect`.
+    pub fn query(self, dialect: &dyn Dialect) -> BasableQuery {
         let ChronoAnalysisOpts {
             table,
             chrono_col,
             basis,
             range,
-        } = value;
+            filters: extra_filters,
+            aggregation,
+            series: _,
+        } = self;
+
+        let x = dialect.chrono_expr(&basis, &dialect.quote_ident(&chrono_col));
 
         // create query operation type
         let selections = Some(vec![
-            format!("{basis}({chrono_col}) AS {BASABLE_CHRONO_XCOL}"),
-            format!("COUNT(*) AS {BASABLE_CHRONO_XCOL}"),
+            QueryExpr::Sql(format!("{x} AS {}", dialect.quote_ident(BASABLE_CHRONO_XCOL))),
+            QueryExpr::Sql(format!(
+                "{} AS {}",
+                aggregation.expr(dialect),
+                dialect.quote_ident(BASABLE_CHRONO_YCOL)
+            )),
         ]);
 
         let operation = QueryCommand::SelectData(selections);
 
         // create query filters
-        let filter = Filter {
-            combinator: FilterCombinator::BASE,
-            column: chrono_col.clone(),
-            expression: FilterExpression::Btw(range.start().to_string(), range.end().to_string()),
-        };
-
         let mut filters = FilterChain::new();
-        filters.add_one(filter);
+        for filter in range.filters(&chrono_col) {
+            filters.add_one(filter);
+        }
+        filters.add_group(extra_filters);
 
         // creating grouping
-        let group_columns = vec![format!("{basis}({chrono_col})")];
-        let group_by = Some(group_columns);
+        let group_by = Some(vec![QueryExpr::Sql(x)]);
 
         let order_by = Some(QueryOrder::ASC(BASABLE_CHRONO_XCOL.to_string()));
 
@@ -169,3 +483,185 @@ impl From<ChronoAnalysisOpts> for BasableQuery {
         }
     }
 }
+
+impl ChronoAnalysisOpts {
+    /// The points of time of the analysis, one at the start of each period of
+    /// [`ChronoAnalysisOpts::range`].
+    pub fn buckets(&self) -> Result<ChronoBuckets, AppError> {
+        let err = |msg: String| AppError::HttpError(StatusCode::EXPECTATION_FAILED, msg);
+
+        let (start, end) = self.range.bounds();
+
+        let mut periods = Vec::new();
+        let mut period = Some(self.basis.period_start(start));
+
+        while let Some(start) = period.filter(|start| *start <= end) {
+            if periods.len() == MAX_CHRONO_BUCKETS {
+                return Err(err(format!(
+                    "The range has more than {MAX_CHRONO_BUCKETS} periods of '{}'. Use a shorter range or longer periods.",
+                    self.basis
+                )));
+            }
+
+            periods.push(start);
+            period = self.basis.next_period(start);
+        }
+
+        Ok(ChronoBuckets {
+            basis: self.basis,
+            periods,
+            aggregation: self.aggregation.clone(),
+        })
+    }
+}
+
+/// The periods of a chrono analysis, which its results are put in.
+pub struct ChronoBuckets {
+    basis: ChronoAnalysisBasis,
+    periods: Vec<PrimitiveDateTime>,
+    aggregation: Aggregation,
+}
+
+impl ChronoBuckets {
+    /// Results of the analysis from `points`, the period starts and values its query returned.
+    /// Periods without rows get the [`Aggregation::empty`] value, so the results have no gaps.
+    pub fn results(
+        &self,
+        points: impl IntoIterator<Item = (ColumnValue, ColumnValue)>,
+    ) -> AnalysisResults {
+        let ChronoBuckets {
+            basis,
+            periods,
+            aggregation,
+        } = self;
+
+        let mut values: BTreeMap<PrimitiveDateTime, AnalysisValue> = periods
+            .iter()
+            .map(|start| (*start, aggregation.empty()))
+            .collect();
+
+        for (x, y) in points {
+            let start = match x {
+                ColumnValue::Date(y, m, d, h, min, s, _) => Month::try_from(m)
+                    .ok()
+                    .and_then(|m| Date::from_calendar_date(y.into(), m, d).ok())
+                    .zip(Time::from_hms(h, min, s).ok())
+                    .map(|(date, time)| PrimitiveDateTime::new(date, time)),
+                ColumnValue::Text(text) => parse_datetime(&text),
+                _ => None,
+            };
+
+            if let Some(start) = start {
+                values.insert(start, aggregation.result(y));
+            }
+        }
+
+        values
+            .into_iter()
+            .map(|(start, y)| {
+                let x = match basis.has_time() {
+                    true => AnalysisValue::DateTime(start),
+                    false => AnalysisValue::Date(start.date()),
+                };
+
+                AnalysisResult::new(x, y)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::http::StatusCode;
+    use common::{data::row::ColumnValue, error::AppError, query::filter::FilterExpression};
+    use time::macros::{date, datetime};
+
+    use super::{ChronoAnalysisBasis, ChronoAnalysisOpts, ChronoAnalysisRange, RangeBound};
+    use crate::graphs::{aggregate::Aggregation, FromQueryParams};
+
+    #[test]
+    fn test_chrono_buckets() {
+        let at = datetime!(2024-05-16 10:47:30);
+        let start = |basis: &str| {
+            let basis = ChronoAnalysisBasis::try_from(basis.to_string()).unwrap();
+            basis.period_start(at)
+        };
+
+        assert_eq!(start("Hour"), datetime!(2024-05-16 10:00));
+        assert_eq!(start("Week"), datetime!(2024-05-13 0:00));
+        assert_eq!(start("Quarter"), datetime!(2024-04-01 0:00));
+        assert_eq!(start("15m"), datetime!(2024-05-16 10:45));
+        assert!(ChronoAnalysisBasis::try_from("0m".to_string()).is_err());
+
+        let opts = ChronoAnalysisOpts {
+            table: "orders".to_string(),
+            chrono_col: "created_at".to_string(),
+            basis: ChronoAnalysisBasis::Quarterly,
+            range: ChronoAnalysisRange(
+                RangeBound::Date(date!(2023-11-05)),
+                RangeBound::Date(date!(2024-06-30)),
+            ),
+            filters: vec![],
+            aggregation: Aggregation::default(),
+            series: None,
+        };
+
+        let points = vec![(ColumnValue::from("2024-01-01"), ColumnValue::Int(3))];
+        let results = opts.buckets().unwrap().results(points);
+        assert_eq!(
+            serde_json::to_string(&results).unwrap(),
+            r#"[[["2023-10-01"],[0]],[["2024-01-01"],[3]],[["2024-04-01"],[0]]]"#
+        );
+    }
+
+    #[test]
+    fn test_chrono_range() {
+        let range = |range: &str| ChronoAnalysisRange::try_from(range.to_string());
+
+        let loose = range("2024/1/5 range 2024-02-01T10:30:00Z").unwrap();
+        assert_eq!(loose.start(), RangeBound::Date(date!(2024-01-05)));
+        assert_eq!(loose.end(), RangeBound::DateTime(datetime!(2024-02-01 10:30)));
+
+        // Bounds are compared as `YYYY-MM-DD HH:MM:SS` in UTC, whatever they're written as.
+        let bounds = |range: &ChronoAnalysisRange| {
+            (range.start().to_string(), range.end().to_string())
+        };
+        assert_eq!(bounds(&loose), ("2024-01-05".into(), "2024-02-01 10:30:00".into()));
+
+        let offsets = range("2024-01-31T00:30:00.250+01:00 range 2024-02-01 10:30:00-0530");
+        let offsets = offsets.unwrap();
+        assert_eq!(
+            bounds(&offsets),
+            ("2024-01-30 23:30:00".into(), "2024-02-01 16:00:00".into())
+        );
+        assert!(range("2024-01-31T10:30:00 CET range 2024-02-01").is_err());
+
+        // An end date includes its whole day, so it's matched as anything before the next day.
+        let [start, end] = range("2024-01-01 range 2024-01-31").unwrap().filters("at");
+        assert!(matches!(start.expression, FilterExpression::Gte(v) if v == "2024-01-01"));
+        assert!(matches!(end.expression, FilterExpression::Lt(v) if v == "2024-02-01"));
+
+        assert!(range("2024-01-05").is_err());
+        assert!(range("05/01/2024 range 2024-02-01").is_err());
+
+        let params: HashMap<String, String> = [
+            ("table", "orders"),
+            ("column", "created_at"),
+            ("basis", "Month"),
+            ("range", "yesterday range today"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        match ChronoAnalysisOpts::from_query_params(params) {
+            Err(AppError::HttpError(status, msg)) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                assert!(msg.contains("'yesterday'"), "{msg}");
+            }
+            _ => panic!("the range should be rejected"),
+        }
+    }
+}
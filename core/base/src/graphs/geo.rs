@@ -1,11 +1,12 @@
 use std::{collections::HashMap, fmt::Display};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{BasableQuery, QueryCommand}};
+use common::{error::AppError, query::{filter::{FilterChain, FilterNode}, BasableQuery, QueryCommand, QueryExpr}};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use super::FromQueryParams;
+use super::{aggregate::Aggregation, filters_param, series::SeriesOpts, FromQueryParams};
+use crate::{dialect::Dialect, globals::BASABLE_GRAPH_YCOL, DbType};
 
 #[derive(EnumIter)]
 pub enum GeoGraphScope {
@@ -52,27 +53,65 @@ pub struct GeoGraphOpts {
     pub table: String,
     pub scope: GeoGraphScope,
     pub target_column: String,
+
+    /// Filters of the analyzed rows.
+    pub filters: Vec<FilterNode>,
+
+    /// The value of each location, the number of rows by default.
+    pub aggregation: Aggregation,
+
+    /// Splits the analysis into several series.
+    pub series: Option<SeriesOpts>,
 }
 
-impl From<GeoGraphOpts> for BasableQuery {
-    fn from(value: GeoGraphOpts) -> Self {
+impl GeoGraphOpts {
+    /// Build the query aggregating rows of each value of the target column, written for
+    /// `dialect`.
+    pub fn query(self, dialect: &dyn Dialect) -> BasableQuery {
         let GeoGraphOpts {
             table,
-            scope,
+            scope: _,
             target_column,
-        } = value;
-
-        let selections = vec!["COUNT(*) as COUNT".to_string(), target_column];
+            filters,
+            aggregation,
+            series: _,
+        } = self;
+
+        let selections = vec![
+            QueryExpr::Sql(format!(
+                "{} AS {}",
+                aggregation.expr(dialect),
+                dialect.quote_ident(BASABLE_GRAPH_YCOL)
+            )),
+            target_column.as_str().into(),
+        ];
         let operation = QueryCommand::SelectData(Some(selections));
 
+        let mut chain = FilterChain::new();
+        chain.add_group(filters);
+
         BasableQuery {
             table,
             command: operation,
+            filters: chain,
+            group_by: Some(vec![target_column.into()]),
             ..Default::default()
         }
     }
 }
 
+impl GeoGraphOpts {
+    /// Make sure the analyzed table and columns exist in `db`.
+    pub fn validate(&self, db: &DbType) -> Result<(), AppError> {
+        let mut columns = vec![self.target_column.as_str()];
+        columns.extend(self.aggregation.value_column.as_deref());
+        columns.extend(self.series.as_ref().map(|series| series.column.as_str()));
+        columns.extend(self.filters.iter().flat_map(FilterNode::columns));
+
+        db.validate_columns(&self.table, &columns)
+    }
+}
+
 impl FromQueryParams for GeoGraphOpts {
     fn from_query_params(params: HashMap<String, String>) -> Result<Self, AppError>
     where
@@ -92,6 +131,9 @@ impl FromQueryParams for GeoGraphOpts {
                     table,
                     scope,
                     target_column,
+                    filters: filters_param(&params)?,
+                    aggregation: Aggregation::from_params(&params)?,
+                    series: SeriesOpts::from_params(&params)?,
                 };
 
                 Ok(opts)
@@ -6,16 +6,23 @@ use std::{
 use axum::http::StatusCode;
 use category::CategoryGraphOpts;
 use chrono::ChronoAnalysisOpts;
-use common::error::AppError;
+use common::{data::row::ColumnValue, error::AppError, query::filter::FilterNode};
 use geo::GeoGraphOpts;
-use mysql::Value as MysqlValue;
-use serde::{ser::SerializeTuple, Serialize};
-use time::Date;
+use serde::{
+    ser::{Error, SerializeTuple},
+    Serialize,
+};
+use serde_json::value::RawValue;
+use series::GraphResults;
+use time::{Date, Month, PrimitiveDateTime};
 use trend::TrendGraphOpts;
 
+pub mod aggregate;
 pub mod category;
 pub mod chrono;
 pub mod geo;
+pub mod series;
+pub mod sql;
 pub mod trend;
 
 pub type AnalysisResults = Vec<AnalysisResult>;
@@ -28,8 +35,15 @@ pub enum AnalysisValue {
     Int(isize),
     Text(String),
     Date(Date),
+
+    /// Written as `2024-01-31T10:30:00`.
+    DateTime(PrimitiveDateTime),
+
     Float(f32),
     Double(f64),
+
+    /// An exact number, written as is. See [`ColumnValue::Decimal`].
+    Decimal(String),
 }
 
 impl Serialize for AnalysisValue {
@@ -45,8 +59,13 @@ impl Serialize for AnalysisValue {
             AnalysisValue::Int(int) => s.serialize_element(int)?,
             AnalysisValue::Text(text) => s.serialize_element(text)?,
             AnalysisValue::Date(date) => s.serialize_element(&date.to_string())?,
+            AnalysisValue::DateTime(_) => s.serialize_element(&self.to_string())?,
             AnalysisValue::Float(float) => s.serialize_element(float)?,
             AnalysisValue::Double(double) => s.serialize_element(double)?,
+            AnalysisValue::Decimal(decimal) => {
+                let number = RawValue::from_string(decimal.clone()).map_err(S::Error::custom)?;
+                s.serialize_element(&number)?
+            }
         }
 
         s.end()
@@ -61,44 +80,39 @@ impl Display for AnalysisValue {
             AnalysisValue::Int(value) => value.to_string(),
             AnalysisValue::Text(value) => value.to_string(),
             AnalysisValue::Date(value) => value.to_string(),
+            AnalysisValue::DateTime(value) => format!(
+                "{}T{:02}:{:02}:{:02}",
+                value.date(),
+                value.hour(),
+                value.minute(),
+                value.second()
+            ),
             AnalysisValue::Float(value) => value.to_string(),
             AnalysisValue::Double(value) => value.to_string(),
+            AnalysisValue::Decimal(value) => value.to_string(),
         };
 
         write!(f, "{}", value)
     }
 }
 
-impl TryFrom<MysqlValue> for AnalysisValue {
-    type Error = AppError;
-
-    fn try_from(value: MysqlValue) -> Result<Self, Self::Error> {
-        let s = match value {
-            MysqlValue::NULL => AnalysisValue::NULL,
-            MysqlValue::Bytes(v) => {
-                let v = String::from_utf8(v).map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })?;
-                AnalysisValue::Text(v)
-            }
-            MysqlValue::UInt(v) => {
-                let v = usize::try_from(v).map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })?;
-                AnalysisValue::UInt(v)
-            }
-            MysqlValue::Int(v) => {
-                let v = isize::try_from(v).map_err(|err| {
-                    AppError::HttpError(StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
-                })?;
-                AnalysisValue::Int(v)
-            }
-            MysqlValue::Float(v) => AnalysisValue::Float(v),
-            MysqlValue::Double(v) => AnalysisValue::Double(v),
-            _ => AnalysisValue::NULL,
-        };
-
-        Ok(s)
+impl From<ColumnValue> for AnalysisValue {
+    fn from(value: ColumnValue) -> Self {
+        match value {
+            ColumnValue::NULL => AnalysisValue::NULL,
+            ColumnValue::Text(v) => AnalysisValue::Text(v),
+            ColumnValue::Int(v) if v >= 0 => AnalysisValue::UInt(v as usize),
+            ColumnValue::Int(v) => AnalysisValue::Int(v as isize),
+            ColumnValue::UInt(v) => AnalysisValue::UInt(v as usize),
+            ColumnValue::Float(v) => AnalysisValue::Float(v),
+            ColumnValue::Double(v) => AnalysisValue::Double(v),
+            ColumnValue::Decimal(v) => AnalysisValue::Decimal(v),
+            ColumnValue::Date(y, m, d, ..) => Month::try_from(m)
+                .ok()
+                .and_then(|m| Date::from_calendar_date(y as i32, m, d).ok())
+                .map_or(AnalysisValue::NULL, AnalysisValue::Date),
+            time @ ColumnValue::Time(..) => AnalysisValue::Text(time.to_string()),
+        }
     }
 }
 
@@ -117,14 +131,27 @@ impl Debug for AnalysisResult {
 }
 
 pub trait VisualizeDB {
-    fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<AnalysisResults, AppError>;
+    fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<GraphResults, AppError>;
     fn trend_graph(&self, opts: TrendGraphOpts) -> Result<AnalysisResults, AppError>;
-    fn category_graph(&self, opts: CategoryGraphOpts) -> Result<AnalysisResults, AppError>;
-    fn geo_graph(&self, opts: GeoGraphOpts) -> Result<AnalysisResults, AppError>;
+    fn category_graph(&self, opts: CategoryGraphOpts) -> Result<GraphResults, AppError>;
+    fn geo_graph(&self, opts: GeoGraphOpts) -> Result<GraphResults, AppError>;
 }
 
 pub trait FromQueryParams {
     fn from_query_params(params: HashMap<String, String>) -> Result<Self, AppError>
     where
         Self: Sized;
-}
\ No newline at end of file
+}
+/// Filters of the rows a graph is drawn from, given in the `filters` query parameter as a JSON
+/// list of [`FilterNode`]s.
+fn filters_param(params: &HashMap<String, String>) -> Result<Vec<FilterNode>, AppError> {
+    match params.get("filters") {
+        Some(filters) => serde_json::from_str(filters).map_err(|err| {
+            AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                format!("Invalid 'filters' parameter: {err}"),
+            )
+        }),
+        None => Ok(Vec::new()),
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use common::{
+    data::row::{ColumnValue, Row},
+    error::AppError,
+    query::{
+        filter::{Filter, FilterCombinator, FilterExpression, FilterNode},
+        BasableQuery, QueryCommand, QueryExpr, QueryOrder,
+    },
+};
+use serde::Serialize;
+
+use super::{aggregate::Aggregation, AnalysisResults};
+use crate::{
+    dialect::Dialect,
+    globals::{BASABLE_GRAPH_SERIES, BASABLE_GRAPH_YCOL},
+};
+
+/// Most series a graph is split into when no limit is given.
+const DEFAULT_SERIES_LIMIT: usize = 10;
+
+/// Name of the series of values beyond the top ones.
+const OTHER_SERIES: &str = "other";
+
+/// Splits a graph into one series per value of [`SeriesOpts::column`].
+#[derive(Clone)]
+pub struct SeriesOpts {
+    pub column: String,
+
+    /// Most series returned, ranked by the graph's aggregate over all of their rows. Rows of
+    /// values beyond the top ones are put together in an "other" series.
+    pub limit: usize,
+}
+
+impl SeriesOpts {
+    /// Read the options from the `series_column` and `series_limit` query parameters. Graphs
+    /// aren't split without a series column.
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Option<Self>, AppError> {
+        let Some(column) = params.get("series_column") else {
+            return Ok(None);
+        };
+
+        let limit = params
+            .get("series_limit")
+            .map(|limit| limit.parse::<usize>())
+            .transpose()
+            .map_err(|err| AppError::HttpError(StatusCode::EXPECTATION_FAILED, err.to_string()))?
+            .unwrap_or(DEFAULT_SERIES_LIMIT);
+
+        if limit == 0 {
+            return Err(AppError::HttpError(
+                StatusCode::EXPECTATION_FAILED,
+                "'series_limit' must be at least 1".to_string(),
+            ));
+        }
+
+        Ok(Some(SeriesOpts {
+            column: column.clone(),
+            limit,
+        }))
+    }
+}
+
+/// A series of a graph split by [`SeriesOpts`].
+#[derive(Serialize)]
+pub struct AnalysisSeries {
+    /// The value of the series column, or `None` when it's null.
+    pub name: Option<String>,
+
+    /// Whether the series puts together the values beyond the top ones.
+    pub other: bool,
+
+    pub data: AnalysisResults,
+}
+
+/// Results of a graph: a single series, or several when split by [`SeriesOpts`].
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum GraphResults {
+    Single(AnalysisResults),
+    Series(Vec<AnalysisSeries>),
+}
+
+/// Run `query`, the query of a graph aggregated by `aggregation`, and read its points with
+/// `points`. With `series`, the graph is split into the series of the top values of its column,
+/// followed by the "other" series if there are more values.
+///
+/// `run` executes queries. Columns selected by `query` come first in the rows `points` reads,
+/// followed by the series value when split.
+pub(crate) fn graph_results(
+    query: BasableQuery,
+    aggregation: &Aggregation,
+    series: Option<&SeriesOpts>,
+    dialect: &dyn Dialect,
+    run: impl Fn(BasableQuery) -> Result<Vec<Row>, AppError>,
+    points: impl Fn(Vec<Row>) -> AnalysisResults,
+) -> Result<GraphResults, AppError> {
+    let Some(series) = series else {
+        return Ok(GraphResults::Single(points(run(query)?)));
+    };
+
+    let col = dialect.quote_ident(&series.column);
+    let series_alias = dialect.quote_ident(BASABLE_GRAPH_SERIES);
+
+    // Rank values by their aggregate, with one more than needed to know whether there are
+    // others. Values whose aggregate is null, such as the average of only nulls, rank last.
+    let ranking = BasableQuery {
+        table: query.table.clone(),
+        command: QueryCommand::SelectData(Some(vec![
+            QueryExpr::Sql(format!("{col} AS {series_alias}")),
+            QueryExpr::Sql(format!(
+                "{} AS {}",
+                aggregation.expr(dialect),
+                dialect.quote_ident(BASABLE_GRAPH_YCOL)
+            )),
+        ])),
+        filters: query.filters.clone(),
+        group_by: Some(vec![series.column.as_str().into()]),
+        order_by: Some(QueryOrder::DescNullsLast(BASABLE_GRAPH_YCOL.to_string())),
+        row_count: Some(series.limit + 1),
+        ..Default::default()
+    };
+
+    let mut top: Vec<ColumnValue> = run(ranking)?
+        .iter()
+        .map(|row| series_value(row.get_at(0)))
+        .collect();
+
+    let has_other = top.len() > series.limit;
+    top.truncate(series.limit);
+
+    let values: Vec<ColumnValue> = top.iter().filter(|v| !v.is_null()).cloned().collect();
+    let has_null = top.iter().any(ColumnValue::is_null);
+
+    // The graph's limit of points applies to each series.
+    let mut split = query.clone();
+    let row_count = split.row_count.take();
+    if let QueryCommand::SelectData(Some(selections)) = &mut split.command {
+        selections.push(QueryExpr::Sql(format!("{col} AS {series_alias}")));
+    }
+    split.group_by.get_or_insert_with(Vec::new).push(series.column.as_str().into());
+    split.filters.add_group(top_filters(&series.column, values.clone(), has_null, true));
+
+    // Rows are put in series by value rather than by text, so values written alike, such as the
+    // number 1 and the text "1", stay apart.
+    let mut rows: Vec<Vec<Row>> = vec![Vec::new(); top.len()];
+    for row in run(split)? {
+        let value = series_value(row.values().last());
+        let Some(idx) = top.iter().position(|v| *v == value) else {
+            continue;
+        };
+
+        if row_count.is_none_or(|count| rows[idx].len() < count) {
+            rows[idx].push(row);
+        }
+    }
+
+    let mut results: Vec<AnalysisSeries> = top
+        .into_iter()
+        .zip(rows)
+        .map(|(value, rows)| AnalysisSeries {
+            name: (!value.is_null()).then(|| value.to_string()),
+            other: false,
+            data: points(rows),
+        })
+        .collect();
+
+    if has_other {
+        let mut others = query;
+        others.filters.add_group(top_filters(&series.column, values, has_null, false));
+
+        results.push(AnalysisSeries {
+            name: Some(OTHER_SERIES.to_string()),
+            other: true,
+            data: points(run(others)?),
+        });
+    }
+
+    Ok(GraphResults::Series(results))
+}
+
+/// The value of the series column read from a row, null if missing.
+fn series_value(value: Option<&ColumnValue>) -> ColumnValue {
+    value.cloned().unwrap_or(ColumnValue::NULL)
+}
+
+/// Filters matching rows whose `column` is one of the top `values` (or null, if `has_null`), or
+/// with `is_top` false, rows whose column is none of them. Values are bound as they were read,
+/// so they're compared as the column's type.
+fn top_filters(
+    column: &str,
+    values: Vec<ColumnValue>,
+    has_null: bool,
+    is_top: bool,
+) -> Vec<FilterNode> {
+    let filter = |combinator, expression| {
+        FilterNode::from(Filter {
+            combinator,
+            column: column.to_string(),
+            expression,
+            table: None,
+        })
+    };
+
+    // `IN` and `NOT IN` never match nulls, so they're matched separately.
+    let mut filters = Vec::new();
+    let match_null = match (is_top, values.is_empty()) {
+        (true, true) => has_null,
+        (true, false) => {
+            filters.push(filter(
+                FilterCombinator::BASE,
+                FilterExpression::IncludesValues(values),
+            ));
+            has_null
+        }
+        (false, true) => {
+            filters.push(filter(FilterCombinator::BASE, FilterExpression::NotNull));
+            false
+        }
+        (false, false) => {
+            filters.push(filter(
+                FilterCombinator::BASE,
+                FilterExpression::NotIncludeValues(values),
+            ));
+            !has_null
+        }
+    };
+
+    if match_null {
+        filters.push(filter(FilterCombinator::OR, FilterExpression::Null));
+    }
+
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, sync::Arc};
+
+    use common::{
+        data::row::{ColumnValue, Row},
+        query::{BasableQuery, QueryCommand, QueryExpr},
+    };
+
+    use super::{graph_results, GraphResults, SeriesOpts};
+    use crate::{
+        db::QuerySqlParser,
+        dialect::{Dialect, SqliteDialect},
+        graphs::{aggregate::Aggregation, AnalysisResult, AnalysisValue},
+    };
+
+    struct Parser;
+
+    impl QuerySqlParser for Parser {
+        fn dialect(&self) -> &dyn Dialect {
+            &SqliteDialect
+        }
+    }
+
+    #[test]
+    fn test_graph_series() {
+        let row = |values: Vec<ColumnValue>| Row::new(Arc::new(vec![]), values);
+        let text = |v: &str| ColumnValue::Text(v.to_string());
+
+        let query = BasableQuery {
+            table: "orders".to_string(),
+            command: QueryCommand::SelectData(Some(vec![QueryExpr::Sql("COUNT(*)".to_string())])),
+            ..Default::default()
+        };
+        let series = SeriesOpts {
+            column: "country".to_string(),
+            limit: 3,
+        };
+
+        // Queries are ranking, top series and others, in that order. The number 1 and the text
+        // "1" are different series.
+        let queries = RefCell::new(Vec::new());
+        let run = |query: BasableQuery| {
+            let sql = Parser.generate_sql(query).unwrap();
+            queries.borrow_mut().push((sql.sql, sql.params));
+            let rows = match queries.borrow().len() {
+                1 => vec![
+                    row(vec![ColumnValue::Int(1), ColumnValue::Int(5)]),
+                    row(vec![ColumnValue::NULL, ColumnValue::Int(3)]),
+                    row(vec![text("1"), ColumnValue::Int(2)]),
+                    row(vec![text("FR"), ColumnValue::Int(1)]),
+                ],
+                2 => vec![
+                    row(vec![ColumnValue::Int(2), text("1")]),
+                    row(vec![ColumnValue::Int(3), ColumnValue::NULL]),
+                    row(vec![ColumnValue::Int(5), ColumnValue::Int(1)]),
+                ],
+                _ => vec![row(vec![ColumnValue::Int(1)])],
+            };
+            Ok(rows)
+        };
+        let points = |rows: Vec<Row>| {
+            rows.iter()
+                .map(|r| {
+                    let y = r.get_at(0).cloned().map_or(AnalysisValue::NULL, AnalysisValue::from);
+                    AnalysisResult::new(AnalysisValue::NULL, y)
+                })
+                .collect()
+        };
+
+        let aggregation = Aggregation::default();
+        let results =
+            graph_results(query, &aggregation, Some(&series), &SqliteDialect, run, points)
+                .unwrap();
+
+        let GraphResults::Series(results) = results else {
+            panic!("graph isn't split");
+        };
+        let names: Vec<_> = results.iter().map(|s| (s.name.as_deref(), s.other)).collect();
+        assert_eq!(
+            names,
+            [(Some("1"), false), (None, false), (Some("1"), false), (Some("other"), true)]
+        );
+        let data: Vec<_> = results.iter().map(|s| format!("{:?}", s.data)).collect();
+        assert_eq!(
+            data[..3],
+            ["[{x: null, y: 5}]", "[{x: null, y: 3}]", "[{x: null, y: 2}]"]
+        );
+
+        // Values are ranked with nulls last. Top values are bound with the type they were read
+        // with, and the null one is matched apart, since `IN` never matches nulls.
+        let queries = queries.into_inner();
+        assert_eq!(
+            queries[0],
+            (
+                r#"SELECT "country" AS "BASABLE_GRAPH_SERIES", COUNT(*) AS "BASABLE_GRAPH_RESULT" FROM "orders" GROUP BY "country" ORDER BY "BASABLE_GRAPH_RESULT" IS NULL, "BASABLE_GRAPH_RESULT" DESC LIMIT 4 OFFSET 0"#.to_string(),
+                vec![]
+            )
+        );
+
+        let top = vec![ColumnValue::Int(1), text("1")];
+        assert_eq!(
+            queries[1],
+            (
+                r#"SELECT COUNT(*), "country" AS "BASABLE_GRAPH_SERIES" FROM "orders" WHERE ("country" IN (?, ?) OR "country" IS NULL) GROUP BY "country""#.to_string(),
+                top.clone()
+            )
+        );
+        assert_eq!(
+            queries[2],
+            (
+                r#"SELECT COUNT(*) FROM "orders" WHERE ("country" NOT IN (?, ?))"#.to_string(),
+                top
+            )
+        );
+    }
+}
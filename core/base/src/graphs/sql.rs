@@ -0,0 +1,216 @@
+use common::{
+    data::row::{ColumnValue, Row},
+    error::AppError,
+    query::BasableQuery,
+};
+
+use super::{
+    aggregate::Aggregation,
+    category::CategoryGraphOpts,
+    chrono::ChronoAnalysisOpts,
+    geo::GeoGraphOpts,
+    series::{graph_results, GraphResults},
+    trend::TrendGraphOpts,
+    AnalysisResult, AnalysisResults, AnalysisValue, VisualizeDB,
+};
+use crate::{connector::Connector, db::QuerySqlParser};
+
+/// A backend whose graphs are drawn by running the SQL it generates on its [`Connector`].
+/// [`VisualizeDB`] is implemented for every such backend.
+pub trait SqlGraphs: QuerySqlParser {
+    /// The connector graph queries are run on.
+    fn sql_connector(&self) -> &dyn Connector<Row = Row>;
+}
+
+/// Points are read from the columns selected by the graph queries, in order, so they don't
+/// depend on how backends name the columns of results.
+impl<D: SqlGraphs> VisualizeDB for D {
+    fn chrono_graph(&self, opts: ChronoAnalysisOpts) -> Result<GraphResults, AppError> {
+        let buckets = opts.buckets()?;
+        let aggregation = opts.aggregation.clone();
+        let series = opts.series.clone();
+        let query = opts.query(self.dialect());
+
+        let points = |rows: Vec<Row>| {
+            buckets.results(rows.iter().map(|r| (column_value(r, 0), column_value(r, 1))))
+        };
+
+        let run = |query| run(self, query);
+        graph_results(query, &aggregation, series.as_ref(), self.dialect(), run, points)
+    }
+
+    fn trend_graph(&self, opts: TrendGraphOpts) -> Result<AnalysisResults, AppError> {
+        let query = opts.query(self.dialect())?;
+        let rows = run(self, query)?;
+
+        // Values of the x column are labels, so they're written as text. Nulls stay null, as in
+        // other graphs.
+        let results = rows
+            .iter()
+            .map(|r| {
+                let x = match analysis_value(r, 0) {
+                    x @ (AnalysisValue::NULL | AnalysisValue::Text(_)) => x,
+                    x => AnalysisValue::Text(x.to_string()),
+                };
+
+                AnalysisResult::new(x, analysis_value(r, 1))
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    fn category_graph(&self, opts: CategoryGraphOpts) -> Result<GraphResults, AppError> {
+        let aggregation = opts.aggregation.clone();
+        let series = opts.series.clone();
+        let query = opts.query(self.dialect());
+
+        let run = |query| run(self, query);
+        let points = |rows: Vec<Row>| aggregated_points(&rows, &aggregation);
+        graph_results(query, &aggregation, series.as_ref(), self.dialect(), run, points)
+    }
+
+    fn geo_graph(&self, opts: GeoGraphOpts) -> Result<GraphResults, AppError> {
+        let aggregation = opts.aggregation.clone();
+        let series = opts.series.clone();
+        let query = opts.query(self.dialect());
+
+        let run = |query| run(self, query);
+        let points = |rows: Vec<Row>| aggregated_points(&rows, &aggregation);
+        graph_results(query, &aggregation, series.as_ref(), self.dialect(), run, points)
+    }
+}
+
+fn run(db: &impl SqlGraphs, query: BasableQuery) -> Result<Vec<Row>, AppError> {
+    db.sql_connector().exec_sql(&db.generate_sql(query)?)
+}
+
+/// Points of category and geo graphs, whose selections are the aggregate followed by the
+/// target column.
+fn aggregated_points(rows: &[Row], aggregation: &Aggregation) -> AnalysisResults {
+    rows.iter()
+        .map(|r| AnalysisResult::new(analysis_value(r, 1), aggregation.result(column_value(r, 0))))
+        .collect()
+}
+
+fn analysis_value(row: &Row, idx: usize) -> AnalysisValue {
+    column_value(row, idx).into()
+}
+
+fn column_value(row: &Row, idx: usize) -> ColumnValue {
+    row.get_at(idx).cloned().unwrap_or(ColumnValue::NULL)
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use std::{collections::HashMap, fs};
+
+    use rusqlite::Connection;
+    use uuid::Uuid;
+
+    use crate::{
+        config::{ConfigRaw, DataDir},
+        graphs::{chrono::ChronoAnalysisOpts, trend::TrendGraphOpts, FromQueryParams},
+        registry::BackendRegistry,
+    };
+
+    #[test]
+    fn test_trend_labels() {
+        let root = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        Connection::open(root.join("shop.db"))
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, code INTEGER, total REAL);
+                INSERT INTO orders (code, total) VALUES (7, 20.5), (NULL, 10);",
+            )
+            .unwrap();
+
+        let mut registry = BackendRegistry::default();
+        registry.set_data_dir(DataDir::new(&root, &[]).unwrap());
+        let config = ConfigRaw {
+            source: "sqlite".to_string(),
+            path: Some("shop.db".to_string()),
+            ..Default::default()
+        };
+        let db = registry.create(&config, Uuid::new_v4(), "user".to_string()).unwrap();
+
+        let params: HashMap<String, String> = [
+            ("table", "orders"),
+            ("graph_type", "intra"),
+            ("xcol", "code"),
+            ("ycol", "total"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let opts = TrendGraphOpts::from_query_params(params).unwrap();
+
+        // Labels are text, and null labels stay null.
+        let results = db.trend_graph(opts).unwrap();
+        assert_eq!(
+            serde_json::to_string(&results).unwrap(),
+            r#"[[["7"],[20.5]],[[null],[10.0]]]"#
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn test_chrono_range_bounds() {
+        let root = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        Connection::open(root.join("shop.db"))
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, day DATE, at DATETIME);
+                INSERT INTO orders (day, at) VALUES
+                    ('2024-01-30', '2024-01-30 23:30:00'),
+                    ('2024-01-31', '2024-01-31 00:00:00'),
+                    ('2024-01-31', '2024-01-31 18:00:00'),
+                    ('2024-02-01', '2024-02-01 00:00:00');",
+            )
+            .unwrap();
+
+        let mut registry = BackendRegistry::default();
+        registry.set_data_dir(DataDir::new(&root, &[]).unwrap());
+        let config = ConfigRaw {
+            source: "sqlite".to_string(),
+            path: Some("shop.db".to_string()),
+            ..Default::default()
+        };
+        let db = registry.create(&config, Uuid::new_v4(), "user".to_string()).unwrap();
+
+        let chrono = |column: &str, range: &str| {
+            let params: HashMap<String, String> = [
+                ("table", "orders"),
+                ("column", column),
+                ("basis", "Date"),
+                ("range", range),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+            let opts = ChronoAnalysisOpts::from_query_params(params).unwrap();
+            serde_json::to_string(&db.chrono_graph(opts).unwrap()).unwrap()
+        };
+
+        // An end date includes its whole day, on date columns as on datetime ones.
+        let last_day = r#"[[["2024-01-31"],[2]]]"#;
+        assert_eq!(chrono("day", "2024-01-31 range 2024-01-31"), last_day);
+        assert_eq!(chrono("at", "2024-01-31 range 2024-01-31"), last_day);
+
+        // Times are compared in UTC, whether written with a `T`, a `Z` or an offset.
+        assert_eq!(
+            chrono("at", "2024-01-31T00:00:00Z range 2024-01-31T19:00:00+01:00"),
+            last_day
+        );
+        assert_eq!(
+            chrono("at", "2024-01-31T00:30:00+01:00 range 2024-01-31T00:00:00"),
+            r#"[[["2024-01-30"],[1]],[["2024-01-31"],[1]]]"#
+        );
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -1,11 +1,12 @@
 use std::{collections::HashMap, fmt::Display};
 
 use axum::http::StatusCode;
-use common::{error::AppError, query::{filter::{Filter, FilterChain, FilterCombinator, FilterExpression}, BasableQuery, QueryCommand, QueryOrder}};
+use common::{error::AppError, query::{filter::{Filter, FilterChain, FilterCombinator, FilterExpression, FilterNode}, BasableQuery, QueryCommand, QueryExpr, QueryOrder}};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use super::FromQueryParams;
+use super::{filters_param, FromQueryParams};
+use crate::{dialect::Dialect, DbType};
 
 #[derive(Clone)]
 pub enum TrendGraphType {
@@ -94,6 +95,32 @@ pub struct TrendGraphOpts {
 
     /// Configure this option if you're using [`TrendAnalysisType::CrossModel`].
     pub cross: Option<CrossOptions>,
+
+    /// Filters of the analyzed rows of [`TrendGraphOpts::table`].
+    pub filters: Vec<FilterNode>,
+}
+
+impl TrendGraphOpts {
+    /// Make sure the analyzed tables and columns exist in `db`.
+    pub fn validate(&self, db: &DbType) -> Result<(), AppError> {
+        let filtered = self.filters.iter().flat_map(FilterNode::columns);
+
+        match &self.cross {
+            Some(cross) => {
+                let mut columns = vec![self.xcol.as_str(), cross.target_col.as_str()];
+                columns.extend(filtered);
+
+                db.validate_columns(&self.table, &columns)?;
+                db.validate_columns(&cross.foreign_table, &[&self.ycol])
+            }
+            None => {
+                let mut columns = vec![self.xcol.as_str(), self.ycol.as_str()];
+                columns.extend(filtered);
+
+                db.validate_columns(&self.table, &columns)
+            }
+        }
+    }
 }
 
 impl FromQueryParams for TrendGraphOpts {
@@ -118,11 +145,12 @@ impl FromQueryParams for TrendGraphOpts {
                 }
 
                 // parse query limit
-                let limit = trend_limit.map_or(Ok(0), |lmt| {
-                    lmt.parse::<usize>().map_err(|err| {
+                let limit = trend_limit
+                    .map(|lmt| lmt.parse::<usize>())
+                    .transpose()
+                    .map_err(|err| {
                         AppError::HttpError(StatusCode::EXPECTATION_FAILED, err.to_string())
-                    })
-                }).ok();
+                    })?;
                 
                 // parse cross analysis options
                 let mut cross_err = Ok(());
@@ -152,9 +180,7 @@ impl FromQueryParams for TrendGraphOpts {
                 }
 
                 // if insufficient parameters are supplied for cross analysis, return error
-                if let Err(err) = cross_err {
-                    return Err(err);
-                }
+                cross_err?;
 
                 let opts = TrendGraphOpts {
                     table: String::from(table),
@@ -164,6 +190,7 @@ impl FromQueryParams for TrendGraphOpts {
                     order,
                     limit,
                     cross,
+                    filters: filters_param(&params)?,
                 };
 
                 Ok(opts)
@@ -179,10 +206,9 @@ impl FromQueryParams for TrendGraphOpts {
     }
 }
 
-impl TryFrom<TrendGraphOpts> for BasableQuery {
-    type Error = AppError;
-
-    fn try_from(value: TrendGraphOpts) -> Result<Self, Self::Error> {
+impl TrendGraphOpts {
+    /// Build the query for the analysis, written for `dialect`.
+    pub fn query(self, dialect: &dyn Dialect) -> Result<BasableQuery, AppError> {
         let TrendGraphOpts {
             table,
             graph_type: analysis_type,
@@ -191,11 +217,13 @@ impl TryFrom<TrendGraphOpts> for BasableQuery {
             order,
             limit,
             cross,
-        } = value;
+            filters: extra_filters,
+        } = self;
 
         match analysis_type {
             TrendGraphType::IntraModel => {
-                let operation = QueryCommand::SelectData(Some(vec![xcol, ycol.clone()]));
+                let operation =
+                    QueryCommand::SelectData(Some(vec![xcol.into(), ycol.as_str().into()]));
 
                 let order = match order {
                     Some(order) => match order {
@@ -207,9 +235,13 @@ impl TryFrom<TrendGraphOpts> for BasableQuery {
 
                 let order_by = Some(order);
 
+                let mut filters = FilterChain::new();
+                filters.add_group(extra_filters);
+
                 let q = BasableQuery {
                     table,
                     command: operation,
+                    filters,
                     order_by,
                     row_count: limit,
                     ..Default::default()
@@ -225,21 +257,40 @@ impl TryFrom<TrendGraphOpts> for BasableQuery {
                         target_col,
                     } = cross;
 
+                    let (x, y) = (
+                        dialect.column_ref(Some("x"), &xcol),
+                        dialect.column_ref(Some("y"), &ycol),
+                    );
+                    let (x_alias, y_alias) = (dialect.quote_ident(&xcol), dialect.quote_ident(&ycol));
+
                     let select_columns = vec![
-                        format!("x.{xcol} AS {xcol}"),
-                        format!("COUNT(y.{ycol}) AS {ycol}"),
+                        QueryExpr::Sql(format!("{x} AS {x_alias}")),
+                        QueryExpr::Sql(format!("COUNT({y}) AS {y_alias}")),
                     ];
 
                     let operation = QueryCommand::SelectData(Some(select_columns));
-                    let left_join = format!("{foreign_table} y ON x.{target_col} = y.{ycol}");
+                    let left_join = format!(
+                        "{} ON {} = {y}",
+                        dialect.table_ref(&foreign_table, Some("y")),
+                        dialect.column_ref(Some("x"), &target_col)
+                    );
 
-                    let mut having = FilterChain::new();
-                    having.add_one(Filter{
+                    // Rows of `table` without a match in `foreign_table` are left out. Not all
+                    // backends allow output aliases in `HAVING`, so they're filtered before grouping.
+                    let mut filters = FilterChain::new();
+                    filters.add_one(Filter {
                         combinator: FilterCombinator::BASE,
                         column: ycol.clone(),
-                        expression: FilterExpression::Gt("0".to_string())
+                        expression: FilterExpression::NotNull,
+                        table: Some("y".to_string()),
                     });
 
+                    // Both tables are joined, so filtered columns are qualified with the alias
+                    // of `table`.
+                    let mut extra_filters = extra_filters;
+                    qualify_columns(&mut extra_filters, "x");
+                    filters.add_group(extra_filters);
+
                     let order = match order {
                         Some(order) => match order {
                             TrendGraphOrder::DESC => QueryOrder::DESC(ycol),
@@ -252,10 +303,11 @@ impl TryFrom<TrendGraphOpts> for BasableQuery {
 
                     let q = BasableQuery {
                         command: operation,
-                        having,
-                        table: format!("{table} x"),
+                        filters,
+                        table,
+                        alias: Some("x".to_string()),
                         left_join: Some(left_join),
-                        group_by: Some(vec![xcol]),
+                        group_by: Some(vec![QueryExpr::Sql(x)]),
                         order_by,
                         row_count: limit,
                         ..Default::default()
@@ -275,3 +327,13 @@ impl TryFrom<TrendGraphOpts> for BasableQuery {
         }
     }
 }
+
+/// Qualify the columns of `nodes` with the table `alias`.
+fn qualify_columns(nodes: &mut [FilterNode], alias: &str) {
+    for node in nodes {
+        match node {
+            FilterNode::Filter(filter) => filter.table = Some(alias.to_string()),
+            FilterNode::Group(group) => qualify_columns(&mut group.filters, alias),
+        }
+    }
+}
@@ -1,39 +1,41 @@
 use std::sync::Arc;
 
+use common::data::row::Row;
 use connector::Connector;
 use db::DB;
 use table::Table;
 
-use mysql_plugin::{connector::MysqlConnector, db::MySqlDB, table::MySqlTable};
-
 
 pub mod db;
+pub mod dialect;
 pub mod graphs;
 pub mod connector;
 pub mod table;
 pub mod config;
+pub mod registry;
 mod globals;
 
-// we need to find a way to seperate mysql as an independent plugin
+#[cfg(feature = "mysql")]
 pub mod mysql_plugin;
+#[cfg(feature = "postgres")]
+pub mod postgres_plugin;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_plugin;
+#[cfg(feature = "files")]
+pub mod file_plugin;
 
 
 /// Dynamic [`DB`] type to be implemented across the app.
-pub type DbType = dyn DB<
-    Row = <MySqlDB as DB>::Row,
->;
+pub type DbType = dyn DB;
 
-/// Dynamic [`Connector`] type implemented across the app.
-pub type ConnectorType = Arc<dyn Connector<Row = <MysqlConnector as Connector>::Row>>;
+/// Dynamic [`Connector`] type. Connectors convert their results into backend-agnostic [`Row`]s.
+pub type ConnectorType = Arc<dyn Connector<Row = Row>>;
 
 /// Dynamic [`Table`] type implemented across the app.
-pub type TableType = dyn Table<
-    Row = <MySqlTable as Table>::Row,
->;
+pub type TableType = dyn Table;
 
 /// A thread-safe sharable DB instance
 pub type SharedDB = Arc<DbType>;
 
 /// A thread-safe sharable Database Table
 pub type SharedTable = Arc<TableType>;
-
@@ -1,7 +1,17 @@
-use common::error::AppError;
-use mysql::{prelude::Queryable, Opts, Params, Pool, Row};
+use std::sync::Arc;
 
-use crate::{config::ConfigRaw, connector::Connector};
+use common::{
+    data::row::{ColumnValue, Row},
+    error::AppError,
+};
+use mysql::{prelude::Queryable, Opts, Params, Pool};
+
+use crate::{
+    config::ConfigRaw,
+    connector::{Connector, RowSink},
+};
+
+use super::{column_value, sql_value};
 
 /// MySQL implementation of `BasableConnection`
 #[derive(Clone, Default)]
@@ -35,13 +45,34 @@ impl Connector for MysqlConnector {
             .map_err(|err| AppError::ServerError(err.to_string()))
     }
 
-    fn exec_query(&self, query: &str) -> Result<Vec<Self::Row>, AppError> {
+    fn stream_params(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        sink: &mut RowSink<'_, Self::Row>,
+    ) -> Result<(), AppError> {
         let conn = &mut self.pool().get_conn()?;
 
         let stmt = conn.prep(query)?;
-        let rows = conn.exec(stmt, Params::Empty)?;
+        let params = match params {
+            [] => Params::Empty,
+            params => Params::Positional(params.iter().map(sql_value).collect()),
+        };
+
+        let columns: Arc<Vec<String>> = Arc::new(
+            stmt.columns()
+                .iter()
+                .map(|col| col.name_str().to_string())
+                .collect(),
+        );
+
+        // Rows are read from the connection as they're iterated, rather than buffered.
+        for row in conn.exec_iter(&stmt, params)? {
+            let values = row?.unwrap().into_iter().map(column_value).collect();
+            sink(Row::new(columns.clone(), values))?;
+        }
 
-        Ok(rows)
+        Ok(())
     }
 
     fn config(&self) -> &ConfigRaw {
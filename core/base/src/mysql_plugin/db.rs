@@ -1,11 +1,23 @@
 use std::{collections::HashMap, sync::Arc};
 
-use common::{data::table::{TableSummaries, TableSummary}, error::AppError, DBVersion, DbServerDetails};
-use mysql::Row;
-use time::Date;
+use common::{
+    data::{
+        row::{ColumnValue, Row},
+        table::{TableSummaries, TableSummary},
+    },
+    error::AppError,
+    DBVersion, DbServerDetails,
+};
 use uuid::Uuid;
 
-use crate::{config::ConfigRaw, db::{QuerySqlParser, DB}, table::Table, ConnectorType, SharedTable};
+use crate::{
+    config::ConfigRaw,
+    connector::Connector,
+    db::{DBQueryResult, QuerySqlParser, DB},
+    dialect::{Dialect, MySqlDialect},
+    graphs::sql::SqlGraphs,
+    ConnectorType, SharedTable,
+};
 
 use super::table::MySqlTable;
 
@@ -17,12 +29,12 @@ pub struct MySqlDB {
 }
 
 impl MySqlDB {
-    pub fn new(connector: ConnectorType, user_id: String) -> Self {
+    pub fn new(connector: ConnectorType, id: Uuid, user_id: String) -> Self {
         MySqlDB {
             connector,
             tables: Vec::new(),
             user_id,
-            id: Uuid::new_v4(),
+            id,
         }
     }
 
@@ -44,8 +56,8 @@ impl MySqlDB {
         let mut data = HashMap::new();
 
         for v in vars {
-            let name: String = v.get("Variable_name").unwrap();
-            let value: String = v.get("Value").unwrap();
+            let name = v.get_string("Variable_name").unwrap_or_default();
+            let value = v.get_string("Value").unwrap_or_default();
             data.insert(name, value);
         }
 
@@ -55,30 +67,52 @@ impl MySqlDB {
     fn size(&self) -> Result<f64, AppError> {
         let db = self.config().db_name.as_ref().unwrap();
 
-        let query = format!(
+        let qr = self.connector.exec_params(
             "
-            SELECT table_schema '{db}', 
+            SELECT table_schema, 
             ROUND(SUM(data_length + index_length) / 1024 / 1024, 1) 'size' 
             FROM information_schema.tables 
-            WHERE table_schema = '{db}'
+            WHERE table_schema = ?
             GROUP BY table_schema
-        "
-        );
-
-        let qr = self.exec_query(&query)?;
+        ",
+            &[db.as_str().into()],
+        )?;
 
         // db size is returned in MB, we may want to write a function
         // to convert for GB, TB...etc
-        let size: f64 = qr.first().map_or(0.0, |r| {
-            let s: String = r.get("size").unwrap();
-            s.parse().unwrap()
-        });
+        let size = qr
+            .first()
+            .and_then(|r| r.get("size"))
+            .and_then(ColumnValue::as_f64)
+            .unwrap_or_default();
 
         Ok(size)
     }
 
+    /// Get the [`ConnectorType`] instance for [`MySqlDB`].
+    pub fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    /// Query server for information about available tables. It only queries the database server and
+    /// return results as [`Row`]s. It is different from [`DB::load_tables`] which actually loads the [`Table`]
+    /// abstraction into memory.
+    fn query_tables(&self) -> DBQueryResult<Row, AppError> {
+        let db = self.config().db_name.clone().unwrap();
+
+        self.connector.exec_params(
+            "
+                SELECT table_name, table_rows, create_time, update_time
+                FROM information_schema.tables
+                WHERE table_schema = ?
+                ORDER BY table_name;
+            ",
+            &[db.into()],
+        )
+    }
+
     fn config(&self) -> &ConfigRaw {
-        &self.connector.config()
+        self.connector.config()
     }
 
     fn exec_query(&self, query: &str) -> Result<Vec<Row>, AppError> {
@@ -87,8 +121,6 @@ impl MySqlDB {
 }
 
 impl DB for MySqlDB {
-    type Row = mysql::Row;
-
     fn id(&self) -> &Uuid {
         &self.id
     }
@@ -97,17 +129,13 @@ impl DB for MySqlDB {
         &self.user_id
     }
 
-    fn connector(&self) -> &ConnectorType {
-        &self.connector
-    }
-
-    fn load_tables(&mut self, connector: ConnectorType) -> Result<(), AppError> {
+    fn load_tables(&mut self) -> Result<(), AppError> {
         let tables = self.query_tables()?;
 
         if !tables.is_empty() {
             tables.iter().for_each(|t| {
-                let connector = connector.clone();
-                let name: String = t.get("TABLE_NAME").unwrap();
+                let connector = self.connector.clone();
+                let name = t.get_string("TABLE_NAME").unwrap_or_default();
 
                 let table = MySqlTable::new(name, connector);
                 self.tables.push(Arc::new(table));
@@ -121,37 +149,22 @@ impl DB for MySqlDB {
         &self.tables
     }
 
-    fn query_tables(&self) -> Result<Vec<Row>, AppError> {
-        let query = format!(
-            "
-                SELECT table_name, table_rows, create_time, update_time
-                FROM information_schema.tables
-                WHERE table_schema = '{}'
-                ORDER BY table_name;
-            ",
-            self.config().db_name.clone().unwrap()
-        );
-
-        self.connector.exec_query(&query)
-    }
-
     fn build_table_list(&self) -> Result<TableSummaries, AppError> {
         let results = self.query_tables()?;
         let tables: Vec<TableSummary> = results
             .iter()
             .map(|res| {
-                let created = res.get("CREATE_TIME") as Option<Date>;
-                let updated = res.get("CREATE_TIME") as Option<Date>;
-                let name: String = res.get("TABLE_NAME").unwrap();
+                let name = res.get_string("TABLE_NAME").unwrap_or_default();
+                let row_count = res.get("TABLE_ROWS").and_then(ColumnValue::as_i64);
 
-                let col_count = self.query_column_count(&name).unwrap();
+                let col_count = self.query_column_count(&name).unwrap_or_default();
 
                 TableSummary {
                     name,
                     col_count,
-                    row_count: res.get("TABLE_ROWS").unwrap(),
-                    created: created.map_or(None, |d| Some(d.to_string())),
-                    updated: updated.map_or(None, |d| Some(d.to_string())),
+                    row_count: row_count.unwrap_or_default() as u32,
+                    created: res.get_string("CREATE_TIME"),
+                    updated: res.get_string("UPDATE_TIME"),
                 }
             })
             .collect();
@@ -160,21 +173,24 @@ impl DB for MySqlDB {
     }
 
     fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
-        let query = format!(
+        let db = self.config().db_name.clone().unwrap();
+
+        let qr = self.connector.exec_params(
             "
                 SELECT count(*) 
                 FROM information_schema.columns 
-                WHERE table_schema = '{}' and table_name = '{}'
+                WHERE table_schema = ? and table_name = ?
                 ORDER BY table_name;
             ",
-            self.config().db_name.clone().unwrap(),
-            tb_name
-        );
-
-        let qr = self.exec_query(&query)?;
-        let c: u32 = qr.first().map_or(0, |r| r.get("count(*)").unwrap());
+            &[db.into(), tb_name.into()],
+        )?;
+        let c = qr
+            .first()
+            .and_then(|r| r.get("count(*)"))
+            .and_then(ColumnValue::as_i64)
+            .unwrap_or_default();
 
-        Ok(c)
+        Ok(c as u32)
     }
 
     fn get_table(&self, name: &str) -> Option<&SharedTable> {
@@ -198,4 +214,14 @@ impl DB for MySqlDB {
     }
 }
 
-impl QuerySqlParser for MySqlDB {}
+impl QuerySqlParser for MySqlDB {
+    fn dialect(&self) -> &dyn Dialect {
+        &MySqlDialect
+    }
+}
+
+impl SqlGraphs for MySqlDB {
+    fn sql_connector(&self) -> &dyn Connector<Row = Row> {
+        self.connector.as_ref()
+    }
+}
@@ -1,42 +1,48 @@
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use common::data::row::ColumnValue;
 use mysql::Value;
 
+use crate::{connector::Connector, registry::BackendRegistry};
+
+use self::{connector::MysqlConnector, db::MySqlDB};
+
 pub mod db;
 pub mod connector;
 pub mod table;
-pub mod graphs;
 
-/// Client side representation of a value of MySql column.
-///
-/// The `Value` is also used as a parameter to a prepared statement.
-#[derive(Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
-pub enum ColumnValue {
-    NULL,
-    Text(String),
-    Int(i64),
-    UInt(u64),
-    Float(f32),
-    Double(f64),
-    /// year, month, day, hour, minutes, seconds, micro seconds
-    Date(u16, u8, u8, u8, u8, u8, u32),
-    /// is negative, days, hours, minutes, seconds, micro seconds
-    Time(bool, u32, u8, u8, u8, u32),
+/// Register the MySQL backend for `database` sources named `mysql`.
+pub fn register(registry: &mut BackendRegistry) {
+    registry.register("database", "mysql", |config, id, user_id| {
+        let conn = MysqlConnector::new(config.clone())?;
+        Ok(Box::new(MySqlDB::new(Arc::new(conn), id, user_id)))
+    });
 }
 
-impl From<Value> for ColumnValue {
-    fn from(value: Value) -> Self {
-        match value {
-            Value::NULL => ColumnValue::NULL,
-            Value::Bytes(buf) => {
-                let s = String::from_utf8(buf).unwrap();
-                ColumnValue::Text(s)
-            },
-            Value::Int(v) => ColumnValue::Int(v),
-            Value::UInt(v) => ColumnValue::UInt(v),
-            Value::Float(v) => ColumnValue::Float(v),
-            Value::Double(v) => ColumnValue::Double(v),
-            Value::Date(y, m, d, h, min, sec, ms) => ColumnValue::Date(y, m, d, h, min, sec, ms),
-            Value::Time(neg, d, h, min, sec, ms) => ColumnValue::Time(neg, d, h, min, sec, ms),
-        }
+/// Convert a MySQL [`Value`] into a [`ColumnValue`].
+pub(crate) fn column_value(value: Value) -> ColumnValue {
+    match value {
+        Value::NULL => ColumnValue::NULL,
+        Value::Bytes(buf) => ColumnValue::Text(String::from_utf8_lossy(&buf).into_owned()),
+        Value::Int(v) => ColumnValue::Int(v),
+        Value::UInt(v) => ColumnValue::UInt(v),
+        Value::Float(v) => ColumnValue::Float(v),
+        Value::Double(v) => ColumnValue::Double(v),
+        Value::Date(y, m, d, h, min, sec, ms) => ColumnValue::Date(y, m, d, h, min, sec, ms),
+        Value::Time(neg, d, h, min, sec, ms) => ColumnValue::Time(neg, d, h, min, sec, ms),
     }
-}
\ No newline at end of file
+}
+
+/// Convert a [`ColumnValue`] into a MySQL [`Value`], to be bound to a query.
+pub(crate) fn sql_value(value: &ColumnValue) -> Value {
+    match value.clone() {
+        ColumnValue::NULL => Value::NULL,
+        ColumnValue::Text(v) | ColumnValue::Decimal(v) => Value::Bytes(v.into_bytes()),
+        ColumnValue::Int(v) => Value::Int(v),
+        ColumnValue::UInt(v) => Value::UInt(v),
+        ColumnValue::Float(v) => Value::Float(v),
+        ColumnValue::Double(v) => Value::Double(v),
+        ColumnValue::Date(y, m, d, h, min, sec, ms) => Value::Date(y, m, d, h, min, sec, ms),
+        ColumnValue::Time(neg, d, h, min, sec, ms) => Value::Time(neg, d, h, min, sec, ms),
+    }
+}
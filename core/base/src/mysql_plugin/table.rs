@@ -1,28 +1,41 @@
 use std::collections::HashMap;
-use common::{data::{columns::{Column, ColumnList}, table::{DataQueryResult, TableConfig, TableExportFormat, TableExportOpts, TableQueryOpts, UpdateTableData}}, error::AppError, query::{filter::FilterChain, BasableQuery, QueryCommand}};
+use common::{data::{columns::{Column, ColumnList}, row::ColumnValue, table::{TableConfig, TableExportOpts, TableQueryOpts, UpdateTableData}}, error::AppError, query::{filter::FilterChain, BasableQuery, QueryCommand, QueryExpr}};
 
-use crate::{table::{Table, TableCRUD}, ConnectorType, SharedDB};
+use crate::{connector::RowSink, db::{Placeholder, QueryParams, SqlQuery}, dialect::{Dialect, MySqlDialect}, table::{process_exports, Table, TableCRUD}, ConnectorType, SharedDB};
 
-use super::ColumnValue;
 
 pub struct MySqlTable {
     pub name: String,
     pub connector: ConnectorType,
 }
 impl MySqlTable {
-    fn search_index_name(&self, search_cols: &Vec<String>) -> String {
+    /// Create a new [`MySqlTable`] and assign the given [`ConnectorType`].
+    pub fn new(name: String, conn: ConnectorType) -> Self {
+        MySqlTable {
+            name,
+            connector: conn,
+        }
+    }
+
+    /// Get the table's [`ConnectorType`].
+    pub fn connector(&self) -> &ConnectorType {
+        &self.connector
+    }
+
+    fn search_index_name(&self, search_cols: &[String]) -> String {
         let name = format!("bsearch_{}", search_cols.join("_"));
         name.replace(" ", "_")
     }
 
-    fn create_search_index(&self, search_cols: &Vec<String>) -> Result<(), AppError> {
-        let wrap_cols: Vec<String> = search_cols.iter().map(|col| format!("`{col}`")).collect();
+    fn create_search_index(&self, search_cols: &[String]) -> Result<(), AppError> {
+        let wrap_cols: Vec<String> = search_cols.iter().map(|col| MySqlDialect.quote_ident(col)).collect();
 
-        let index_name = self.search_index_name(&search_cols);
+        let index_name = self.search_index_name(search_cols);
         let index_query = format!(
-            "CREATE FULLTEXT INDEX {index_name} 
+            "CREATE FULLTEXT INDEX {} 
                 ON {} ({})",
-            self.name,
+            MySqlDialect.quote_ident(&index_name),
+            MySqlDialect.quote_ident(&self.name),
             wrap_cols.join(", ")
         );
 
@@ -32,31 +45,29 @@ impl MySqlTable {
         Ok(())
     }
 
-    fn search_index_exists(&self, search_cols: &Vec<String>) -> bool {
-        let index_name = self.search_index_name(&search_cols);
+    fn search_index_exists(&self, search_cols: &[String]) -> bool {
+        let index_name = self.search_index_name(search_cols);
 
-        let index_query = format!("SHOW INDEX FROM {}", self.name);
+        let index_query = format!("SHOW INDEX FROM {}", MySqlDialect.quote_ident(&self.name));
         let conn = self.connector();
 
         if let Ok(rows) = conn.exec_query(&index_query) {
-            let exists = rows.iter().find(|row| {
-                if let Some(name) = row.get::<String, &str>("Key_name") {
-                    return name == index_name;
-                }
-
-                false
-            });
-
-            return exists.is_some();
+            return rows
+                .iter()
+                .any(|row| row.get("Key_name").and_then(ColumnValue::as_str) == Some(index_name.as_str()));
         }
 
         false
     }
 
-    fn drop_search_index(&self, search_cols: &Vec<String>) -> Result<(), AppError> {
-        if self.search_index_exists(&search_cols) {
-            let index_name = self.search_index_name(&search_cols);
-            let index_query = format!("DROP INDEX {index_name} ON {};", self.name,);
+    fn drop_search_index(&self, search_cols: &[String]) -> Result<(), AppError> {
+        if self.search_index_exists(search_cols) {
+            let index_name = self.search_index_name(search_cols);
+            let index_query = format!(
+                "DROP INDEX {} ON {};",
+                MySqlDialect.quote_ident(&index_name),
+                MySqlDialect.quote_ident(&self.name)
+            );
 
             let conn = self.connector();
             conn.exec_query(&index_query)?;
@@ -65,35 +76,20 @@ impl MySqlTable {
         Ok(())
     }
 
-    fn search_prelude(&self, search_cols: &Vec<String>) -> Result<(), AppError> {
-        self.drop_search_index(&search_cols)?;
-        self.create_search_index(&search_cols)?;
+    fn search_prelude(&self, search_cols: &[String]) -> Result<(), AppError> {
+        self.drop_search_index(search_cols)?;
+        self.create_search_index(search_cols)?;
         Ok(())
     }
 }
 
 impl Table for MySqlTable {
-    type Row = mysql::Row;
-
-    fn new(name: String, conn: ConnectorType) -> Self
-    where
-        Self: Sized,
-    {
-        MySqlTable {
-            name,
-            connector: conn,
-        }
-    }
-
     fn name(&self) -> &str {
         &self.name
     }
 
     fn query_columns(&self) -> Result<ColumnList, AppError> {
-        let table_name = &self.name;
-
-        let query = format!(
-            "
+        let query = "
             SELECT 
                 cols.column_name,
                 cols.column_type,
@@ -110,41 +106,40 @@ impl Table for MySqlTable {
                 FROM
                     information_schema.statistics
                 WHERE
-                    table_name = '{table_name}'
+                    table_schema = DATABASE()
+                    AND table_name = ?
                     AND non_unique = 0) AS stats
             ON 
                 cols.column_name = stats.column_name
-                AND cols.table_name = '{table_name}'
+                AND cols.table_name = ?
             LEFT JOIN
                 information_schema.key_column_usage AS kcus
             ON
-                cols.table_name = kcus.table_name
+                cols.table_schema = kcus.table_schema
+                AND cols.table_name = kcus.table_name
                 AND cols.column_name = kcus.column_name
                 AND kcus.constraint_name = 'PRIMARY'
             WHERE
-                cols.table_name = '{table_name}'
+                cols.table_schema = DATABASE()
+                AND cols.table_name = ?
 
-        "
-        );
+        ";
 
         let conn = self.connector();
-        let result = conn.exec_query(&query)?;
+        let table_name = ColumnValue::from(self.name.as_str());
+        let result = conn.exec_params(query, &[table_name.clone(), table_name.clone(), table_name])?;
 
         let cols: ColumnList = result
             .iter()
             .map(|r| {
-                let name: String = r.get("COLUMN_NAME").unwrap();
-                let col_type: String = r.get("COLUMN_TYPE").unwrap();
-                let default: Option<String> = r.get("COLUMN_DEFAULT").unwrap();
+                let name = r.get_string("COLUMN_NAME").unwrap_or_default();
+                let col_type = r.get_string("COLUMN_TYPE").unwrap_or_default();
+                let default = r.get_string("COLUMN_DEFAULT");
 
-                let nullable: Option<String> = r.get("IS_NULLABLE");
-                let nullable = nullable.map(|s| s == "YES".to_owned()).unwrap();
-
-                let unique: Option<String> = r.get("IS_UNIQUE");
-                let unique = unique.map(|s| s == "YES".to_owned()).unwrap();
-
-                let primary: Option<String> = r.get("IS_PRIMARY");
-                let primary = primary.map(|s| s == "YES".to_owned()).unwrap();
+                let flag = |col: &str| r.get(col).and_then(ColumnValue::as_bool).unwrap_or_default();
+                let nullable = flag("IS_NULLABLE");
+                let unique = flag("IS_UNIQUE");
+                let primary = flag("IS_PRIMARY");
 
                 Column {
                     name,
@@ -160,10 +155,6 @@ impl Table for MySqlTable {
         Ok(cols)
     }
 
-    fn connector(&self) -> &ConnectorType {
-        &self.connector
-    }
-
     fn init_config(&self) -> Option<TableConfig> {
         let mut config = None;
 
@@ -171,7 +162,7 @@ impl Table for MySqlTable {
             let mut iter = cols.iter();
             let mut pk = iter.find(|c| c.primary);
 
-            if let None = pk {
+            if pk.is_none() {
                 pk = iter.find(|c| c.unique);
             }
 
@@ -192,11 +183,13 @@ impl Table for MySqlTable {
 }
 
 impl TableCRUD for MySqlTable {
-    fn query_data(
+    fn stream_data(
         &self,
         opts: TableQueryOpts,
         db: &SharedDB,
-    ) -> DataQueryResult<ColumnValue, AppError> {
+        sink: &mut RowSink<'_, HashMap<String, ColumnValue>>,
+    ) -> Result<(), AppError> {
+        self.validate_columns(&opts.referenced_columns())?;
         let is_search_mode = opts.is_search_mode();
         let mut search_cols = Vec::new();
 
@@ -219,35 +212,32 @@ impl TableCRUD for MySqlTable {
                 }
             });
 
-        let query = opts.try_into()?;
-        let sql = db.generate_sql(query)?;
+        let query = TableQueryOpts { table: self.name.clone(), ..opts }.try_into()?;
+        let query = db.generate_sql(query)?;
 
         let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
-
-        let data = rows
-            .iter()
-            .map(|r| {
-                let mut map: HashMap<String, ColumnValue> = HashMap::new();
+        let streamed = conn.stream_sql(&query, &mut |r| {
+            let mut map: HashMap<String, ColumnValue> = HashMap::new();
 
-                for col in &cols {
-                    if let Some(v) = r.get::<mysql::Value, &str>(col) {
-                        map.insert(col.clone(), v.into());
-                    }
+            for col in &cols {
+                if let Some(v) = r.get(col) {
+                    map.insert(col.clone(), v.clone());
                 }
+            }
 
-                map
-            })
-            .collect();
+            sink(map)
+        });
 
+        // The index is dropped even if streaming was stopped early.
         if is_search_mode {
-            let _ = self.drop_search_index(&search_cols)?;
+            self.drop_search_index(&search_cols)?;
         }
 
-        Ok(data)
+        streamed
     }
 
     fn query_result_count(&self, opts: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError> {
+        self.validate_columns(&opts.referenced_columns())?;
         let is_search_mode = opts.is_search_mode();
         let mut search_cols = Vec::new();
 
@@ -259,54 +249,54 @@ impl TableCRUD for MySqlTable {
         }
 
         let query = BasableQuery {
-            table: opts.table,
-            command: QueryCommand::SelectData(Some(vec!["COUNT(*)".to_string()])),
+            table: self.name.clone(),
+            command: QueryCommand::SelectData(Some(vec![QueryExpr::Sql("COUNT(*)".to_string())])),
             search_opts: opts.search_opts,
             filters: opts
                 .filters
-                .map_or(FilterChain::empty(), |fs| FilterChain::prefill(fs)),
+                .map_or(FilterChain::empty(), FilterChain::prefill),
             ..Default::default()
         };
 
-        let sql = db.generate_sql(query)?;
+        let query = db.generate_sql(query)?;
 
         let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
+        let rows = conn.exec_sql(&query)?;
 
         let count = rows
             .first()
-            .map(|row| row.get::<usize, &str>("COUNT(*)").unwrap_or_default())
+            .and_then(|row| row.get("COUNT(*)"))
+            .and_then(ColumnValue::as_i64)
             .unwrap_or_default();
 
         if is_search_mode {
-            let _ = self.drop_search_index(&search_cols)?;
+            self.drop_search_index(&search_cols)?;
         }
 
-        Ok(count)
+        Ok(count as usize)
     }
 
     fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
-        let len = input.len();
-        let mut data = HashMap::new();
-
-        for (k, v) in input {
-            data.insert(k, format!("'{}'", v));
-        }
-
-        let mut keys = Vec::with_capacity(len);
-        let mut values = Vec::with_capacity(len);
+        let keys: Vec<&str> = input.keys().map(String::as_str).collect();
+        self.validate_columns(&keys)?;
 
-        data.iter().for_each(|(k, v)| {
-            keys.push(k.as_str());
-            values.push(v.as_str());
-        });
+        let mut params = QueryParams::new(Placeholder::Question);
+        let mut cols = Vec::with_capacity(input.len());
+        let mut values = Vec::with_capacity(input.len());
 
-        let keys = keys.join(", ");
-        let values = values.join(", ");
+        for (k, v) in &input {
+            cols.push(MySqlDialect.quote_ident(k));
+            values.push(params.bind(v.as_str()));
+        }
 
-        let query = format!("INSERT INTO {} ({}) VALUES ({})", self.name, keys, values);
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            MySqlDialect.quote_ident(&self.name),
+            cols.join(", "),
+            values.join(", ")
+        );
         let conn = self.connector();
-        conn.exec_query(&query)?;
+        conn.exec_sql(&SqlQuery::new(query, params))?;
 
         Ok(())
     }
@@ -319,41 +309,59 @@ impl TableCRUD for MySqlTable {
             input,
         } = options;
 
+        let mut names: Vec<&str> = columns.iter().map(String::as_str).collect();
+        names.push(&unique_key);
+        self.validate_columns(&names)?;
+
+        let key = MySqlDialect.quote_ident(&unique_key);
+        let mut params = QueryParams::new(Placeholder::Question);
+
         let mut cases = vec![];
         for (index, col) in columns.iter().enumerate() {
             let cmd = if index == 0 { "SET \n" } else { "" };
-            let mut q = format!("{cmd} `{col}` = CASE `{unique_key}` \n");
+            let col_ident = MySqlDialect.quote_ident(col);
+            let mut q = format!("{cmd} {col_ident} = CASE {key} \n");
 
             for (index, uv) in unique_values.iter().enumerate() {
                 if let Some(values) = input.get(index) {
                     if let Some(val) = values.get(col) {
-                        q.push_str(&format!("WHEN {uv} THEN '{val}' \n"));
+                        let uv = params.bind(uv.as_str());
+                        q.push_str(&format!("WHEN {uv} THEN {} \n", params.bind(val.as_str())));
                     }
                 }
             }
 
-            q.push_str(&format!("ELSE `{col}` \n END"));
+            q.push_str(&format!("ELSE {col_ident} \n END"));
             cases.push(q);
         }
 
         let cases = cases.join(", \n");
-        let unique_values = unique_values.join(",");
+        let unique_values = params.bind_list(&unique_values);
 
         let query = format!(
-            "UPDATE {} \n {} WHERE `{}` IN ({})",
-            self.name, cases, unique_key, unique_values
+            "UPDATE {} \n {} WHERE {} IN ({})",
+            MySqlDialect.quote_ident(&self.name),
+            cases,
+            key,
+            unique_values
         );
 
         let conn = self.connector();
-        conn.exec_query(&query)?;
+        conn.exec_sql(&SqlQuery::new(query, params))?;
 
         Ok(())
     }
 
     fn delete_data(&self, col: String, value: String) -> Result<(), AppError> {
-        let query = format!("DELETE FROM {} WHERE {} = '{}'", self.name, col, value);
+        self.validate_columns(&[&col])?;
+
+        let query = format!(
+            "DELETE FROM {} WHERE {} = ?",
+            MySqlDialect.quote_ident(&self.name),
+            MySqlDialect.quote_ident(&col)
+        );
         let conn = self.connector();
-        conn.exec_query(&query)?;
+        conn.exec_params(&query, &[value.into()])?;
 
         Ok(())
     }
@@ -365,6 +373,7 @@ impl TableCRUD for MySqlTable {
             trim,
         } = opts;
 
+        self.validate_columns(&query_opts.referenced_columns())?;
         let cols = query_opts
             .columns
             .clone()
@@ -380,7 +389,7 @@ impl TableCRUD for MySqlTable {
         let selection = if cols.is_empty() {
             None
         } else {
-            Some(cols.clone())
+            Some(cols.iter().map(|col| col.as_str().into()).collect())
         };
 
         let filters = query_opts.filters.map_or(FilterChain::empty(), |filters| {
@@ -389,97 +398,20 @@ impl TableCRUD for MySqlTable {
 
         // get rows
         let query = BasableQuery {
-            table: query_opts.table,
+            table: self.name.clone(),
             command: QueryCommand::SelectData(selection),
             filters,
-            offset: trim.as_ref().map_or(None, |trim| Some(trim.offset)),
-            row_count: trim.map_or(None, |trim| Some(trim.count)),
+            offset: trim.as_ref().map(|trim| trim.offset),
+            row_count: trim.map(|trim| trim.count),
             ..Default::default()
         };
 
-        let sql = db.generate_sql(query)?;
+        let query = db.generate_sql(query)?;
 
         let conn = self.connector();
-        let rows = conn.exec_query(&sql)?;
+        let rows = conn.exec_sql(&query)?;
         let content = process_exports(format, cols, rows);
 
         Ok(content)
     }
 }
-
-fn process_exports(
-    format: TableExportFormat,
-    columns: Vec<String>,
-    rows: Vec<mysql::Row>,
-) -> String {
-    match format {
-        TableExportFormat::CSV
-        | TableExportFormat::PSV
-        | TableExportFormat::TSV
-        | TableExportFormat::TEXT => {
-            let delimiter = format.field_delimiter().unwrap_or_default();
-            let headers = columns.join(&delimiter);
-            let row_list: Vec<String> = rows
-                .iter()
-                .map(|row| {
-                    let mut row_data = Vec::with_capacity(columns.len());
-                    for col in &columns {
-                        row_data.push(row.get::<String, &str>(col).unwrap_or_default());
-                    }
-
-                    row_data.join(&delimiter)
-                })
-                .collect();
-
-            let body = row_list.join("\n");
-            format!("{headers} \n {body}")
-        }
-        TableExportFormat::JSON => {
-            let row_list: Vec<String> = rows
-                .iter()
-                .map(|row| {
-                    let values: Vec<String> = columns
-                        .iter()
-                        .map(|col| {
-                            let val = row.get::<String, &str>(col).unwrap_or_default();
-                            format!("\t\"{}\":\"{}\"", col, val)
-                        })
-                        .collect();
-
-                    format!("{{\n\t{}\n\t}}", values.join(",\n\t"))
-                })
-                .collect();
-
-            format!("[\n\t{}\n]", row_list.join(",\n\t"))
-        }
-        TableExportFormat::HTML => {
-            let col_list: Vec<String> = columns
-                .iter()
-                .map(|col| format!("<th>{col}</th>"))
-                .collect();
-            let header = format!(
-                "<thead>\n\t\t<tr>\n\t\t\t{}\n\t\t</tr>\n\t</thead>",
-                col_list.join("\n\t\t\t")
-            );
-
-            let row_list: Vec<String> = rows
-                .iter()
-                .map(|row| {
-                    let td_list: Vec<String> = columns
-                        .iter()
-                        .map(|col| {
-                            let val = row.get::<String, &str>(col).unwrap_or_default();
-                            format!("<td>{val}</td>")
-                        })
-                        .collect();
-
-                    format!("<tr>\n\t\t\t{}\n\t\t</tr>", td_list.join("\n\t\t\t"))
-                })
-                .collect();
-            let body = format!("<tbody>\n\t\t{}\n\t</tbody>", row_list.join("\n\t\t"));
-
-            format!("<table>\n\t{header}\n\t{body}\n<table>")
-        }
-        _ => "".to_string(),
-    }
-}
@@ -0,0 +1,123 @@
+use std::{sync::Arc, thread};
+
+use common::{
+    data::row::{ColumnValue, Row},
+    error::AppError,
+};
+use postgres::{types::ToSql, Config, NoTls};
+use r2d2::Pool;
+use r2d2_postgres::PostgresConnectionManager;
+
+use crate::{
+    config::ConfigRaw,
+    connector::{Connector, RowSink},
+};
+
+use super::{column_value, TextParam};
+
+type PostgresPool = Pool<PostgresConnectionManager<NoTls>>;
+
+/// Number of rows fetched from a portal at once.
+const FETCH_SIZE: i32 = 1000;
+
+/// PostgreSQL implementation of `BasableConnection`
+///
+/// The `postgres` client drives its own runtime and panics when it blocks inside an async
+/// context, so connectors must only be used from blocking threads, such as the app's
+/// `BlockingPool`.
+#[derive(Default)]
+pub struct PostgresConnector {
+    /// Database connection pool
+    pool: Option<PostgresPool>,
+
+    /// Connection options
+    pub config: ConfigRaw,
+}
+
+impl PostgresConnector {
+    fn pool(&self) -> Result<&PostgresPool, AppError> {
+        self.pool
+            .as_ref()
+            .ok_or_else(|| AppError::ServerError("postgres connection pool not initialized".to_string()))
+    }
+}
+
+impl Connector for PostgresConnector {
+    type Row = Row;
+
+    fn new(config: ConfigRaw) -> Result<Self, AppError> {
+        let url = config.build_url()?;
+        let opts: Config = url
+            .parse()
+            .map_err(|err: postgres::Error| AppError::ServerError(err.to_string()))?;
+
+        let manager = PostgresConnectionManager::new(opts, NoTls);
+        let pool = Pool::new(manager).map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        Ok(PostgresConnector {
+            pool: Some(pool),
+            config,
+        })
+    }
+
+    /// Rows are read through a portal, PostgreSQL's server-side cursor, [`FETCH_SIZE`] at a time.
+    fn stream_params(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        sink: &mut RowSink<'_, Self::Row>,
+    ) -> Result<(), AppError> {
+        let pool = self.pool()?;
+        let map_err = |err: postgres::Error| AppError::ServerError(err.to_string());
+
+        let mut conn = pool
+            .get()
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        let params: Vec<TextParam> = params.iter().map(TextParam).collect();
+        let params: Vec<&(dyn ToSql + Sync)> =
+            params.iter().map(|p| p as &(dyn ToSql + Sync)).collect();
+
+        // Portals only live within a transaction.
+        let mut tx = conn.transaction().map_err(map_err)?;
+        let stmt = tx.prepare(query).map_err(map_err)?;
+        let portal = tx.bind(&stmt, &params).map_err(map_err)?;
+
+        let columns: Arc<Vec<String>> = Arc::new(
+            stmt.columns()
+                .iter()
+                .map(|c| c.name().to_string())
+                .collect(),
+        );
+
+        loop {
+            let rows = tx.query_portal(&portal, FETCH_SIZE).map_err(map_err)?;
+            let done = rows.len() < FETCH_SIZE as usize;
+
+            for row in &rows {
+                let values = (0..columns.len()).map(|idx| column_value(row, idx)).collect();
+                sink(Row::new(columns.clone(), values))?;
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        tx.commit().map_err(map_err)
+    }
+
+    fn config(&self) -> &ConfigRaw {
+        &self.config
+    }
+}
+
+impl Drop for PostgresConnector {
+    fn drop(&mut self) {
+        // Closing pooled clients blocks on their runtimes as well, and the last connection may
+        // be dropped on an async thread.
+        if let Some(pool) = self.pool.take() {
+            thread::spawn(move || drop(pool));
+        }
+    }
+}
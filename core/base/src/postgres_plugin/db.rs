@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use common::{
+    data::{
+        row::{ColumnValue, Row},
+        table::{TableSummaries, TableSummary},
+    },
+    error::AppError,
+    DbServerDetails,
+};
+use uuid::Uuid;
+
+use crate::{
+    connector::Connector,
+    db::{DBQueryResult, QuerySqlParser, DB},
+    dialect::{Dialect, PostgresDialect},
+    graphs::sql::SqlGraphs,
+    SharedTable,
+};
+
+use super::{connector::PostgresConnector, table::PostgresTable};
+
+pub struct PostgresDB {
+    pub connector: Arc<PostgresConnector>,
+    pub tables: Vec<SharedTable>,
+    user_id: String,
+    id: Uuid,
+}
+
+impl PostgresDB {
+    pub fn new(connector: Arc<PostgresConnector>, id: Uuid, user_id: String) -> Self {
+        PostgresDB {
+            connector,
+            tables: Vec::new(),
+            user_id,
+            id,
+        }
+    }
+
+    /// Get the [`PostgresConnector`] instance for [`PostgresDB`].
+    pub fn connector(&self) -> &Arc<PostgresConnector> {
+        &self.connector
+    }
+
+    /// Query server for base tables in the current schema, with their estimated row count.
+    fn query_tables(&self) -> DBQueryResult<Row, AppError> {
+        self.exec_query(
+            "
+                SELECT c.relname AS table_name, COALESCE(s.n_live_tup, 0) AS table_rows
+                FROM pg_catalog.pg_class c
+                JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+                LEFT JOIN pg_catalog.pg_stat_user_tables s ON s.relid = c.oid
+                WHERE c.relkind IN ('r', 'p') AND n.nspname = current_schema()
+                ORDER BY c.relname
+            ",
+        )
+    }
+
+    /// Database size in MB
+    fn size(&self) -> Result<f64, AppError> {
+        let qr = self.exec_query(
+            "SELECT ROUND(pg_database_size(current_database()) / 1024.0 / 1024.0, 1)::float8 AS size",
+        )?;
+
+        let size = qr
+            .first()
+            .and_then(|r| r.get("size"))
+            .and_then(ColumnValue::as_f64)
+            .unwrap_or_default();
+
+        Ok(size)
+    }
+
+    fn exec_query(&self, query: &str) -> Result<Vec<Row>, AppError> {
+        self.connector.exec_query(query)
+    }
+}
+
+impl DB for PostgresDB {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    fn load_tables(&mut self) -> Result<(), AppError> {
+        let tables = self.query_tables()?;
+
+        for t in tables {
+            let name = t.get_string("table_name").unwrap_or_default();
+            let table = PostgresTable::new(name, self.connector.clone());
+            self.tables.push(Arc::new(table));
+        }
+
+        Ok(())
+    }
+
+    fn tables(&self) -> &Vec<SharedTable> {
+        &self.tables
+    }
+
+    fn build_table_list(&self) -> Result<TableSummaries, AppError> {
+        let results = self.query_tables()?;
+        let tables: Vec<TableSummary> = results
+            .iter()
+            .map(|res| {
+                let name = res.get_string("table_name").unwrap_or_default();
+                let row_count = res.get("table_rows").and_then(ColumnValue::as_i64).unwrap_or_default();
+
+                let col_count = self.query_column_count(&name).unwrap_or_default();
+
+                // PostgreSQL does not keep track of table creation or update time.
+                TableSummary {
+                    name,
+                    col_count,
+                    row_count: row_count as u32,
+                    created: None,
+                    updated: None,
+                }
+            })
+            .collect();
+
+        Ok(tables)
+    }
+
+    fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        let qr = self.connector.exec_params(
+            "
+                SELECT COUNT(*) AS count
+                FROM information_schema.columns
+                WHERE table_schema = current_schema() AND table_name = $1
+            ",
+            &[tb_name.into()],
+        )?;
+        let c = qr
+            .first()
+            .and_then(|r| r.get("count"))
+            .and_then(ColumnValue::as_i64)
+            .unwrap_or_default();
+
+        Ok(c as u32)
+    }
+
+    fn get_table(&self, name: &str) -> Option<&SharedTable> {
+        self.tables.iter().find(|t| t.name() == name)
+    }
+
+    fn details(&self) -> Result<DbServerDetails, AppError> {
+        let rows = self.exec_query("SELECT current_setting('server_version') AS version, version() AS comment")?;
+        let row = rows.first();
+
+        let version = row.and_then(|r| r.get_string("version"));
+        let comment = row.and_then(|r| r.get_string("comment"));
+
+        // `version()` reads like "PostgreSQL 16.2 on x86_64-pc-linux-gnu, compiled by ..."
+        let os = comment.as_ref().and_then(|c| {
+            c.split_once(" on ")
+                .map(|(_, rest)| rest.split(',').next().unwrap_or_default().to_string())
+        });
+
+        let size = self.size()?;
+
+        Ok(DbServerDetails {
+            version: version.unwrap_or_default(),
+            os: os.unwrap_or_default(),
+            comment,
+            db_size: size,
+        })
+    }
+}
+
+impl QuerySqlParser for PostgresDB {
+    fn dialect(&self) -> &dyn Dialect {
+        &PostgresDialect
+    }
+}
+
+impl SqlGraphs for PostgresDB {
+    fn sql_connector(&self) -> &dyn Connector<Row = Row> {
+        self.connector.as_ref()
+    }
+}
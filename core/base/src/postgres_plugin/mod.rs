@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use common::data::row::ColumnValue;
+use bytes::BytesMut;
+use postgres::{
+    types::{to_sql_checked, Format, IsNull, ToSql, Type},
+    Row,
+};
+use rust_decimal::Decimal;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time};
+
+use crate::{connector::Connector, registry::BackendRegistry};
+
+use self::{connector::PostgresConnector, db::PostgresDB};
+
+pub mod db;
+pub mod connector;
+pub mod table;
+
+/// Register the PostgreSQL backend for `database` sources named `postgres`.
+pub fn register(registry: &mut BackendRegistry) {
+    registry.register("database", "postgres", |config, id, user_id| {
+        let conn = PostgresConnector::new(config.clone())?;
+        Ok(Box::new(PostgresDB::new(Arc::new(conn), id, user_id)))
+    });
+}
+
+/// Read the value at `idx` of a PostgreSQL [`Row`] as a [`ColumnValue`].
+///
+/// Types without a dedicated conversion are read as text where possible, and as
+/// [`ColumnValue::NULL`] otherwise.
+pub(crate) fn column_value(row: &Row, idx: usize) -> ColumnValue {
+    let ty = row.columns()[idx].type_();
+
+    let value = match *ty {
+        Type::BOOL => row
+            .try_get::<_, Option<bool>>(idx)
+            .map(|v| v.map(|v| ColumnValue::Int(v as i64))),
+        Type::CHAR => row
+            .try_get::<_, Option<i8>>(idx)
+            .map(|v| v.map(|v| ColumnValue::Int(v as i64))),
+        Type::INT2 => row
+            .try_get::<_, Option<i16>>(idx)
+            .map(|v| v.map(|v| ColumnValue::Int(v as i64))),
+        Type::INT4 => row
+            .try_get::<_, Option<i32>>(idx)
+            .map(|v| v.map(|v| ColumnValue::Int(v as i64))),
+        Type::INT8 => row
+            .try_get::<_, Option<i64>>(idx)
+            .map(|v| v.map(ColumnValue::Int)),
+        Type::OID => row
+            .try_get::<_, Option<u32>>(idx)
+            .map(|v| v.map(|v| ColumnValue::UInt(v as u64))),
+        Type::FLOAT4 => row
+            .try_get::<_, Option<f32>>(idx)
+            .map(|v| v.map(ColumnValue::Float)),
+        Type::FLOAT8 => row
+            .try_get::<_, Option<f64>>(idx)
+            .map(|v| v.map(ColumnValue::Double)),
+        Type::NUMERIC => row
+            .try_get::<_, Option<Decimal>>(idx)
+            .map(|v| v.map(|v| ColumnValue::Decimal(v.to_string()))),
+        Type::DATE => row
+            .try_get::<_, Option<Date>>(idx)
+            .map(|v| v.map(|d| date_value(d, Time::MIDNIGHT))),
+        Type::TIMESTAMP => row
+            .try_get::<_, Option<PrimitiveDateTime>>(idx)
+            .map(|v| v.map(|dt| date_value(dt.date(), dt.time()))),
+        Type::TIMESTAMPTZ => row
+            .try_get::<_, Option<OffsetDateTime>>(idx)
+            .map(|v| {
+                v.map(|dt| {
+                    let dt = dt.to_offset(time::UtcOffset::UTC);
+                    date_value(dt.date(), dt.time())
+                })
+            }),
+        Type::TIME => row.try_get::<_, Option<Time>>(idx).map(|v| {
+            v.map(|t| ColumnValue::Time(false, 0, t.hour(), t.minute(), t.second(), t.microsecond()))
+        }),
+        Type::JSON | Type::JSONB => row
+            .try_get::<_, Option<serde_json::Value>>(idx)
+            .map(|v| v.map(|v| ColumnValue::Text(v.to_string()))),
+        Type::UUID => row
+            .try_get::<_, Option<uuid::Uuid>>(idx)
+            .map(|v| v.map(|v| ColumnValue::Text(v.to_string()))),
+        _ => row
+            .try_get::<_, Option<String>>(idx)
+            .map(|v| v.map(ColumnValue::Text)),
+    };
+
+    value.ok().flatten().unwrap_or(ColumnValue::NULL)
+}
+
+fn date_value(date: Date, time: Time) -> ColumnValue {
+    ColumnValue::Date(
+        date.year() as u16,
+        date.month() as u8,
+        date.day(),
+        time.hour(),
+        time.minute(),
+        time.second(),
+        time.microsecond(),
+    )
+}
+
+/// A [`ColumnValue`] bound to a query in text format.
+///
+/// The server parses the text as whatever type it infers for the placeholder, as it does
+/// with untyped literals, so values can be sent without knowing the column types.
+#[derive(Debug)]
+pub(crate) struct TextParam<'a>(pub &'a ColumnValue);
+
+impl ToSql for TextParam<'_> {
+    fn to_sql(
+        &self,
+        _ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        if self.0.is_null() {
+            return Ok(IsNull::Yes);
+        }
+
+        out.extend_from_slice(self.0.to_string().as_bytes());
+        Ok(IsNull::No)
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    fn encode_format(&self, _ty: &Type) -> Format {
+        Format::Text
+    }
+
+    to_sql_checked!();
+}
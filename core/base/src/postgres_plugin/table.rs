@@ -0,0 +1,310 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::http::StatusCode;
+use common::{
+    data::{
+        columns::{Column, ColumnList},
+        row::ColumnValue,
+        table::{TableConfig, TableExportOpts, TableQueryOpts, UpdateTableData},
+    },
+    error::AppError,
+    query::{filter::FilterChain, BasableQuery, QueryCommand, QueryExpr},
+};
+
+use crate::{
+    connector::{Connector, RowSink},
+    db::{Placeholder, QueryParams, SqlQuery},
+    dialect::quote_ident,
+    table::{process_exports, Table, TableCRUD},
+    SharedDB,
+};
+
+use super::connector::PostgresConnector;
+
+pub struct PostgresTable {
+    pub name: String,
+    pub connector: Arc<PostgresConnector>,
+}
+
+impl PostgresTable {
+    /// Create a new [`PostgresTable`] and assign the given [`PostgresConnector`].
+    pub fn new(name: String, conn: Arc<PostgresConnector>) -> Self {
+        PostgresTable {
+            name,
+            connector: conn,
+        }
+    }
+
+    /// Get the table's [`PostgresConnector`].
+    pub fn connector(&self) -> &Arc<PostgresConnector> {
+        &self.connector
+    }
+
+    fn selected_columns(&self, columns: &Option<Vec<String>>) -> Vec<String> {
+        columns
+            .clone()
+            .filter(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            })
+    }
+}
+
+impl Table for PostgresTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn query_columns(&self) -> Result<ColumnList, AppError> {
+        let query = "
+            SELECT
+                a.attname AS column_name,
+                pg_catalog.format_type(a.atttypid, a.atttypmod) AS column_type,
+                NOT a.attnotnull AS is_nullable,
+                pg_catalog.pg_get_expr(d.adbin, d.adrelid) AS column_default,
+                EXISTS (
+                    SELECT 1 FROM pg_catalog.pg_index i
+                    WHERE i.indrelid = a.attrelid AND i.indisunique
+                        AND i.indnatts = 1 AND i.indkey[0] = a.attnum
+                ) AS is_unique,
+                EXISTS (
+                    SELECT 1 FROM pg_catalog.pg_index i
+                    WHERE i.indrelid = a.attrelid AND i.indisprimary
+                        AND a.attnum = ANY(i.indkey)
+                ) AS is_primary
+            FROM
+                pg_catalog.pg_attribute a
+            LEFT JOIN
+                pg_catalog.pg_attrdef d
+            ON
+                d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE
+                a.attrelid = $1::regclass
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+            ORDER BY a.attnum
+        ";
+
+        let table = quote_ident(&self.name);
+        let result = self.connector.exec_params(query, &[table.into()])?;
+
+        let cols: ColumnList = result
+            .iter()
+            .map(|r| {
+                let flag = |col: &str| r.get(col).and_then(ColumnValue::as_bool).unwrap_or_default();
+
+                Column {
+                    name: r.get_string("column_name").unwrap_or_default(),
+                    col_type: r.get_string("column_type").unwrap_or_default(),
+                    default_value: r.get_string("column_default"),
+                    nullable: flag("is_nullable"),
+                    unique: flag("is_unique"),
+                    primary: flag("is_primary"),
+                }
+            })
+            .collect();
+
+        Ok(cols)
+    }
+
+    fn init_config(&self) -> Option<TableConfig> {
+        let cols = self.query_columns().ok()?;
+
+        let pk = cols
+            .iter()
+            .find(|c| c.primary)
+            .or_else(|| cols.iter().find(|c| c.unique))
+            .map(|pk| pk.name.clone());
+
+        Some(TableConfig {
+            pk_column: pk,
+            name: self.name.clone(),
+            label: self.name.clone(),
+            ..TableConfig::default()
+        })
+    }
+}
+
+impl TableCRUD for PostgresTable {
+    fn stream_data(
+        &self,
+        opts: TableQueryOpts,
+        db: &SharedDB,
+        sink: &mut RowSink<'_, HashMap<String, ColumnValue>>,
+    ) -> Result<(), AppError> {
+        self.validate_columns(&opts.referenced_columns())?;
+        let cols = self.selected_columns(&opts.columns);
+
+        let query = TableQueryOpts { table: self.name.clone(), ..opts }.try_into()?;
+        let query = db.generate_sql(query)?;
+
+        self.connector.stream_sql(&query, &mut |r| {
+            let mut map = r.into_map();
+            map.retain(|col, _| cols.contains(col));
+            sink(map)
+        })
+    }
+
+    fn query_result_count(&self, opts: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError> {
+        self.validate_columns(&opts.referenced_columns())?;
+
+        let query = BasableQuery {
+            table: self.name.clone(),
+            command: QueryCommand::SelectData(Some(vec![QueryExpr::Sql("COUNT(*)".to_string())])),
+            search_opts: opts.search_opts,
+            filters: opts
+                .filters
+                .map_or(FilterChain::empty(), FilterChain::prefill),
+            ..Default::default()
+        };
+
+        let query = db.generate_sql(query)?;
+        let rows = self.connector.exec_sql(&query)?;
+
+        let count = rows
+            .first()
+            .and_then(|row| row.get_at(0))
+            .and_then(ColumnValue::as_i64)
+            .unwrap_or_default();
+
+        Ok(count as usize)
+    }
+
+    fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
+        let keys: Vec<&str> = input.keys().map(String::as_str).collect();
+        self.validate_columns(&keys)?;
+
+        let mut params = QueryParams::new(Placeholder::Numbered);
+        let mut cols = Vec::with_capacity(input.len());
+        let mut values = Vec::with_capacity(input.len());
+
+        for (k, v) in &input {
+            cols.push(quote_ident(k));
+            values.push(params.bind(v.as_str()));
+        }
+
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_ident(&self.name),
+            cols.join(", "),
+            values.join(", ")
+        );
+        self.connector.exec_sql(&SqlQuery::new(query, params))?;
+
+        Ok(())
+    }
+
+    fn update_data(&self, options: UpdateTableData) -> Result<(), AppError> {
+        let UpdateTableData {
+            unique_key,
+            columns,
+            unique_values,
+            input,
+        } = options;
+
+        // Values arrive as text, so each CASE is evaluated as text and cast back to the column's type.
+        let column_types: HashMap<String, String> = self
+            .query_columns()?
+            .into_iter()
+            .map(|c| (c.name, c.col_type))
+            .collect();
+
+        if !column_types.contains_key(&unique_key) {
+            return Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("Unknown column '{unique_key}' in table '{}'", self.name),
+            ));
+        }
+
+        let key = quote_ident(&unique_key);
+        let mut params = QueryParams::new(Placeholder::Numbered);
+
+        let mut cases = vec![];
+        for col in &columns {
+            let col_type = column_types.get(col).ok_or_else(|| {
+                AppError::HttpError(
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown column '{col}' in table '{}'", self.name),
+                )
+            })?;
+
+            let col_ident = quote_ident(col);
+            let mut q = format!("{col_ident} = CASE {key}::text \n");
+
+            for (index, uv) in unique_values.iter().enumerate() {
+                if let Some(val) = input.get(index).and_then(|values| values.get(col)) {
+                    let uv = params.bind(uv.as_str());
+                    q.push_str(&format!("WHEN {uv} THEN {} \n", params.bind(val.as_str())));
+                }
+            }
+
+            q.push_str(&format!("ELSE {col_ident}::text \n END::{col_type}"));
+            cases.push(q);
+        }
+
+        let query = format!(
+            "UPDATE {} \n SET {} \n WHERE {key}::text IN ({})",
+            quote_ident(&self.name),
+            cases.join(", \n"),
+            params.bind_list(&unique_values),
+        );
+
+        self.connector.exec_sql(&SqlQuery::new(query, params))?;
+
+        Ok(())
+    }
+
+    fn delete_data(&self, col: String, value: String) -> Result<(), AppError> {
+        self.validate_columns(&[&col])?;
+
+        let query = format!(
+            "DELETE FROM {} WHERE {}::text = $1",
+            quote_ident(&self.name),
+            quote_ident(&col),
+        );
+        self.connector.exec_params(&query, &[value.into()])?;
+
+        Ok(())
+    }
+
+    fn export(&self, opts: TableExportOpts, db: &SharedDB) -> Result<String, AppError> {
+        let TableExportOpts {
+            query_opts,
+            format,
+            trim,
+        } = opts;
+
+        self.validate_columns(&query_opts.referenced_columns())?;
+        let cols = self.selected_columns(&query_opts.columns);
+
+        let selection = if cols.is_empty() {
+            None
+        } else {
+            Some(cols.iter().map(|col| col.as_str().into()).collect())
+        };
+
+        let filters = query_opts
+            .filters
+            .map_or(FilterChain::empty(), FilterChain::prefill);
+
+        let query = BasableQuery {
+            table: self.name.clone(),
+            command: QueryCommand::SelectData(selection),
+            filters,
+            offset: trim.as_ref().map(|trim| trim.offset),
+            row_count: trim.map(|trim| trim.count),
+            ..Default::default()
+        };
+
+        let query = db.generate_sql(query)?;
+        let rows = self.connector.exec_sql(&query)?;
+
+        let content = process_exports(format, cols, rows);
+
+        Ok(content)
+    }
+}
@@ -0,0 +1,125 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::http::StatusCode;
+use common::error::AppError;
+use uuid::Uuid;
+
+use crate::{
+    config::{ConfigRaw, DataDir},
+    DbType, SharedDB,
+};
+
+/// Builds a [`DB`](crate::db::DB) from the connection options, with the given connection id, for
+/// the user with the given id.
+pub type DbFactory = Box<dyn Fn(&ConfigRaw, Uuid, String) -> Result<Box<DbType>, AppError> + Send + Sync>;
+
+/// Factories for every data source the app can connect to, keyed by
+/// [`ConfigRaw::source_type`] and [`ConfigRaw::source`].
+///
+/// [`BackendRegistry::default`] registers the backends enabled through cargo features.
+/// Other sources can be added with [`BackendRegistry::register`].
+pub struct BackendRegistry {
+    factories: HashMap<(String, String), DbFactory>,
+
+    /// Where file sources are read from. Without it, they can't be connected to.
+    data_dir: Option<DataDir>,
+}
+
+impl BackendRegistry {
+    /// Create a registry without any backend.
+    pub fn empty() -> Self {
+        BackendRegistry {
+            factories: HashMap::new(),
+            data_dir: None,
+        }
+    }
+
+    /// Register `factory` for `source_type` and `source`, replacing any factory registered
+    /// for them before.
+    pub fn register<F>(&mut self, source_type: &str, source: &str, factory: F)
+    where
+        F: Fn(&ConfigRaw, Uuid, String) -> Result<Box<DbType>, AppError> + Send + Sync + 'static,
+    {
+        self.factories.insert(
+            (source_type.to_string(), source.to_string()),
+            Box::new(factory),
+        );
+    }
+
+    /// Read file sources from `data_dir`.
+    pub fn set_data_dir(&mut self, data_dir: DataDir) {
+        self.data_dir = Some(data_dir);
+    }
+
+    /// Whether a backend is registered for `source_type` and `source`.
+    pub fn contains(&self, source_type: &str, source: &str) -> bool {
+        self.factories
+            .contains_key(&(source_type.to_string(), source.to_string()))
+    }
+
+    /// The `(source_type, source)` pairs backends are registered for.
+    pub fn sources(&self) -> Vec<(&str, &str)> {
+        let mut sources: Vec<(&str, &str)> = self
+            .factories
+            .keys()
+            .map(|(source_type, source)| (source_type.as_str(), source.as_str()))
+            .collect();
+        sources.sort();
+        sources
+    }
+
+    /// Build a [`SharedDB`] identified by `id` for `config` using the registered factory, and
+    /// load its tables. The path of file sources is resolved in the data directory first.
+    pub fn create(&self, config: &ConfigRaw, id: Uuid, user_id: String) -> Result<SharedDB, AppError> {
+        let key = (config.source_type.clone(), config.source.clone());
+
+        let factory = self.factories.get(&key).ok_or_else(|| {
+            AppError::HttpError(
+                StatusCode::NOT_IMPLEMENTED,
+                format!(
+                    "Unsupported source '{}' for source type '{}'",
+                    config.source, config.source_type
+                ),
+            )
+        })?;
+
+        let mut config = config.clone();
+        if config.is_file_source()? {
+            let data_dir = self.data_dir.as_ref().ok_or_else(|| {
+                AppError::HttpError(
+                    StatusCode::FORBIDDEN,
+                    "File sources are disabled, since no data directory is configured".to_string(),
+                )
+            })?;
+
+            let path = data_dir.resolve(&config.build_url()?)?;
+            config.path = Some(path.to_string_lossy().into_owned());
+        }
+
+        let mut db = factory(&config, id, user_id)?;
+        db.load_tables()?;
+
+        Ok(Arc::from(db))
+    }
+}
+
+impl Default for BackendRegistry {
+    fn default() -> Self {
+        #[allow(unused_mut)]
+        let mut registry = BackendRegistry::empty();
+
+        #[cfg(feature = "mysql")]
+        crate::mysql_plugin::register(&mut registry);
+
+        #[cfg(feature = "postgres")]
+        crate::postgres_plugin::register(&mut registry);
+
+        #[cfg(feature = "sqlite")]
+        crate::sqlite_plugin::register(&mut registry);
+
+        #[cfg(feature = "files")]
+        crate::file_plugin::register(&mut registry);
+
+        registry
+    }
+}
@@ -0,0 +1,186 @@
+use std::sync::{Arc, Mutex};
+
+use axum::http::StatusCode;
+use common::{
+    data::row::{ColumnValue, Row},
+    error::AppError,
+};
+use r2d2::Pool;
+use r2d2_sqlite::SqliteConnectionManager;
+use regex::Regex;
+use rusqlite::{functions::FunctionFlags, params_from_iter, types::Value, Connection, OpenFlags};
+
+use crate::{
+    config::ConfigRaw,
+    connector::{Connector, RowSink},
+};
+
+#[cfg(feature = "files")]
+use crate::{
+    config::{FileType, SourceType},
+    file_plugin::load_files,
+};
+#[cfg(feature = "files")]
+use uuid::Uuid;
+
+use super::{column_value, sql_value};
+
+type SqlitePool = Pool<SqliteConnectionManager>;
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// SQLite implementation of `BasableConnection`
+#[derive(Default)]
+pub struct SqliteConnector {
+    /// Database connection pool
+    pool: Option<SqlitePool>,
+
+    /// Connection options
+    pub config: ConfigRaw,
+
+    /// Keeps an in-memory database alive for as long as the connector, whatever the pool does
+    /// with its own connections.
+    _memory: Option<Mutex<Connection>>,
+}
+
+impl SqliteConnector {
+    /// Open the SQLite file at [`ConfigRaw::path`], read-only unless [`ConfigRaw::writable`].
+    /// Missing files are an error rather than created.
+    ///
+    /// The path is absolute once resolved in the [`DataDir`](crate::config::DataDir), so it's
+    /// never read as a URI, which could set other options.
+    fn open_file(config: ConfigRaw) -> Result<Self, AppError> {
+        let path = config.build_url()?;
+
+        let mode = if config.writable {
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+        } else {
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+        };
+        let flags = mode | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+
+        // The pool retries failed connections until it times out, so open errors are surfaced first.
+        Connection::open_with_flags(&path, flags)
+            .map_err(|err| AppError::HttpError(StatusCode::BAD_REQUEST, err.to_string()))?;
+
+        let manager = SqliteConnectionManager::file(path)
+            .with_flags(flags)
+            .with_init(register_regexp);
+
+        let pool = Pool::new(manager).map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        Ok(SqliteConnector {
+            pool: Some(pool),
+            config,
+            _memory: None,
+        })
+    }
+
+    /// Load data files into a private in-memory database. Tables are read-only, since changes
+    /// could never be written back to the files.
+    #[cfg(feature = "files")]
+    fn open_files(config: ConfigRaw, file_type: &FileType) -> Result<Self, AppError> {
+        let path = config.build_url()?;
+
+        let uri = format!("file:basable-{}?mode=memory&cache=shared", Uuid::new_v4());
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE
+            | OpenFlags::SQLITE_OPEN_CREATE
+            | OpenFlags::SQLITE_OPEN_URI
+            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+
+        let memory = Connection::open_with_flags(&uri, flags)
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+        load_files(&memory, file_type, &path)?;
+
+        let manager = SqliteConnectionManager::file(uri)
+            .with_flags(flags)
+            .with_init(|conn| {
+                register_regexp(conn)?;
+                conn.pragma_update(None, "query_only", true)
+            });
+
+        let pool = Pool::new(manager).map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        Ok(SqliteConnector {
+            pool: Some(pool),
+            config,
+            _memory: Some(Mutex::new(memory)),
+        })
+    }
+
+    fn pool(&self) -> Result<&SqlitePool, AppError> {
+        self.pool
+            .as_ref()
+            .ok_or_else(|| AppError::ServerError("sqlite connection pool not initialized".to_string()))
+    }
+}
+
+/// SQLite parses `REGEXP` but ships without an implementation, so one is registered on every connection.
+/// Patterns are compiled once per query rather than for every row.
+fn register_regexp(conn: &mut Connection) -> Result<(), rusqlite::Error> {
+    conn.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let re = ctx.get_or_create_aux(0, |pattern| -> Result<_, BoxError> {
+                Ok(Regex::new(pattern.as_str()?)?)
+            })?;
+            let text: Option<String> = ctx.get(1).ok();
+
+            Ok(text.is_some_and(|t| re.is_match(&t)))
+        },
+    )
+}
+
+impl Connector for SqliteConnector {
+    type Row = Row;
+
+    fn new(config: ConfigRaw) -> Result<Self, AppError> {
+        #[cfg(feature = "files")]
+        if let SourceType::File(file_type) = config.get_source()? {
+            return Self::open_files(config, &file_type);
+        }
+
+        Self::open_file(config)
+    }
+
+    fn stream_params(
+        &self,
+        query: &str,
+        params: &[ColumnValue],
+        sink: &mut RowSink<'_, Self::Row>,
+    ) -> Result<(), AppError> {
+        let conn = self
+            .pool()?
+            .get()
+            .map_err(|err| AppError::ServerError(err.to_string()))?;
+
+        let map_err = |err: rusqlite::Error| AppError::ServerError(err.to_string());
+
+        let mut stmt = conn.prepare(query).map_err(map_err)?;
+        let columns: Arc<Vec<String>> = Arc::new(
+            stmt.column_names()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        );
+
+        let params = params.iter().map(sql_value);
+        let mut rows = stmt.query(params_from_iter(params)).map_err(map_err)?;
+
+        while let Some(row) = rows.next().map_err(map_err)? {
+            let values = (0..columns.len())
+                .map(|idx| row.get::<_, Value>(idx).map(column_value))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(map_err)?;
+
+            sink(Row::new(columns.clone(), values))?;
+        }
+
+        Ok(())
+    }
+
+    fn config(&self) -> &ConfigRaw {
+        &self.config
+    }
+}
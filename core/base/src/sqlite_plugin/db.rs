@@ -0,0 +1,179 @@
+use std::{path::Path, sync::Arc};
+
+use common::{
+    data::{
+        row::{ColumnValue, Row},
+        table::{TableSummaries, TableSummary},
+    },
+    error::AppError,
+    DbServerDetails,
+};
+use uuid::Uuid;
+
+use crate::{
+    connector::Connector,
+    db::{DBQueryResult, QuerySqlParser, DB},
+    dialect::{quote_ident, Dialect, SqliteDialect},
+    graphs::sql::SqlGraphs,
+    SharedTable,
+};
+
+use super::{connector::SqliteConnector, table::SqliteTable};
+
+pub struct SqliteDB {
+    pub connector: Arc<SqliteConnector>,
+    pub tables: Vec<SharedTable>,
+    user_id: String,
+    id: Uuid,
+}
+
+impl SqliteDB {
+    pub fn new(connector: Arc<SqliteConnector>, id: Uuid, user_id: String) -> Self {
+        SqliteDB {
+            connector,
+            tables: Vec::new(),
+            user_id,
+            id,
+        }
+    }
+
+    /// Get the [`SqliteConnector`] instance for [`SqliteDB`].
+    pub fn connector(&self) -> &Arc<SqliteConnector> {
+        &self.connector
+    }
+
+    /// Query the file for user tables.
+    fn query_tables(&self) -> DBQueryResult<Row, AppError> {
+        self.exec_query(
+            "
+                SELECT name AS table_name FROM sqlite_master
+                WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+                ORDER BY name
+            ",
+        )
+    }
+
+    /// SQLite keeps no row statistics, so rows are counted.
+    fn query_row_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        let qr = self.exec_query(&format!("SELECT COUNT(*) FROM {}", quote_ident(tb_name)))?;
+        Ok(first_int(&qr) as u32)
+    }
+
+    /// Database size in MB
+    fn size(&self) -> Result<f64, AppError> {
+        let qr = self.exec_query(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+        )?;
+
+        let bytes = first_int(&qr) as f64;
+        Ok((bytes / 1024.0 / 1024.0 * 10.0).round() / 10.0)
+    }
+
+    fn exec_query(&self, query: &str) -> Result<Vec<Row>, AppError> {
+        self.connector.exec_query(query)
+    }
+}
+
+/// Read the first value of a result as an integer, treating anything else as 0.
+pub(super) fn first_int(rows: &[Row]) -> i64 {
+    rows.first()
+        .and_then(|r| r.get_at(0))
+        .and_then(ColumnValue::as_i64)
+        .unwrap_or_default()
+}
+
+impl DB for SqliteDB {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+
+    fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    fn load_tables(&mut self) -> Result<(), AppError> {
+        let tables = self.query_tables()?;
+
+        for t in tables {
+            let name = t.get_string("table_name").unwrap_or_default();
+            let table = SqliteTable::new(name, self.connector.clone());
+            self.tables.push(Arc::new(table));
+        }
+
+        Ok(())
+    }
+
+    fn tables(&self) -> &Vec<SharedTable> {
+        &self.tables
+    }
+
+    fn build_table_list(&self) -> Result<TableSummaries, AppError> {
+        let results = self.query_tables()?;
+        let tables: Vec<TableSummary> = results
+            .iter()
+            .map(|res| {
+                let name = res.get_string("table_name").unwrap_or_default();
+
+                let row_count = self.query_row_count(&name).unwrap_or_default();
+                let col_count = self.query_column_count(&name).unwrap_or_default();
+
+                // SQLite does not keep track of table creation or update time.
+                TableSummary {
+                    name,
+                    col_count,
+                    row_count,
+                    created: None,
+                    updated: None,
+                }
+            })
+            .collect();
+
+        Ok(tables)
+    }
+
+    fn query_column_count(&self, tb_name: &str) -> Result<u32, AppError> {
+        let qr = self.connector.exec_params(
+            "SELECT COUNT(*) FROM pragma_table_info(?)",
+            &[tb_name.into()],
+        )?;
+        Ok(first_int(&qr) as u32)
+    }
+
+    fn get_table(&self, name: &str) -> Option<&SharedTable> {
+        self.tables.iter().find(|t| t.name() == name)
+    }
+
+    fn details(&self) -> Result<DbServerDetails, AppError> {
+        let rows = self.exec_query("SELECT sqlite_version() AS version")?;
+        let version = rows
+            .first()
+            .and_then(|r| r.get_string("version"))
+            .unwrap_or_default();
+
+        let size = self.size()?;
+
+        // SQLite runs embedded, so the server is this process. Only the file name is shown, as
+        // the path of the data directory on the server is kept private.
+        let file = self.connector.config.path.as_deref().map(Path::new);
+        let file = file.and_then(Path::file_name).map(|name| name.to_string_lossy());
+
+        Ok(DbServerDetails {
+            version,
+            os: std::env::consts::OS.to_string(),
+            comment: Some(file.unwrap_or_default().into_owned()),
+            db_size: size,
+        })
+    }
+}
+
+impl QuerySqlParser for SqliteDB {
+    fn dialect(&self) -> &dyn Dialect {
+        &SqliteDialect
+    }
+}
+
+impl SqlGraphs for SqliteDB {
+    fn sql_connector(&self) -> &dyn Connector<Row = Row> {
+        self.connector.as_ref()
+    }
+}
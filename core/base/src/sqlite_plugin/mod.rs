@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use common::data::row::ColumnValue;
+use rusqlite::types::Value;
+
+use crate::{connector::Connector, registry::BackendRegistry};
+
+use self::{connector::SqliteConnector, db::SqliteDB};
+
+pub mod db;
+pub mod connector;
+pub mod table;
+
+/// Register the SQLite backend for `database` sources named `sqlite`.
+pub fn register(registry: &mut BackendRegistry) {
+    registry.register("database", "sqlite", |config, id, user_id| {
+        let conn = SqliteConnector::new(config.clone())?;
+        Ok(Box::new(SqliteDB::new(Arc::new(conn), id, user_id)))
+    });
+}
+
+/// Convert a SQLite [`Value`] into a [`ColumnValue`].
+pub(crate) fn column_value(value: Value) -> ColumnValue {
+    match value {
+        Value::Null => ColumnValue::NULL,
+        Value::Integer(v) => ColumnValue::Int(v),
+        Value::Real(v) => ColumnValue::Double(v),
+        Value::Text(v) => ColumnValue::Text(v),
+        Value::Blob(v) => ColumnValue::Text(String::from_utf8_lossy(&v).into_owned()),
+    }
+}
+
+/// Convert a [`ColumnValue`] into a SQLite [`Value`], to be bound to a query.
+pub(crate) fn sql_value(value: &ColumnValue) -> Value {
+    match value {
+        ColumnValue::NULL => Value::Null,
+        ColumnValue::Text(v) | ColumnValue::Decimal(v) => Value::Text(v.clone()),
+        ColumnValue::Int(v) => Value::Integer(*v),
+        ColumnValue::UInt(v) => i64::try_from(*v).map_or(Value::Real(*v as f64), Value::Integer),
+        ColumnValue::Float(v) => Value::Real(*v as f64),
+        ColumnValue::Double(v) => Value::Real(*v),
+        v @ (ColumnValue::Date(..) | ColumnValue::Time(..)) => Value::Text(v.to_string()),
+    }
+}
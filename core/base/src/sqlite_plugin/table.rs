@@ -0,0 +1,365 @@
+use std::{collections::HashMap, sync::Arc};
+
+use common::{
+    data::{
+        columns::{Column, ColumnList},
+        row::ColumnValue,
+        table::{TableConfig, TableExportOpts, TableQueryOpts, UpdateTableData},
+    },
+    error::AppError,
+    query::{filter::FilterChain, BasableQuery, QueryCommand, QueryExpr},
+};
+
+use crate::{
+    connector::{Connector, RowSink},
+    db::{Placeholder, QueryParams, SqlQuery},
+    dialect::quote_ident,
+    table::{process_exports, Table, TableCRUD},
+    SharedDB,
+};
+
+use super::{connector::SqliteConnector, db::first_int};
+
+pub struct SqliteTable {
+    pub name: String,
+    pub connector: Arc<SqliteConnector>,
+}
+
+impl SqliteTable {
+    /// Create a new [`SqliteTable`] and assign the given [`SqliteConnector`].
+    pub fn new(name: String, conn: Arc<SqliteConnector>) -> Self {
+        SqliteTable {
+            name,
+            connector: conn,
+        }
+    }
+
+    /// Get the table's [`SqliteConnector`].
+    pub fn connector(&self) -> &Arc<SqliteConnector> {
+        &self.connector
+    }
+
+    fn selected_columns(&self, columns: &Option<Vec<String>>) -> Vec<String> {
+        columns
+            .clone()
+            .filter(|cols| !cols.is_empty())
+            .unwrap_or_else(|| match self.query_columns() {
+                Ok(cs) => cs.iter().map(|col| col.name.clone()).collect(),
+                Err(err) => {
+                    tracing::error!("error reading db column: {err}");
+                    vec![]
+                }
+            })
+    }
+}
+
+impl Table for SqliteTable {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn query_columns(&self) -> Result<ColumnList, AppError> {
+        let query = "
+            SELECT
+                c.name AS column_name,
+                c.type AS column_type,
+                NOT c.\"notnull\" AS is_nullable,
+                c.dflt_value AS column_default,
+                EXISTS (
+                    SELECT 1 FROM pragma_index_list(?1) il
+                    WHERE il.\"unique\" AND (
+                        SELECT COUNT(*) FROM pragma_index_info(il.name)
+                    ) = 1 AND (
+                        SELECT ii.name FROM pragma_index_info(il.name) ii
+                    ) = c.name
+                ) AS is_unique,
+                c.pk > 0 AS is_primary
+            FROM
+                pragma_table_info(?1) c
+            ORDER BY c.cid
+        ";
+
+        let result = self.connector.exec_params(query, &[self.name.as_str().into()])?;
+
+        let cols: ColumnList = result
+            .iter()
+            .map(|r| {
+                let flag = |col: &str| r.get(col).and_then(ColumnValue::as_bool).unwrap_or_default();
+
+                Column {
+                    name: r.get_string("column_name").unwrap_or_default(),
+                    col_type: r.get_string("column_type").unwrap_or_default(),
+                    default_value: r.get_string("column_default"),
+                    nullable: flag("is_nullable"),
+                    unique: flag("is_unique"),
+                    primary: flag("is_primary"),
+                }
+            })
+            .collect();
+
+        Ok(cols)
+    }
+
+    fn init_config(&self) -> Option<TableConfig> {
+        let cols = self.query_columns().ok()?;
+
+        // Tables without a declared key can still be addressed by their rowid, but that
+        // isn't a selectable column, so only declared keys are used.
+        let pk = cols
+            .iter()
+            .find(|c| c.primary)
+            .or_else(|| cols.iter().find(|c| c.unique))
+            .map(|pk| pk.name.clone());
+
+        Some(TableConfig {
+            pk_column: pk,
+            name: self.name.clone(),
+            label: self.name.clone(),
+            ..TableConfig::default()
+        })
+    }
+}
+
+impl TableCRUD for SqliteTable {
+    fn stream_data(
+        &self,
+        opts: TableQueryOpts,
+        db: &SharedDB,
+        sink: &mut RowSink<'_, HashMap<String, ColumnValue>>,
+    ) -> Result<(), AppError> {
+        self.validate_columns(&opts.referenced_columns())?;
+        let cols = self.selected_columns(&opts.columns);
+
+        let query = TableQueryOpts { table: self.name.clone(), ..opts }.try_into()?;
+        let query = db.generate_sql(query)?;
+
+        self.connector.stream_sql(&query, &mut |r| {
+            let mut map = r.into_map();
+            map.retain(|col, _| cols.contains(col));
+            sink(map)
+        })
+    }
+
+    fn query_result_count(&self, opts: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError> {
+        self.validate_columns(&opts.referenced_columns())?;
+
+        let query = BasableQuery {
+            table: self.name.clone(),
+            command: QueryCommand::SelectData(Some(vec![QueryExpr::Sql("COUNT(*)".to_string())])),
+            search_opts: opts.search_opts,
+            filters: opts
+                .filters
+                .map_or(FilterChain::empty(), FilterChain::prefill),
+            ..Default::default()
+        };
+
+        let query = db.generate_sql(query)?;
+        let rows = self.connector.exec_sql(&query)?;
+
+        let count = first_int(&rows);
+
+        Ok(count as usize)
+    }
+
+    fn insert_data(&self, input: HashMap<String, String>) -> Result<(), AppError> {
+        let keys: Vec<&str> = input.keys().map(String::as_str).collect();
+        self.validate_columns(&keys)?;
+
+        let mut params = QueryParams::new(Placeholder::Question);
+        let mut cols = Vec::with_capacity(input.len());
+        let mut values = Vec::with_capacity(input.len());
+
+        for (k, v) in &input {
+            cols.push(quote_ident(k));
+            values.push(params.bind(v.as_str()));
+        }
+
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_ident(&self.name),
+            cols.join(", "),
+            values.join(", ")
+        );
+        self.connector.exec_sql(&SqlQuery::new(query, params))?;
+
+        Ok(())
+    }
+
+    fn update_data(&self, options: UpdateTableData) -> Result<(), AppError> {
+        let UpdateTableData {
+            unique_key,
+            columns,
+            unique_values,
+            input,
+        } = options;
+
+        let mut names: Vec<&str> = columns.iter().map(String::as_str).collect();
+        names.push(&unique_key);
+        self.validate_columns(&names)?;
+
+        // SQLite applies column affinity on write, so the text values need no cast.
+        let key = format!("CAST({} AS TEXT)", quote_ident(&unique_key));
+        let mut params = QueryParams::new(Placeholder::Question);
+
+        let mut cases = vec![];
+        for col in &columns {
+            let col_ident = quote_ident(col);
+            let mut q = format!("{col_ident} = CASE {key} \n");
+
+            for (index, uv) in unique_values.iter().enumerate() {
+                if let Some(val) = input.get(index).and_then(|values| values.get(col)) {
+                    let uv = params.bind(uv.as_str());
+                    q.push_str(&format!("WHEN {uv} THEN {} \n", params.bind(val.as_str())));
+                }
+            }
+
+            q.push_str(&format!("ELSE {col_ident} \n END"));
+            cases.push(q);
+        }
+
+        let query = format!(
+            "UPDATE {} \n SET {} \n WHERE {key} IN ({})",
+            quote_ident(&self.name),
+            cases.join(", \n"),
+            params.bind_list(&unique_values),
+        );
+
+        self.connector.exec_sql(&SqlQuery::new(query, params))?;
+
+        Ok(())
+    }
+
+    fn delete_data(&self, col: String, value: String) -> Result<(), AppError> {
+        self.validate_columns(&[&col])?;
+
+        let query = format!(
+            "DELETE FROM {} WHERE CAST({} AS TEXT) = ?",
+            quote_ident(&self.name),
+            quote_ident(&col),
+        );
+        self.connector.exec_params(&query, &[value.into()])?;
+
+        Ok(())
+    }
+
+    fn export(&self, opts: TableExportOpts, db: &SharedDB) -> Result<String, AppError> {
+        let TableExportOpts {
+            query_opts,
+            format,
+            trim,
+        } = opts;
+
+        self.validate_columns(&query_opts.referenced_columns())?;
+        let cols = self.selected_columns(&query_opts.columns);
+
+        let selection = if cols.is_empty() {
+            None
+        } else {
+            Some(cols.iter().map(|col| col.as_str().into()).collect())
+        };
+
+        let filters = query_opts
+            .filters
+            .map_or(FilterChain::empty(), FilterChain::prefill);
+
+        let query = BasableQuery {
+            table: self.name.clone(),
+            command: QueryCommand::SelectData(selection),
+            filters,
+            offset: trim.as_ref().map(|trim| trim.offset),
+            row_count: trim.map(|trim| trim.count),
+            ..Default::default()
+        };
+
+        let query = db.generate_sql(query)?;
+        let rows = self.connector.exec_sql(&query)?;
+
+        let content = process_exports(format, cols, rows);
+
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs};
+
+    use common::data::{row::ColumnValue, table::TableQueryOpts};
+    use rusqlite::Connection;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use crate::{
+        config::{ConfigRaw, DataDir},
+        registry::BackendRegistry,
+    };
+
+    #[test]
+    fn test_sqlite_table() {
+        let root = std::env::temp_dir().join(format!("basable-{}", Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        Connection::open(root.join("shop.db"))
+            .unwrap()
+            .execute_batch(
+                "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer TEXT, total REAL);
+                INSERT INTO orders (customer, total) VALUES ('ada', 20.5), ('bob', 10);",
+            )
+            .unwrap();
+
+        let mut registry = BackendRegistry::default();
+        registry.set_data_dir(DataDir::new(&root, &[]).unwrap());
+        let config = ConfigRaw {
+            source: "sqlite".to_string(),
+            path: Some("shop.db".to_string()),
+            writable: true,
+            ..Default::default()
+        };
+        let db = registry.create(&config, Uuid::new_v4(), "user".to_string()).unwrap();
+        let table = db.get_table("orders").unwrap();
+
+        let opts = |filter: serde_json::Value| -> TableQueryOpts {
+            serde_json::from_value(json!({
+                "table": "orders",
+                "offset": 0,
+                "row_count": 10,
+                "filters": [filter],
+                "columns": null,
+                "order_by": null,
+                "search_opts": null,
+            }))
+            .unwrap()
+        };
+        let filter = |column: &str, value: &str| {
+            json!({ "combinator": "BASE", "column": column, "expression": { "Eq": value } })
+        };
+
+        let rows = table.query_data(opts(filter("customer", "ada")), &db).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].get("total"), Some(&ColumnValue::Double(20.5)));
+
+        // Values are bound, so they're never read as SQL.
+        let rows = table.query_data(opts(filter("customer", "x' OR '1'='1")), &db).unwrap();
+        assert!(rows.is_empty());
+
+        // Patterns are matched by the `regexp` function registered on every connection.
+        let regex = |pattern: &str| {
+            json!({ "combinator": "BASE", "column": "customer", "expression": { "Regex": pattern } })
+        };
+        assert_eq!(table.query_data(opts(regex("^(a|c)")), &db).unwrap().len(), 1);
+        assert!(table.query_data(opts(regex("(")), &db).is_err());
+
+        // Only the file name is shown, not where the data directory is on the server.
+        assert_eq!(db.details().unwrap().comment.as_deref(), Some("shop.db"));
+
+        // Identifiers must be columns of the table.
+        assert!(table.query_data(opts(filter("customer\" OR 1=1 --", "x")), &db).is_err());
+        let input = HashMap::from([("secret".to_string(), "1".to_string())]);
+        assert!(table.insert_data(input).is_err());
+
+        let input = HashMap::from([("customer".to_string(), "cy".to_string())]);
+        table.insert_data(input).unwrap();
+        assert_eq!(table.query_result_count(opts(filter("customer", "cy")), &db).unwrap(), 1);
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
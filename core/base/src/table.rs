@@ -1,46 +1,43 @@
 use common::{
-    data::{columns::ColumnList, table::{DataQueryResult, TableConfig, TableExportOpts, TableQueryOpts, UpdateTableData}},
+    data::{columns::ColumnList, row::{ColumnValue, Row}, table::{DataQueryResult, TableConfig, TableExportFormat, TableExportOpts, TableQueryOpts, UpdateTableData}},
     error::AppError,
 };
+use axum::http::StatusCode;
 use std::collections::HashMap;
 
-use crate::mysql_plugin::ColumnValue;
-
-use super::{ConnectorType, SharedDB};
+use super::{connector::RowSink, SharedDB};
 
+/// An abstraction of a database table.
+///
+/// Backends construct their tables in [`DB::load_tables`](crate::db::DB::load_tables), handing each
+/// table a copy of the connector it should use for its own queries.
 pub trait Table: TableCRUD + Sync + Send {
-    type Row;
-
-    /// Create a new [`Table`] and assign the given [`ConnectorType`].
-    ///
-    /// It returns the new [`Table`]. And if a [`TableConfig`] is created for the table,
-    /// then the config is also returned. It is up to the caller to save or send the config for the table.
-    ///
-    /// # Example:
-    /// ```
-    /// let (table, config) = Table::new("table_name".to_string(), conn);
-    /// // config is Option<TableConfig>
-    /// ```
-    ///
-    /// This call initializes [`TableConfig`] for the table if certain query conditions are true for the table.
-    /// For example if the table has a column named id, a primary key or a unique column, we automatically
-    /// set the `pk` field of the table to any of the column.
-    fn new(name: String, conn: ConnectorType) -> Self
-    where
-        Self: Sized;
-
     /// [Table]'s name
     fn name(&self) -> &str;
 
-    /// Get the table's [`ConnectorType`].
-    fn connector(&self) -> &ConnectorType;
-
     /// Retrieve available columns for the table and build a [`ColumnList`].
     fn query_columns(&self) -> Result<ColumnList, AppError>;
 
     /// Create table's initial [`TableConfig`] if possible. Caller is responsible for
     /// saving the configuration in persistent DB.
+    ///
+    /// For example if the table has a column named id, a primary key or a unique column, we automatically
+    /// set the `pk` field of the table to any of the column.
     fn init_config(&self) -> Option<TableConfig>;
+
+    /// Make sure each of `columns` is a column of the table, before they are used as identifiers
+    /// in a query.
+    fn validate_columns(&self, columns: &[&str]) -> Result<(), AppError> {
+        let known = self.query_columns()?;
+
+        match columns.iter().find(|col| !known.iter().any(|c| c.name == **col)) {
+            Some(col) => Err(AppError::HttpError(
+                StatusCode::BAD_REQUEST,
+                format!("Unknown column '{col}' in table '{}'", self.name()),
+            )),
+            None => Ok(()),
+        }
+    }
 }
 
 pub trait TableCRUD {
@@ -52,7 +49,24 @@ pub trait TableCRUD {
         &self,
         filter: TableQueryOpts,
         db: &SharedDB,
-    ) -> DataQueryResult<ColumnValue, AppError>;
+    ) -> DataQueryResult<ColumnValue, AppError> {
+        let mut data = Vec::new();
+        self.stream_data(filter, db, &mut |row| {
+            data.push(row);
+            Ok(())
+        })?;
+
+        Ok(data)
+    }
+
+    /// Retrieve data from table based on query `filter`, handing each row to `sink` as soon as
+    /// it's read. Use this over [`TableCRUD::query_data`] when results may not fit in memory.
+    fn stream_data(
+        &self,
+        filter: TableQueryOpts,
+        db: &SharedDB,
+        sink: &mut RowSink<'_, HashMap<String, ColumnValue>>,
+    ) -> Result<(), AppError>;
 
     /// Get total size of returnable data based on [TableQueryOpts].
     fn query_result_count(&self, filter: TableQueryOpts, db: &SharedDB) -> Result<usize, AppError>;
@@ -62,4 +76,79 @@ pub trait TableCRUD {
     fn delete_data(&self, col: String, value: String) -> Result<(), AppError>;
 
     fn export(&self, opts: TableExportOpts, db: &SharedDB) -> Result<String, AppError>;
-}
\ No newline at end of file
+}
+
+/// Render exported `rows` in the given [`TableExportFormat`].
+pub fn process_exports(format: TableExportFormat, columns: Vec<String>, rows: Vec<Row>) -> String {
+    let get = |row: &Row, col: &str| row.get_string(col).unwrap_or_default();
+    match format {
+        TableExportFormat::CSV
+        | TableExportFormat::PSV
+        | TableExportFormat::TSV
+        | TableExportFormat::TEXT => {
+            let delimiter = format.field_delimiter().unwrap_or_default();
+            let headers = columns.join(&delimiter);
+            let row_list: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    let mut row_data = Vec::with_capacity(columns.len());
+                    for col in &columns {
+                        row_data.push(get(row, col));
+                    }
+
+                    row_data.join(&delimiter)
+                })
+                .collect();
+
+            let body = row_list.join("\n");
+            format!("{headers} \n {body}")
+        }
+        TableExportFormat::JSON => {
+            let row_list: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    let values: Vec<String> = columns
+                        .iter()
+                        .map(|col| {
+                            let val = get(row, col);
+                            format!("\t\"{}\":\"{}\"", col, val)
+                        })
+                        .collect();
+
+                    format!("{{\n\t{}\n\t}}", values.join(",\n\t"))
+                })
+                .collect();
+
+            format!("[\n\t{}\n]", row_list.join(",\n\t"))
+        }
+        TableExportFormat::HTML => {
+            let col_list: Vec<String> = columns
+                .iter()
+                .map(|col| format!("<th>{col}</th>"))
+                .collect();
+            let header = format!(
+                "<thead>\n\t\t<tr>\n\t\t\t{}\n\t\t</tr>\n\t</thead>",
+                col_list.join("\n\t\t\t")
+            );
+
+            let row_list: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    let td_list: Vec<String> = columns
+                        .iter()
+                        .map(|col| {
+                            let val = get(row, col);
+                            format!("<td>{val}</td>")
+                        })
+                        .collect();
+
+                    format!("<tr>\n\t\t\t{}\n\t\t</tr>", td_list.join("\n\t\t\t"))
+                })
+                .collect();
+            let body = format!("<tbody>\n\t\t{}\n\t</tbody>", row_list.join("\n\t\t"));
+
+            format!("<table>\n\t{header}\n\t{body}\n<table>")
+        }
+        _ => "".to_string(),
+    }
+}
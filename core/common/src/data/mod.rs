@@ -1,2 +1,3 @@
 pub mod table;
-pub mod columns;
\ No newline at end of file
+pub mod columns;
+pub mod row;
@@ -0,0 +1,165 @@
+use std::{collections::HashMap, fmt::Display, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+/// Client side representation of a column value, whichever backend it was read from.
+#[derive(Clone, Debug, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub enum ColumnValue {
+    NULL,
+    Text(String),
+    Int(i64),
+    UInt(u64),
+    Float(f32),
+    Double(f64),
+    /// An exact number, such as a `NUMERIC` or `DECIMAL`, kept as its text so no precision is
+    /// lost. Only ever holds a plain decimal number, such as `-1234.50`.
+    Decimal(String),
+    /// year, month, day, hour, minutes, seconds, micro seconds
+    Date(u16, u8, u8, u8, u8, u8, u32),
+    /// is negative, days, hours, minutes, seconds, micro seconds
+    Time(bool, u32, u8, u8, u8, u32),
+}
+
+impl ColumnValue {
+    /// A [`ColumnValue::Decimal`] of `text` if it's a plain decimal number, or else
+    /// [`ColumnValue::Text`].
+    pub fn decimal(text: String) -> Self {
+        let digits = text.strip_prefix('-').unwrap_or(&text);
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, "0"));
+        let is_decimal = [int, frac]
+            .iter()
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+
+        match is_decimal {
+            true => ColumnValue::Decimal(text),
+            false => ColumnValue::Text(text),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, ColumnValue::NULL)
+    }
+
+    /// The value as text. Only [`ColumnValue::Text`] values are returned.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ColumnValue::Text(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The value as an integer. Numeric text is parsed, so values sent over text protocols
+    /// (such as MySQL `DECIMAL`s) can still be read.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ColumnValue::Int(v) => Some(*v),
+            ColumnValue::UInt(v) => i64::try_from(*v).ok(),
+            ColumnValue::Text(v) | ColumnValue::Decimal(v) => v.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The value as a float. Numeric text is parsed, as with [`ColumnValue::as_i64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ColumnValue::Int(v) => Some(*v as f64),
+            ColumnValue::UInt(v) => Some(*v as f64),
+            ColumnValue::Float(v) => Some(*v as f64),
+            ColumnValue::Double(v) => Some(*v),
+            ColumnValue::Text(v) | ColumnValue::Decimal(v) => v.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The value as a boolean. Backends without a boolean type return 0 or 1, and MySQL's
+    /// `information_schema` uses `YES` and `NO`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ColumnValue::Text(v) => match v.as_str() {
+                "YES" | "true" | "t" => Some(true),
+                "NO" | "false" | "f" => Some(false),
+                v => v.parse::<i64>().ok().map(|v| v != 0),
+            },
+            v => v.as_i64().map(|v| v != 0),
+        }
+    }
+}
+
+impl From<String> for ColumnValue {
+    fn from(value: String) -> Self {
+        ColumnValue::Text(value)
+    }
+}
+
+impl From<&str> for ColumnValue {
+    fn from(value: &str) -> Self {
+        ColumnValue::Text(value.to_string())
+    }
+}
+
+/// Text representation of the value, as used in exports. `NULL` is written as an empty string.
+impl Display for ColumnValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnValue::NULL => Ok(()),
+            ColumnValue::Text(v) => write!(f, "{v}"),
+            ColumnValue::Int(v) => write!(f, "{v}"),
+            ColumnValue::UInt(v) => write!(f, "{v}"),
+            ColumnValue::Float(v) => write!(f, "{v}"),
+            ColumnValue::Double(v) => write!(f, "{v}"),
+            ColumnValue::Decimal(v) => write!(f, "{v}"),
+            ColumnValue::Date(y, m, d, h, min, s, _) => {
+                write!(f, "{y:04}-{m:02}-{d:02} {h:02}:{min:02}:{s:02}")
+            }
+            ColumnValue::Time(neg, d, h, min, s, _) => {
+                let sign = if *neg { "-" } else { "" };
+                write!(f, "{sign}{:02}:{min:02}:{s:02}", *d * 24 + *h as u32)
+            }
+        }
+    }
+}
+
+/// A row of query results. Every backend converts its own rows into this, so the rest of
+/// the app never depends on a database driver.
+#[derive(Clone)]
+pub struct Row {
+    columns: Arc<Vec<String>>,
+    values: Vec<ColumnValue>,
+}
+
+impl Row {
+    /// Create a new row. Rows from the same result should share `columns`.
+    pub fn new(columns: Arc<Vec<String>>, values: Vec<ColumnValue>) -> Self {
+        Row { columns, values }
+    }
+
+    /// Names of the columns in the row.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    pub fn values(&self) -> &[ColumnValue] {
+        &self.values
+    }
+
+    /// Get the value of column `col`.
+    pub fn get(&self, col: &str) -> Option<&ColumnValue> {
+        let idx = self.columns.iter().position(|c| c == col)?;
+        self.values.get(idx)
+    }
+
+    /// Get the value at `idx`.
+    pub fn get_at(&self, idx: usize) -> Option<&ColumnValue> {
+        self.values.get(idx)
+    }
+
+    /// Get the text value of column `col`. Other values are formatted as text.
+    pub fn get_string(&self, col: &str) -> Option<String> {
+        self.get(col).filter(|v| !v.is_null()).map(|v| v.to_string())
+    }
+
+    /// Convert the row into a map of column names to values.
+    pub fn into_map(self) -> HashMap<String, ColumnValue> {
+        self.columns.iter().cloned().zip(self.values).collect()
+    }
+}
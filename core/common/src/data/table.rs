@@ -3,7 +3,7 @@ use std::collections::HashMap;
 
 use crate::{
     query::{
-        filter::{Filter, FilterChain},
+        filter::{FilterChain, FilterNode},
         BasableQuery, QueryCommand, QueryOrder,
     },
     error::AppError,
@@ -133,7 +133,7 @@ impl Default for TableConfig {
 
 #[derive(Deserialize)]
 pub struct TableQueryOpts {
-    /// The table we're querying
+    /// The table we're querying. Tables ignore it, as they only ever query themselves.
     pub table: String,
 
     /// Query offset
@@ -142,8 +142,8 @@ pub struct TableQueryOpts {
     /// Query row count
     pub row_count: usize,
 
-    /// Query filters
-    pub filters: Option<Vec<Filter>>,
+    /// Query filters, which may be nested in groups.
+    pub filters: Option<Vec<FilterNode>>,
 
     /// The columns(s) you want selected in the query. If set to `None` all fields
     /// will be selected.
@@ -157,6 +157,29 @@ impl TableQueryOpts {
     pub fn is_search_mode(&self) -> bool {
         self.search_opts.is_some()
     }
+
+    /// Names of the columns the query refers to, in its selection, filters, order and search.
+    pub fn referenced_columns(&self) -> Vec<&str> {
+        let mut columns: Vec<&str> = Vec::new();
+
+        if let Some(cols) = &self.columns {
+            columns.extend(cols.iter().map(String::as_str));
+        }
+
+        if let Some(filters) = &self.filters {
+            columns.extend(filters.iter().flat_map(FilterNode::columns));
+        }
+
+        if let Some(order) = &self.order_by {
+            columns.push(order.column());
+        }
+
+        if let Some(opts) = &self.search_opts {
+            columns.extend(opts.search_cols.iter().map(String::as_str));
+        }
+
+        columns
+    }
 }
 
 impl TryFrom<TableQueryOpts> for BasableQuery {
@@ -173,7 +196,8 @@ impl TryFrom<TableQueryOpts> for BasableQuery {
             search_opts,
         } = opts;
 
-        let operation = QueryCommand::SelectData(columns);
+        let operation =
+            QueryCommand::SelectData(columns.map(|cols| cols.into_iter().map(Into::into).collect()));
         let filter_chain = filters.map_or(FilterChain::empty(), |filters| {
             FilterChain::prefill(filters)
         });
@@ -193,7 +217,7 @@ impl TryFrom<TableQueryOpts> for BasableQuery {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct TableSearchOpts {
     pub search_cols: Vec<String>,
     pub query: String,
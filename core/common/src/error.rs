@@ -30,6 +30,9 @@ impl Display for AppError {
     }
 }
 
+impl std::error::Error for AppError {}
+
+#[cfg(feature = "mysql")]
 impl From<mysql::Error> for AppError {
     fn from(value: mysql::Error) -> Self {
         AppError::ServerError(value.to_string())
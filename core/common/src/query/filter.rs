@@ -1,20 +1,8 @@
-use std::fmt::Display;
-
 use serde::{Deserialize, Serialize};
 
-fn escape_special_characters(input: &str) -> String {
-    input
-        .replace('\\', "\\\\") // Escape backslashes
-        .replace('"', "\\\"") // Escape double quotes
-        .replace('\'', "\\\'") // Escape single quotes
-        .replace('\n', "\\n") // Escape newline
-        .replace('\t', "\\t") // Escape tab
-        .replace('\r', "\\r") // Escape carriage return
-        .replace('&', "\\&") // Escape ampersand
-        .replace('_', "\\_") // Escape underscore
-}
+use crate::data::row::ColumnValue;
 
-#[derive(Deserialize, Serialize, Default)]
+#[derive(Clone, Deserialize, Serialize, Default)]
 pub enum FilterExpression {
     Eq(String),
     NotEq(String),
@@ -35,77 +23,90 @@ pub enum FilterExpression {
     Includes(Vec<String>),
     NotInclude(Vec<String>),
 
+    /// Like [`FilterExpression::Includes`], with values bound with the type they were read with,
+    /// rather than as text. It's only built by Basable, from values of query results, and never
+    /// read from requests.
+    #[serde(skip)]
+    IncludesValues(Vec<ColumnValue>),
+
+    /// Like [`FilterExpression::NotInclude`], with typed values as for
+    /// [`FilterExpression::IncludesValues`].
+    #[serde(skip)]
+    NotIncludeValues(Vec<ColumnValue>),
+
     #[default]
     Null,
 
     NotNull,
 }
 
-impl Display for FilterExpression {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let op = match self {
-            FilterExpression::Eq(v) => format!("= '{}'", escape_special_characters(v)),
-            FilterExpression::NotEq(v) => format!("!= '{}'", escape_special_characters(v)),
-            FilterExpression::Gt(v) => format!("> '{}'", escape_special_characters(v)),
-            FilterExpression::Lt(v) => format!("< '{}'", escape_special_characters(v)),
-            FilterExpression::Gte(v) => format!(">= '{}'", escape_special_characters(v)),
-            FilterExpression::Lte(v) => format!("<= '{}'", escape_special_characters(v)),
-            FilterExpression::Contains(v) => format!("LIKE '%{}%'", escape_special_characters(v)),
-            FilterExpression::NotContains(v) => format!("NOT REGEXP '%{}%'", escape_special_characters(v)),
-            // FilterExpression::Like(v) => format!("LIKE '{}%'", escape_special_characters(v)),
-            // FilterExpression::NotLike(v) => format!("NOT LIKE '{}%'", escape_special_characters(v)),
-            // FilterExpression::LikeSingle(v) => format!("LIKE '_{}%'", escape_special_characters(v)),
-            // FilterExpression::NotLikeSingle(v) => format!("NOT LIKE '_{}%'", escape_special_characters(v)),
-            FilterExpression::Regex(v) => format!("REGEXP '{}'", escape_special_characters(v)),
-            FilterExpression::NotRegex(v) => format!("NOT REGEXP '{}'", escape_special_characters(v)),
-            FilterExpression::Btw(start, end) => format!("BETWEEN ('{}' AND '{}')", escape_special_characters(start), escape_special_characters(end)),
-            FilterExpression::NotBtw(start, end) => format!("NOT BETWEEN ('{}' AND '{}')", escape_special_characters(start), escape_special_characters(end)),
-            FilterExpression::Includes(values) => {
-                let v: Vec<String> = values.iter().map(|v| escape_special_characters(v)).collect();
-                let v = v.join(", ");
-
-                format!("IN ({v})")
-            }
-            FilterExpression::NotInclude(values) => {
-                let v: Vec<String> = values.iter().map(|v| escape_special_characters(v)).collect();
-                let v = v.join(", ");
-
-                format!("NOT IN ({v})")
-            }
-            FilterExpression::Null => "IS NULL".to_string(),
-            FilterExpression::NotNull => "IS NOT NULL".to_string(),
-        };
-
-        write!(f, "{}", op)
-    }
-}
-
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub enum FilterCombinator {
     BASE, AND, OR
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Filter {
     pub combinator: FilterCombinator,
     pub column: String,
-    pub expression: FilterExpression
+    pub expression: FilterExpression,
+
+    /// Alias of the joined table `column` belongs to. It's only set by Basable, when joining
+    /// tables, and never read from requests.
+    #[serde(skip)]
+    pub table: Option<String>,
 }
 
-impl Display for Filter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let comb = match self.combinator {
-            FilterCombinator::AND => "AND ",
-            FilterCombinator::OR => "OR ",
-            FilterCombinator::BASE => ""
-        };
+/// Filters in parentheses, such as `(b = 2 OR c = 3)` in `a = 1 AND (b = 2 OR c = 3)`. Filters
+/// of the group are combined with each other by their own combinators, and the group with the
+/// filters before it by `combinator`.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct FilterGroup {
+    pub combinator: FilterCombinator,
+    pub filters: Vec<FilterNode>,
+}
 
-        write!(f, "{comb} `{}` {}", self.column, self.expression)
+/// A node of a filter tree: a single [`Filter`], or a [`FilterGroup`] of nodes. Either is
+/// accepted as JSON, so lists of plain filters are still valid trees.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FilterNode {
+    Filter(Filter),
+    Group(FilterGroup),
+}
+
+impl FilterNode {
+    pub fn combinator(&self) -> &FilterCombinator {
+        match self {
+            FilterNode::Filter(filter) => &filter.combinator,
+            FilterNode::Group(group) => &group.combinator,
+        }
+    }
+
+    /// Names of the columns filtered on, in this node and all nodes under it.
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            FilterNode::Filter(filter) => vec![filter.column.as_str()],
+            FilterNode::Group(group) => group.filters.iter().flat_map(FilterNode::columns).collect(),
+        }
     }
 }
 
-#[derive(Default)]
-pub struct FilterChain(Vec<Filter>);
+impl From<Filter> for FilterNode {
+    fn from(filter: Filter) -> Self {
+        FilterNode::Filter(filter)
+    }
+}
+
+impl From<FilterGroup> for FilterNode {
+    fn from(group: FilterGroup) -> Self {
+        FilterNode::Group(group)
+    }
+}
+
+/// The conditions of a query, as a tree of [`FilterNode`]s.
+#[derive(Clone, Default)]
+pub struct FilterChain(Vec<FilterNode>);
 impl FilterChain {
     pub fn new() -> Self {
         FilterChain(Vec::new())
@@ -115,19 +116,29 @@ impl FilterChain {
         FilterChain(Vec::with_capacity(0))
     }
     
-    pub fn prefill(filters: Vec<Filter>) -> FilterChain {
+    pub fn prefill(filters: Vec<FilterNode>) -> FilterChain {
         FilterChain(filters)
     }
 
-    pub fn add_one(&mut self, filter: Filter) {
-        self.0.push(filter);
+    pub fn add_one(&mut self, filter: impl Into<FilterNode>) {
+        self.0.push(filter.into());
+    }
+
+    /// Add `filters` in a group, `AND`'ed with the filters already in the chain.
+    pub fn add_group(&mut self, filters: Vec<FilterNode>) {
+        if !filters.is_empty() {
+            self.add_one(FilterGroup {
+                combinator: FilterCombinator::AND,
+                filters,
+            });
+        }
     }
 
     // pub fn add_multiple(&mut self, filters: Vec<Filter>) {
     //     filters.iter().for_each(|f| self.0.push(f.clone()));
     // }
 
-    pub fn all(&self) -> &Vec<Filter> {
+    pub fn all(&self) -> &Vec<FilterNode> {
         &self.0
     }
 
@@ -140,25 +151,6 @@ impl FilterChain {
     }
 }
 
-impl Display for FilterChain {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let values = &self.0;
-
-        if !values.is_empty() {
-            if let Some(first) = values.get(0) {
-                if !matches!(&first.combinator, &FilterCombinator::BASE) {
-                    return Err(std::fmt::Error);
-                }
-            }
-        }
-
-        let values: Vec<String> = values.iter().map(|f| f.to_string()).collect();
-        let values = values.join(",");
-
-        write!(f, "{values}")
-    }
-}
-
 #[cfg(test)]
 pub(crate) mod tests {
     use super::Filter;
@@ -168,10 +160,14 @@ pub(crate) mod tests {
         let filter = Filter {
             combinator: super::FilterCombinator::BASE,
             column: "test_column".to_string(),
-            expression: super::FilterExpression::Gte("310".to_string())
+            expression: super::FilterExpression::Gte("310".to_string()),
+            table: None,
         };
 
         let s = serde_json::to_string(&filter).unwrap();
-        println!("{s}")
+        assert_eq!(
+            s,
+            r#"{"combinator":"BASE","column":"test_column","expression":{"Gte":"310"}}"#
+        );
     }
 }
@@ -7,8 +7,33 @@ use super::data::table::TableSearchOpts;
 
 pub mod filter;
 
+/// An expression selected or grouped by in a [`BasableQuery`].
+#[derive(Clone)]
+pub enum QueryExpr {
+    /// A column, whose name is always quoted.
+    Column(String),
+
+    /// SQL written by Basable itself, such as `COUNT(*)` or the expression of a graph, which is
+    /// used as it is. Names in it must be quoted already, and nothing else in it may come from
+    /// a request.
+    Sql(String),
+}
+
+impl From<String> for QueryExpr {
+    fn from(column: String) -> Self {
+        QueryExpr::Column(column)
+    }
+}
+
+impl From<&str> for QueryExpr {
+    fn from(column: &str) -> Self {
+        QueryExpr::Column(column.to_string())
+    }
+}
+
+#[derive(Clone)]
 pub enum QueryCommand {
-    SelectData(Option<Vec<String>>),
+    SelectData(Option<Vec<QueryExpr>>),
 }
 
 impl Default for QueryCommand {
@@ -17,10 +42,22 @@ impl Default for QueryCommand {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub enum QueryOrder {
     ASC(String),
     DESC(String),
+
+    /// Descending, with nulls after every other value whatever the database.
+    DescNullsLast(String),
+}
+
+impl QueryOrder {
+    /// The column rows are ordered by.
+    pub fn column(&self) -> &str {
+        match self {
+            QueryOrder::ASC(col) | QueryOrder::DESC(col) | QueryOrder::DescNullsLast(col) => col,
+        }
+    }
 }
 
 impl Display for QueryOrder {
@@ -28,21 +65,25 @@ impl Display for QueryOrder {
         let order = match self {
             QueryOrder::ASC(col) => format!("`{col}` ASC"),
             QueryOrder::DESC(col) => format!("`{col}` DESC"),
+            QueryOrder::DescNullsLast(col) => format!("`{col}` DESC NULLS LAST"),
         };
 
         write!(f, "{order}")
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct BasableQuery {
     pub table: String,
+
+    /// Alias of `table`, for queries joining other tables.
+    pub alias: Option<String>,
     pub command: QueryCommand,
     pub filters: FilterChain,
     pub row_count: Option<usize>,
     pub offset: Option<usize>,
     pub order_by: Option<QueryOrder>,
-    pub group_by: Option<Vec<String>>,
+    pub group_by: Option<Vec<QueryExpr>>,
     pub left_join: Option<String>,
     pub having: FilterChain,
     pub search_opts: Option<TableSearchOpts>
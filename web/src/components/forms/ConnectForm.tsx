@@ -174,7 +174,7 @@ export const ConnectForm = () => {
                 </MenuItem>
                 <MenuItem value="mongo">MongoDB</MenuItem>
                 <MenuItem value="mysql">MySQL</MenuItem>
-                <MenuItem value="postgress">PostgreSQL</MenuItem>
+                <MenuItem value="postgres">PostgreSQL</MenuItem>
               </Select>
             </div>
           </div>